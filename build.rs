@@ -5,8 +5,23 @@ fn main() {
     {
         println!("cargo:rerun-if-changed=res/app.rc");
         println!("cargo:rerun-if-changed=res/cards.png");
+        println!("cargo:rerun-if-changed=res/cards.json");
         println!("cargo:rerun-if-changed=res/app.manifest");
         println!("cargo:rerun-if-changed=res/app.ico");
+        println!("cargo:rerun-if-changed=res/sounds");
         embed_resource::compile("res/app.rc", embed_resource::NONE);
     }
+
+    // Short commit hash for the About box; left unset when building outside a git checkout.
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    let hash = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if let Some(hash) = hash.filter(|hash| !hash.is_empty()) {
+        println!("cargo:rustc-env=SOLITAIRE_GIT_HASH={hash}");
+    }
 }
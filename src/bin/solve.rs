@@ -0,0 +1,154 @@
+//! Command-line solver for triaging "is this deal winnable?" reports without the GUI.
+//!
+//! Reads 52 card tokens (`AS`, `TD`, `QH`, ...) from a file, or from stdin when the file is
+//! `-` or left out. They are taken in the order `solve_deck` expects: the seven tableau
+//! piles from left to right, each from the bottom card up, then the stock in draw order.
+//! `--path` follows the verdict with the winning line in the game's move-log notation
+//! (`S draw`, `W→F2`, `T3:2→T6`, `T4 flip`), stock clicks and flips included.
+//!
+//! `--sample N` reads no deck; it deals `N` random games instead and reports how many were
+//! proven winnable, spending at most the budget on the whole batch.
+//!
+//! ```text
+//! mdsol-solve [--draw 1|2|3] [--any-to-empty] [--budget-secs N] [--path] [FILE]
+//! mdsol-solve [--draw 1|2|3] [--any-to-empty] [--budget-secs N] --sample N
+//! ```
+
+// The game's own modules, shared with the GUI build; most of the engine goes unused here.
+#[allow(dead_code)]
+#[path = "../engine.rs"]
+mod engine;
+#[allow(dead_code)]
+#[path = "../solver.rs"]
+mod solver;
+
+use std::io::Read;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::engine::{DrawMode, GameState};
+use crate::solver::{
+    estimate_winnable_fraction, parse_deck, solve_deck_with_path, solve_deck_with_stats,
+};
+
+const DEFAULT_BUDGET_SECS: u64 = 30;
+
+struct Args {
+    draw_size: u8,
+    any_to_empty: bool,
+    budget: Duration,
+    path: bool,
+    sample: Option<usize>,
+    file: Option<String>,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args {
+        draw_size: 1,
+        any_to_empty: false,
+        budget: Duration::from_secs(DEFAULT_BUDGET_SECS),
+        path: false,
+        sample: None,
+        file: None,
+    };
+    let mut rest = std::env::args().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--draw" => {
+                args.draw_size = match rest.next().as_deref() {
+                    Some("1") => 1,
+                    Some("2") => 2,
+                    Some("3") => 3,
+                    other => bail!("--draw takes 1, 2 or 3, not {other:?}"),
+                };
+            }
+            "--any-to-empty" => args.any_to_empty = true,
+            "--budget-secs" => {
+                let secs = rest
+                    .next()
+                    .ok_or_else(|| anyhow!("--budget-secs needs a value"))?;
+                let secs = secs
+                    .parse()
+                    .with_context(|| format!("bad --budget-secs {secs:?}"))?;
+                args.budget = Duration::from_secs(secs);
+            }
+            "--path" => args.path = true,
+            "--sample" => {
+                let count = rest
+                    .next()
+                    .ok_or_else(|| anyhow!("--sample needs a value"))?;
+                let count = count
+                    .parse()
+                    .with_context(|| format!("bad --sample {count:?}"))?;
+                args.sample = Some(count);
+            }
+            "-" => args.file = None,
+            flag if flag.starts_with("--") => bail!("unknown option {flag}"),
+            file => args.file = Some(file.to_string()),
+        }
+    }
+    Ok(args)
+}
+
+fn main() -> Result<()> {
+    let args = parse_args()?;
+    let draw_mode = DrawMode::from_count(args.draw_size).expect("--draw is 1, 2 or 3");
+    if let Some(samples) = args.sample {
+        let estimate =
+            estimate_winnable_fraction(draw_mode, args.any_to_empty, samples, args.budget)?;
+        println!(
+            "{} of {} deals winnable ({:.1}%), {} timed out",
+            estimate.winnable,
+            estimate.sampled,
+            estimate.fraction() * 100.0,
+            estimate.timeouts,
+        );
+        return Ok(());
+    }
+    let text = match &args.file {
+        Some(file) => {
+            std::fs::read_to_string(file).with_context(|| format!("cannot read {file}"))?
+        }
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            text
+        }
+    };
+    let tokens: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .collect();
+    let deck = parse_deck(&tokens)
+        .ok_or_else(|| anyhow!("expected 52 cards such as AS or TD, found {}", tokens.len()))?;
+    let mut seen = [false; 52];
+    for (&card, token) in deck.iter().zip(&tokens) {
+        if std::mem::replace(&mut seen[card as usize], true) {
+            bail!("{token} appears more than once");
+        }
+    }
+
+    if args.path {
+        let (result, path) =
+            solve_deck_with_path(&deck, args.draw_size, args.any_to_empty, args.budget);
+        println!("{result:?}");
+        if let Some(path) = path {
+            let won = GameState::from_solver_deck(&deck, draw_mode, args.any_to_empty)
+                .and_then(|game| game.play_solver_path(&path))
+                .map(|(game, _)| game)
+                .ok_or_else(|| anyhow!("the winning line does not play out on the deal"))?;
+            for (index, mv) in won.move_log.iter().enumerate() {
+                println!("{}. {mv}", index + 1);
+            }
+        }
+    } else {
+        let stats = solve_deck_with_stats(&deck, args.draw_size, args.any_to_empty, args.budget);
+        println!("{:?}", stats.result);
+        println!(
+            "{} positions searched, {} distinct, in {:.2?}",
+            stats.nodes, stats.tt_size, stats.elapsed
+        );
+    }
+    Ok(())
+}
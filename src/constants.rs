@@ -3,34 +3,126 @@
 
 pub const COMPANY_NAME: &str = "0x4D44 Software";
 pub const PRODUCT_NAME: &str = "Solitaire";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Commit the binary was built from, when `build.rs` could ask git.
+pub const GIT_HASH: Option<&str> = option_env!("SOLITAIRE_GIT_HASH");
 
 // Resource identifiers
 pub const IDR_MAINMENU: u16 = 101;
 pub const IDR_ACCEL: u16 = 201;
 pub const IDB_CARDS: u16 = 301;
+pub const IDB_CARDS_MAP: u16 = 302;
 pub const IDD_ABOUT: u16 = 401;
+pub const IDD_DEAL_SEED: u16 = 402;
+pub const IDD_STATS: u16 = 403;
+pub const IDD_OPTIONS: u16 = 404;
+pub const IDD_WIN: u16 = 405;
 #[allow(dead_code)]
 pub const IDI_APPICON: u16 = 501;
+pub const IDW_TABLEAU: u16 = 601;
+pub const IDW_FOUNDATION: u16 = 602;
+pub const IDW_STOCK: u16 = 603;
+pub const IDW_INVALID: u16 = 604;
+pub const IDW_VICTORY: u16 = 605;
 
 // Command identifiers (must match MENU/ACCEL definitions)
 pub const IDM_FILE_NEW: u16 = 40001;
 pub const IDM_FILE_DEALAGAIN: u16 = 40002;
+pub const IDM_FILE_SAVE: u16 = 40005;
+pub const IDM_FILE_LOAD: u16 = 40006;
+pub const IDM_FILE_EXPORT_MOVES: u16 = 40007;
+pub const IDM_FILE_REPLAY: u16 = 40008;
+pub const IDM_FILE_OPTIONS: u16 = 40003;
 pub const IDM_FILE_EXIT: u16 = 40004;
 pub const IDM_EDIT_UNDO: u16 = 40010;
 pub const IDM_EDIT_REDO: u16 = 40011;
+pub const IDM_EDIT_UNDO_ALL: u16 = 40012;
+pub const IDM_EDIT_REDO_ALL: u16 = 40013;
 pub const IDM_GAME_DRAW1: u16 = 40020;
 pub const IDM_GAME_DRAW3: u16 = 40021;
+pub const IDM_GAME_NEW_SOLVABLE: u16 = 40029;
+pub const IDM_GAME_DEAL_BY_SEED: u16 = 40033;
+pub const IDM_GAME_RESTART: u16 = 40034;
+pub const IDM_GAME_ANIMATE_DEAL: u16 = 40035;
+pub const IDM_GAME_ANIMATE_FLIPS: u16 = 40036;
+pub const IDM_GAME_SOUND: u16 = 40037;
+pub const IDM_GAME_FELT_GREEN: u16 = 40038;
+pub const IDM_GAME_FELT_BLUE: u16 = 40039;
+pub const IDM_GAME_FELT_BURGUNDY: u16 = 40040;
+pub const IDM_GAME_FELT_CHARCOAL: u16 = 40041;
+pub const IDM_GAME_BACK_CLASSIC: u16 = 40042;
+pub const IDM_GAME_BACK_DIAMOND: u16 = 40043;
+pub const IDM_GAME_BACK_LATTICE: u16 = 40044;
+pub const IDM_GAME_BACK_PLAIN: u16 = 40045;
+pub const IDM_GAME_HIGHLIGHT_MOVES: u16 = 40046;
+pub const IDM_GAME_PASSES_UNLIMITED: u16 = 40047;
+pub const IDM_GAME_PASSES_ONE: u16 = 40048;
+pub const IDM_GAME_PASSES_THREE: u16 = 40049;
+pub const IDM_GAME_CORNER_INDICES: u16 = 40050;
+pub const IDM_GAME_PEEK: u16 = 40051;
+pub const IDM_GAME_LEFT_HANDED: u16 = 40052;
+pub const IDM_GAME_AUTO_FLIP: u16 = 40053;
+pub const IDM_GAME_ANY_TO_EMPTY: u16 = 40054;
+pub const IDM_GAME_OUTLINES_STANDARD: u16 = 40055;
+pub const IDM_GAME_OUTLINES_HIGH_CONTRAST: u16 = 40056;
+pub const IDM_GAME_ANNOUNCE: u16 = 40057;
+pub const IDM_GAME_CARD_SHADOWS: u16 = 40058;
+pub const IDM_GAME_REWIND_WINNABLE: u16 = 40059;
+pub const IDM_GAME_REWIND_ASSIST: u16 = 40060;
+pub const IDM_GAME_SIZE_SMALL: u16 = 40061;
+pub const IDM_GAME_SIZE_MEDIUM: u16 = 40062;
+pub const IDM_GAME_SIZE_LARGE: u16 = 40063;
+pub const IDM_GAME_DUMP_BOARD: u16 = 40064;
+pub const IDM_GAME_PAUSE: u16 = 40065;
+pub const IDM_GAME_RESHUFFLE_ASSIST: u16 = 40066;
+pub const IDM_GAME_STOCK_BADGE: u16 = 40067;
+pub const IDM_GAME_DRAW2: u16 = 40068;
+pub const IDM_GAME_PHYSICS_CALM: u16 = 40069;
+pub const IDM_GAME_PHYSICS_CLASSIC: u16 = 40070;
+pub const IDM_GAME_PHYSICS_CHAOTIC: u16 = 40071;
+pub const IDM_GAME_VICTORY_ANIMATION: u16 = 40072;
+pub const IDM_GAME_STOCK_PREVIEW: u16 = 40073;
+pub const IDM_GAME_COPY_SEED: u16 = 40074;
+pub const IDM_GAME_AUTO_DEAL: u16 = 40075;
+pub const IDM_GAME_COLLECT_LOW: u16 = 40076;
+pub const IDM_GAME_HINT: u16 = 40022;
+pub const IDM_GAME_AUTOPLAY: u16 = 40023;
+pub const IDM_GAME_AUTOCOMPLETE: u16 = 40024;
 pub const IDM_GAME_VICTORY: u16 = 40025;
 pub const IDM_GAME_CANCEL_VICTORY: u16 = 40026;
 #[allow(dead_code)]
 pub const IDM_GAME_VICTORY_CLASSIC: u16 = 40027;
 #[allow(dead_code)]
 pub const IDM_GAME_VICTORY_MODERN: u16 = 40028;
+pub const IDM_GAME_SCORING_STANDARD: u16 = 40030;
+pub const IDM_GAME_SCORING_VEGAS: u16 = 40031;
+pub const IDM_GAME_SCORING_VEGAS_CUMULATIVE: u16 = 40032;
+pub const IDM_VIEW_ALWAYS_ON_TOP: u16 = 40080;
+pub const IDM_VIEW_COMPACT: u16 = 40081;
+pub const IDM_VIEW_SCORE_OVERLAY: u16 = 40082;
 pub const IDM_HELP_ABOUT: u16 = 40100;
+pub const IDM_HELP_STATISTICS: u16 = 40101;
 
 // Registry paths
 #[allow(dead_code)]
 pub const REGISTRY_BASE_KEY: &str = r"Software\0x4D44 Software\Solitaire";
 
+// Dialog control identifiers
+pub const IDC_SEED_EDIT: u16 = 1101;
+pub const IDC_STATS_TEXT: u16 = 1102;
+pub const IDC_OPT_DRAW: u16 = 1103;
+pub const IDC_OPT_SCORING: u16 = 1104;
+pub const IDC_OPT_PASSES: u16 = 1105;
+pub const IDC_OPT_FELT: u16 = 1106;
+pub const IDC_OPT_BACK: u16 = 1107;
+pub const IDC_OPT_SOUND: u16 = 1108;
+pub const IDC_OPT_ANIMATE_DEAL: u16 = 1109;
+pub const IDC_OPT_ANIMATE_FLIPS: u16 = 1110;
+pub const IDC_OPT_HIGHLIGHT: u16 = 1111;
+pub const IDC_WIN_NEW: u16 = 1112;
+pub const IDC_WIN_REPLAY: u16 = 1113;
+pub const IDC_OPT_SHOW_SEED: u16 = 1114;
+pub const IDC_OPT_CONFIRM_DISCARD: u16 = 1115;
+
 // Status bar identifiers
 pub const STATUS_BAR_ID: u32 = 1001;
@@ -1,11 +1,18 @@
 //! Core Solitaire game engine scaffolding.
-//! Implements deck construction, shuffling via BCrypt RNG, and a fresh deal.
+//! Implements deck construction, seeded shuffling, and a fresh deal. Seeds come from
+//! BCrypt on Windows and a std-based fallback elsewhere so the rules can be tested anywhere.
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt::{self, Write as _};
+use std::path::Path;
 use std::time::Duration;
 
-use crate::solver::{solve_deck, SolveResult};
+use crate::solver::{self, solve_deck, solve_position_with_path, suggest_move, SolveResult};
+#[cfg(windows)]
 use windows::Win32::Foundation::STATUS_SUCCESS;
+#[cfg(windows)]
 use windows::Win32::Security::Cryptography::{
     BCryptGenRandom, BCRYPT_ALG_HANDLE, BCRYPT_USE_SYSTEM_PREFERRED_RNG,
 };
@@ -13,7 +20,8 @@ use windows::Win32::Security::Cryptography::{
 const FOUNDATION_PILES: usize = 4;
 const TABLEAU_PILES: usize = 7;
 const DECK_SIZE: usize = 52;
-const SOLVER_TIME_BUDGET_MS: u64 = 120;
+pub(crate) const SOLVER_TIME_BUDGET_MS: u64 = 120;
+const HINT_TIME_BUDGET_MS: u64 = 200;
 const SUITS: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
 const RANKS: [Rank; 13] = [
     Rank::Ace,
@@ -31,7 +39,7 @@ const RANKS: [Rank; 13] = [
     Rank::King,
 ];
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Suit {
     Spades,
     Hearts,
@@ -63,7 +71,7 @@ impl Suit {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Rank {
     Ace = 1,
     Two,
@@ -86,7 +94,7 @@ impl Rank {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Card {
     pub suit: Suit,
     pub rank: Rank,
@@ -106,11 +114,213 @@ impl Card {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Pile {
     pub cards: Vec<Card>,
 }
 
+/// A single reversible change to the game. Stock moves record how many cards they shifted
+/// so [`GameState::undo`] can put exactly those back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Move {
+    StockDraw {
+        count: usize,
+    },
+    StockRecycle {
+        count: usize,
+    },
+    WasteToFoundation {
+        foundation: usize,
+    },
+    WasteToTableau {
+        column: usize,
+    },
+    TableauToFoundation {
+        column: usize,
+        foundation: usize,
+    },
+    TableauToTableau {
+        from: usize,
+        count: usize,
+        to: usize,
+    },
+    FoundationToTableau {
+        foundation: usize,
+        column: usize,
+    },
+    /// Turns up a face-down tableau top. Scores, but is not counted as a move.
+    FlipTableauTop {
+        column: usize,
+    },
+    /// Forgiving variant: gathers the stock and the waste, shuffles them face down into a
+    /// new stock with `seed`, and starts the passes over. `waste` and `passes` record what
+    /// was there before, so the shuffle can be taken back.
+    Reshuffle {
+        seed: u64,
+        waste: usize,
+        passes: u32,
+    },
+}
+
+/// Move-log notation with piles numbered from one: `S draw`, `W→F2`, `T3:2→T6` for two
+/// cards from the third column to the sixth, `T4 flip`, `S reshuffle 0x2A`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Move::StockDraw { .. } => write!(f, "S draw"),
+            Move::StockRecycle { .. } => write!(f, "S recycle"),
+            Move::WasteToFoundation { foundation } => write!(f, "W→F{}", foundation + 1),
+            Move::WasteToTableau { column } => write!(f, "W→T{}", column + 1),
+            Move::TableauToFoundation { column, foundation } => {
+                write!(f, "T{}→F{}", column + 1, foundation + 1)
+            }
+            Move::TableauToTableau { from, count, to } => {
+                write!(f, "T{}:{count}→T{}", from + 1, to + 1)
+            }
+            Move::FoundationToTableau { foundation, column } => {
+                write!(f, "F{}→T{}", foundation + 1, column + 1)
+            }
+            Move::FlipTableauTop { column } => write!(f, "T{} flip", column + 1),
+            Move::Reshuffle { seed, .. } => write!(f, "S reshuffle 0x{seed:X}"),
+        }
+    }
+}
+
+/// Reads one move written by the [`Move`] `Display` impl. Stock moves come back with a zero
+/// count, and reshuffles without what they gathered; [`MoveLog::parse`] fills both in from
+/// the deal.
+fn parse_move(text: &str) -> Result<Move> {
+    let pile = |text: &str, prefix: char| -> Option<usize> {
+        let number: usize = text.strip_prefix(prefix)?.parse().ok()?;
+        number.checked_sub(1)
+    };
+    let parsed = match text {
+        "S draw" => Some(Move::StockDraw { count: 0 }),
+        "S recycle" => Some(Move::StockRecycle { count: 0 }),
+        _ if text.starts_with("S reshuffle ") => {
+            parse_seed(&text["S reshuffle ".len()..]).map(|seed| Move::Reshuffle {
+                seed,
+                waste: 0,
+                passes: 0,
+            })
+        }
+        _ => match text.split_once('→') {
+            Some(("W", to)) => pile(to, 'F')
+                .map(|foundation| Move::WasteToFoundation { foundation })
+                .or_else(|| pile(to, 'T').map(|column| Move::WasteToTableau { column })),
+            Some((from, to)) if from.starts_with('F') => pile(from, 'F')
+                .zip(pile(to, 'T'))
+                .map(|(foundation, column)| Move::FoundationToTableau { foundation, column }),
+            Some((from, to)) => match from.split_once(':') {
+                Some((from, count)) => pile(from, 'T')
+                    .zip(count.parse().ok())
+                    .zip(pile(to, 'T'))
+                    .map(|((from, count), to)| Move::TableauToTableau { from, count, to }),
+                None => pile(from, 'T')
+                    .zip(pile(to, 'F'))
+                    .map(|(column, foundation)| Move::TableauToFoundation { column, foundation }),
+            },
+            None => text
+                .strip_suffix(" flip")
+                .and_then(|column| pile(column, 'T'))
+                .map(|column| Move::FlipTableauTop { column }),
+        },
+    };
+    parsed.ok_or_else(|| anyhow!("unrecognised move {text:?}"))
+}
+
+/// A game read back from [`GameState::export_move_log`] text. Every move has been checked
+/// against the deal, so each can go straight to [`GameState::apply`] in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveLog {
+    pub seed: u64,
+    pub draw_mode: DrawMode,
+    pub scoring_mode: ScoringMode,
+    pub pass_limit: Option<u32>,
+    pub allow_any_to_empty: bool,
+    pub moves: Vec<Move>,
+}
+
+impl MoveLog {
+    pub fn parse(text: &str) -> Result<MoveLog> {
+        let mut seed = None;
+        let mut draw_mode = DrawMode::DrawOne;
+        let mut scoring_mode = ScoringMode::Standard;
+        let mut pass_limit = None;
+        let mut allow_any_to_empty = false;
+        let mut steps = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(value) = line.strip_prefix("Seed:") {
+                seed = Some(parse_seed(value).ok_or_else(|| anyhow!("bad seed {value:?}"))?);
+            } else if let Some(value) = line.strip_prefix("Draw:") {
+                draw_mode = value
+                    .trim()
+                    .parse()
+                    .ok()
+                    .and_then(DrawMode::from_count)
+                    .ok_or_else(|| anyhow!("bad draw count {:?}", value.trim()))?;
+            } else if let Some(value) = line.strip_prefix("Scoring:") {
+                scoring_mode = match value.trim() {
+                    "Standard" => ScoringMode::Standard,
+                    "Vegas" => ScoringMode::Vegas,
+                    other => return Err(anyhow!("bad scoring mode {other:?}")),
+                };
+            } else if let Some(value) = line.strip_prefix("Passes:") {
+                let limit = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("bad pass limit {value:?}"))?;
+                pass_limit = Some(limit);
+            } else if let Some(value) = line.strip_prefix("Empty columns:") {
+                allow_any_to_empty = match value.trim() {
+                    "kings" => false,
+                    "any card" => true,
+                    other => return Err(anyhow!("bad empty column rule {other:?}")),
+                };
+            } else {
+                let notation = line.split_once(". ").map_or(line, |(_, rest)| rest);
+                steps.push(parse_move(notation)?);
+            }
+        }
+        let mut log = MoveLog {
+            seed: seed.ok_or_else(|| anyhow!("move log has no seed"))?,
+            draw_mode,
+            scoring_mode,
+            pass_limit,
+            allow_any_to_empty,
+            moves: Vec::new(),
+        };
+
+        // Play the log through once to learn how many cards each stock click moved.
+        let mut game = log.deal()?;
+        let mut moves = Vec::with_capacity(steps.len());
+        for (index, step) in steps.into_iter().enumerate() {
+            let mv = match (step, game.stock_move()) {
+                (Move::StockDraw { .. }, Some(mv @ Move::StockDraw { .. }))
+                | (Move::StockRecycle { .. }, Some(mv @ Move::StockRecycle { .. })) => mv,
+                (Move::Reshuffle { seed, .. }, _) => game.reshuffle_move(seed),
+                _ => step,
+            };
+            game.apply(mv)
+                .with_context(|| format!("move {} ({step}) does not fit the deal", index + 1))?;
+            moves.push(mv);
+        }
+        log.moves = moves;
+        Ok(log)
+    }
+
+    /// A fresh game dealt from the log's seed under the rules it was played with, ready for
+    /// its moves.
+    pub fn deal(&self) -> Result<GameState> {
+        let mut game = GameState::new();
+        game.set_scoring_mode(self.scoring_mode, false);
+        game.pass_limit = self.pass_limit;
+        game.allow_any_to_empty = self.allow_any_to_empty;
+        game.deal_new_game_with_seed(self.draw_mode, self.seed)?;
+        Ok(game)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StockAction {
     Drawn(usize),
@@ -118,15 +328,75 @@ pub enum StockAction {
     NoOp,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Identifies a pile on the board independently of how it is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PileRef {
+    Stock,
+    Waste,
+    Foundation(usize),
+    Tableau(usize),
+}
+
+/// A suggested move: `cards` cards leave `from` for `to`. Clicking the stock is expressed as
+/// `Stock` -> `Waste`; flipping a face-down tableau top uses the same column for both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    pub from: PileRef,
+    pub to: PileRef,
+    pub cards: usize,
+}
+
+// The variant names are what saves and settings store, so they keep their prefix.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum DrawMode {
     #[default]
     DrawOne,
-    #[allow(dead_code)]
+    DrawTwo,
     DrawThree,
 }
 
-#[derive(Debug, Clone)]
+impl DrawMode {
+    /// Cards turned over per stock click.
+    pub const fn count(self) -> u8 {
+        match self {
+            DrawMode::DrawOne => 1,
+            DrawMode::DrawTwo => 2,
+            DrawMode::DrawThree => 3,
+        }
+    }
+
+    pub fn from_count(count: u8) -> Option<DrawMode> {
+        match count {
+            1 => Some(DrawMode::DrawOne),
+            2 => Some(DrawMode::DrawTwo),
+            3 => Some(DrawMode::DrawThree),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScoringMode {
+    #[default]
+    Standard,
+    /// Each deal costs $52 and every card sent to a foundation pays $5.
+    Vegas,
+}
+
+const VEGAS_DEAL_COST: i32 = 52;
+const VEGAS_FOUNDATION_PAYOUT: i32 = 5;
+const STANDARD_FOUNDATION_POINTS: i32 = 10;
+const STANDARD_REVEAL_POINTS: i32 = 5;
+/// Charged each time the waste is turned back over, i.e. for every pass after the first.
+const STANDARD_RECYCLE_PENALTY: i32 = 2;
+const STANDARD_FOUNDATION_RETURN_PENALTY: i32 = 15;
+/// Standard wins earn `TIME_BONUS_NUMERATOR / seconds`, but only for games of at least
+/// `TIME_BONUS_MIN_SECS`, matching the classic Windows game.
+const TIME_BONUS_NUMERATOR: u64 = 700_000;
+const TIME_BONUS_MIN_SECS: u64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GameState {
     pub stock: Pile,
     pub waste: Pile,
@@ -134,8 +404,38 @@ pub struct GameState {
     pub tableaus: [Pile; TABLEAU_PILES],
     pub draw_mode: DrawMode,
     pub score: i32,
+    /// Net moves: undoing one restores the earlier snapshot, count included, and redoing it
+    /// brings the count back. A flip the player makes counts; an automatic reveal does not.
     pub moves: u32,
     pub rng_seed: u64,
+    #[serde(default)]
+    pub scoring_mode: ScoringMode,
+    /// Vegas only: carry the balance over into the next deal instead of starting fresh.
+    #[serde(default)]
+    pub vegas_cumulative: bool,
+    /// Score as it stood right after the current deal, which [`GameState::restart`] returns to.
+    #[serde(default)]
+    pub deal_start_score: i32,
+    /// How many times the waste has been turned back into the stock since the deal.
+    #[serde(default)]
+    pub passes: u32,
+    /// Maximum number of recycles per deal; `None` allows as many as the player likes.
+    #[serde(default)]
+    pub pass_limit: Option<u32>,
+    /// Leave a card uncovered by a move face down until the player turns it over, so the
+    /// reveal (and its points) only comes from an explicit flip.
+    #[serde(default)]
+    pub manual_flips: bool,
+    /// Easy variant: an empty tableau column takes any card or run, not just a king.
+    #[serde(default)]
+    pub allow_any_to_empty: bool,
+    /// Standard only: the end-of-game bonus already folded into `score`, so it is paid once.
+    #[serde(default)]
+    pub time_bonus: i32,
+    /// Every move applied since the deal, oldest first; [`GameState::undo`] drops the last.
+    /// Restoring an earlier snapshot brings its shorter log with it.
+    #[serde(default)]
+    pub move_log: Vec<Move>,
 }
 
 impl GameState {
@@ -149,12 +449,107 @@ impl GameState {
             score: 0,
             moves: 0,
             rng_seed: 0,
+            scoring_mode: ScoringMode::default(),
+            vegas_cumulative: false,
+            deal_start_score: 0,
+            passes: 0,
+            pass_limit: None,
+            manual_flips: false,
+            allow_any_to_empty: false,
+            time_bonus: 0,
+            move_log: Vec::new(),
+        }
+    }
+
+    /// Recycles still allowed this deal, or `None` when the stock may be recycled freely.
+    pub fn passes_remaining(&self) -> Option<u32> {
+        self.pass_limit
+            .map(|limit| limit.saturating_sub(self.passes))
+    }
+
+    fn can_recycle(&self) -> bool {
+        self.passes_remaining() != Some(0)
+    }
+
+    /// Switches scoring rules and clears the running balance; takes effect from the next deal.
+    pub fn set_scoring_mode(&mut self, mode: ScoringMode, cumulative: bool) {
+        self.scoring_mode = mode;
+        self.vegas_cumulative = cumulative;
+        self.score = 0;
+    }
+
+    /// Writes the game as JSON so it can be resumed later with [`GameState::load_from_path`].
+    pub fn save_to_path(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("writing {}", path.display()))
+    }
+
+    pub fn load_from_path(path: &Path) -> Result<GameState> {
+        let json = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let game: GameState =
+            serde_json::from_slice(&json).with_context(|| format!("parsing {}", path.display()))?;
+        game.validate()
+            .with_context(|| format!("checking {}", path.display()))?;
+        Ok(game)
+    }
+
+    /// Checks what every reachable position keeps true, so a damaged or hand-edited save is
+    /// refused rather than played: each of the 52 cards exactly once, foundations built up
+    /// by suit from the ace, only legal runs face up on the tableau, and sprite indices that
+    /// match their cards.
+    pub fn validate(&self) -> Result<()> {
+        let piles = [&self.stock, &self.waste]
+            .into_iter()
+            .chain(&self.foundations)
+            .chain(&self.tableaus);
+        let mut seen = [false; DECK_SIZE];
+        let mut count = 0;
+        for card in piles.flat_map(|pile| &pile.cards) {
+            let index = Card::new(card.suit, card.rank).sprite_index;
+            if card.sprite_index != index {
+                return Err(anyhow!(
+                    "{:?} of {:?} has sprite index {}, expected {index}",
+                    card.rank,
+                    card.suit,
+                    card.sprite_index
+                ));
+            }
+            if std::mem::replace(&mut seen[usize::from(index)], true) {
+                return Err(anyhow!("{:?} of {:?} appears twice", card.rank, card.suit));
+            }
+            count += 1;
+        }
+        if count != DECK_SIZE {
+            return Err(anyhow!("{count} cards in play, expected {DECK_SIZE}"));
+        }
+        for (index, foundation) in self.foundations.iter().enumerate() {
+            let mut top = None;
+            for &card in &foundation.cards {
+                if !can_place_on_foundation(card, top) {
+                    return Err(anyhow!(
+                        "foundation {} is not an ascending run of one suit from the ace",
+                        index + 1
+                    ));
+                }
+                top = Some(card);
+            }
         }
+        for (index, tableau) in self.tableaus.iter().enumerate() {
+            if let Some(start) = tableau.cards.iter().position(|card| card.face_up) {
+                if !is_valid_tableau_run(&tableau.cards[start..]) {
+                    return Err(anyhow!(
+                        "column {} has face-up cards that are not a legal run",
+                        index + 1
+                    ));
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn deal_new_game(&mut self, draw_mode: DrawMode) -> Result<()> {
         let seed = random_seed()?;
-        self.deal_with_seed(draw_mode, seed)
+        self.deal_new_game_with_seed(draw_mode, seed)
     }
 
     pub fn deal_again(&mut self) -> Result<()> {
@@ -163,10 +558,24 @@ impl GameState {
         } else {
             self.rng_seed
         };
-        self.deal_with_seed(self.draw_mode, seed)
+        self.deal_new_game_with_seed(self.draw_mode, seed)
     }
 
-    #[allow(dead_code)]
+    /// Puts the current deal back to its opening layout, score and move count. Unlike
+    /// [`GameState::deal_again`] this never charges cumulative Vegas for another deal.
+    pub fn restart(&mut self) -> Result<()> {
+        if self.rng_seed == 0 {
+            return Err(anyhow!("no deal to restart"));
+        }
+        let start = self.deal_start_score;
+        self.deal_new_game_with_seed(self.draw_mode, self.rng_seed)?;
+        self.score = start;
+        self.deal_start_score = start;
+        Ok(())
+    }
+
+    /// Deals until the solver proves a layout winnable. Can take seconds, so keep it off the
+    /// UI thread.
     pub fn deal_new_solvable(&mut self, draw_mode: DrawMode, max_attempts: usize) -> Result<usize> {
         let capped = max_attempts.min(120);
         let overall_deadline = std::time::Instant::now() + Duration::from_secs(10);
@@ -194,38 +603,339 @@ impl GameState {
 
     fn is_solvable_result(&self) -> Option<bool> {
         let deck = self.to_solver_deck()?;
-        let draw = match self.draw_mode {
-            DrawMode::DrawOne => 1,
-            DrawMode::DrawThree => 3,
-        };
-        match solve_deck(&deck, draw, Duration::from_millis(SOLVER_TIME_BUDGET_MS)) {
+        let budget = Duration::from_millis(SOLVER_TIME_BUDGET_MS);
+        match solve_deck(
+            &deck,
+            self.solver_draw_size(),
+            self.allow_any_to_empty,
+            budget,
+        ) {
             SolveResult::Winnable => Some(true),
             SolveResult::Unwinnable => Some(false),
             SolveResult::Timeout => None,
         }
     }
+
+    /// A solver set up on this game's opening deal, to be stepped through a little at a
+    /// time; `None` without a seed.
+    pub fn opening_solver(&self) -> Option<solver::Solver> {
+        let deck = self.to_solver_deck()?;
+        Some(solver::Solver::from_deck(
+            &deck,
+            self.solver_draw_size(),
+            self.allow_any_to_empty,
+        ))
+    }
+
+    fn solver_draw_size(&self) -> u8 {
+        self.draw_mode.count()
+    }
+    /// Suggests the next move, preferring the first step of a line the solver proves
+    /// winnable and otherwise falling back to any legal foundation move.
+    pub fn hint(&self) -> Option<Hint> {
+        for (column, pile) in self.tableaus.iter().enumerate() {
+            if pile.cards.last().is_some_and(|card| !card.face_up) {
+                return Some(Hint {
+                    from: PileRef::Tableau(column),
+                    to: PileRef::Tableau(column),
+                    cards: 1,
+                });
+            }
+        }
+        let position = self.to_solver_position();
+        suggest_move(&position, Duration::from_millis(HINT_TIME_BUDGET_MS))
+            .and_then(|mv| self.hint_from_solver(mv))
+            .or_else(|| self.foundation_hint())
+    }
+
+    /// Finds a winning line from the current position and spells it out as single steps
+    /// (stock clicks included) that [`GameState::play_hint`] can apply in order.
+    pub fn solve_line(&self, time_budget: Duration) -> Option<Vec<Hint>> {
+        let path = solve_position_with_path(&self.to_solver_position(), time_budget)?;
+        self.play_solver_path(&path).map(|(_, line)| line)
+    }
+
+    /// Plays a solver line out on a copy of this game, clicking the stock up to each waste
+    /// card and turning up what every move uncovers. Returns the won copy, whose move log
+    /// holds the whole game, and the steps as [`Hint`]s; `None` if the line goes wrong.
+    pub(crate) fn play_solver_path(&self, path: &[solver::Move]) -> Option<(GameState, Vec<Hint>)> {
+        // Reaching a waste card can take a full pass through the stock (three for Draw 3).
+        let max_draws = 3 * (self.stock.cards.len() + self.waste.cards.len() + 1);
+        let mut game = self.clone();
+        let mut line = Vec::new();
+        for &mv in path {
+            let mut draws = 0;
+            loop {
+                game.flip_face_down_tops(&mut line);
+                let step = game.hint_from_solver(mv)?;
+                if !game.play_hint(step) {
+                    return None;
+                }
+                line.push(step);
+                if step.from != PileRef::Stock {
+                    break;
+                }
+                draws += 1;
+                if draws > max_draws {
+                    return None;
+                }
+            }
+        }
+        game.flip_face_down_tops(&mut line);
+        game.is_won().then_some((game, line))
+    }
+
+    fn flip_face_down_tops(&mut self, line: &mut Vec<Hint>) {
+        for column in 0..TABLEAU_PILES {
+            if self.flip_tableau_top(column) {
+                line.push(Hint {
+                    from: PileRef::Tableau(column),
+                    to: PileRef::Tableau(column),
+                    cards: 1,
+                });
+            }
+        }
+    }
+
+    /// Carries out a step described as a [`Hint`], turning up the card it uncovers the way
+    /// the UI does. Returns `false`, leaving the game untouched, if the step is not legal.
+    pub fn play_hint(&mut self, hint: Hint) -> bool {
+        match (hint.from, hint.to) {
+            (PileRef::Stock, PileRef::Waste) => self.stock_click() != StockAction::NoOp,
+            (PileRef::Tableau(from), PileRef::Tableau(to)) if from == to => {
+                self.flip_tableau_top(from)
+            }
+            (PileRef::Waste, PileRef::Foundation(foundation)) => {
+                self.move_waste_to_foundation(foundation)
+            }
+            (PileRef::Waste, PileRef::Tableau(column)) => self.move_waste_to_tableau(column),
+            (PileRef::Tableau(column), PileRef::Foundation(foundation)) => {
+                self.move_tableau_to_foundation(column, foundation)
+            }
+            (PileRef::Tableau(from), PileRef::Tableau(to)) => {
+                let Some(start) = self.tableau_len(from).checked_sub(hint.cards) else {
+                    return false;
+                };
+                self.move_tableau_stack(from, start, to)
+            }
+            (PileRef::Foundation(foundation), PileRef::Tableau(column)) => {
+                self.move_foundation_to_tableau(foundation, column)
+            }
+            _ => false,
+        }
+    }
+
+    fn hint_from_solver(&self, mv: solver::Move) -> Option<Hint> {
+        let waste_top = self.waste.cards.len().checked_sub(1);
+        // A spent stock that can't be turned over again has no draw to offer.
+        let draw = (!self.stock.cards.is_empty() || self.can_recycle()).then_some(Hint {
+            from: PileRef::Stock,
+            to: PileRef::Waste,
+            cards: 0,
+        });
+        match mv {
+            solver::Move::TableauToFoundation { src } => {
+                let card = *self.tableaus.get(src)?.cards.last()?;
+                Some(Hint {
+                    from: PileRef::Tableau(src),
+                    to: PileRef::Foundation(self.foundation_for(card)?),
+                    cards: 1,
+                })
+            }
+            solver::Move::TableauToTableau {
+                src,
+                start_idx,
+                dst,
+            } => Some(Hint {
+                from: PileRef::Tableau(src),
+                to: PileRef::Tableau(dst),
+                cards: self.tableau_len(src).checked_sub(start_idx)?,
+            }),
+            // The solver may plan to play a card that is still buried in the stock; the
+            // first step towards it is another click on the stock.
+            solver::Move::WasteToFoundation { idx_in_k } => {
+                if Some(idx_in_k) != waste_top {
+                    return draw;
+                }
+                let card = *self.waste.cards.last()?;
+                Some(Hint {
+                    from: PileRef::Waste,
+                    to: PileRef::Foundation(self.foundation_for(card)?),
+                    cards: 1,
+                })
+            }
+            solver::Move::WasteToTableau { idx_in_k, dst } => {
+                if Some(idx_in_k) != waste_top {
+                    return draw;
+                }
+                Some(Hint {
+                    from: PileRef::Waste,
+                    to: PileRef::Tableau(dst),
+                    cards: 1,
+                })
+            }
+            solver::Move::FoundationToTableau { suit, dst } => {
+                let foundation = self.foundations.iter().position(|pile| {
+                    pile.cards
+                        .last()
+                        .is_some_and(|card| solver_suit(card.suit) as usize == suit)
+                })?;
+                Some(Hint {
+                    from: PileRef::Foundation(foundation),
+                    to: PileRef::Tableau(dst),
+                    cards: 1,
+                })
+            }
+        }
+    }
+
+    fn foundation_hint(&self) -> Option<Hint> {
+        if let Some(card) = self.waste.cards.last().copied() {
+            if let Some(foundation) = self.foundation_for(card) {
+                return Some(Hint {
+                    from: PileRef::Waste,
+                    to: PileRef::Foundation(foundation),
+                    cards: 1,
+                });
+            }
+        }
+        for (column, pile) in self.tableaus.iter().enumerate() {
+            let card = match pile.cards.last() {
+                Some(card) if card.face_up => *card,
+                _ => continue,
+            };
+            if let Some(foundation) = self.foundation_for(card) {
+                return Some(Hint {
+                    from: PileRef::Tableau(column),
+                    to: PileRef::Foundation(foundation),
+                    cards: 1,
+                });
+            }
+        }
+        None
+    }
+
+    /// The pile `card` belongs on: the one its suit was started on, or for an ace the leftmost
+    /// empty pile. A suit with a pile never starts a second one, so every later card of
+    /// that suit routes to the same place.
+    fn foundation_for(&self, card: Card) -> Option<usize> {
+        let started = self
+            .foundations
+            .iter()
+            .position(|pile| pile.cards.first().is_some_and(|c| c.suit == card.suit));
+        match started {
+            Some(idx) => self.can_accept_foundation(idx, card).then_some(idx),
+            None => (0..FOUNDATION_PILES).find(|&idx| {
+                self.foundations[idx].cards.is_empty() && self.can_accept_foundation(idx, card)
+            }),
+        }
+    }
+
+    pub(crate) fn to_solver_position(&self) -> solver::Position {
+        let piles = std::array::from_fn(|column| {
+            let cards = &self.tableaus[column].cards;
+            let up_from = cards
+                .iter()
+                .position(|card| card.face_up)
+                .unwrap_or(cards.len());
+            (
+                cards.iter().map(|&card| solver_card(card)).collect(),
+                up_from,
+            )
+        });
+        let mut foundations = [-1i8; FOUNDATION_PILES];
+        for pile in &self.foundations {
+            if let Some(top) = pile.cards.last() {
+                foundations[solver_suit(top.suit) as usize] = top.rank.column() as i8;
+            }
+        }
+        solver::Position {
+            piles,
+            foundations,
+            waste: self
+                .waste
+                .cards
+                .iter()
+                .map(|&card| solver_card(card))
+                .collect(),
+            // The stock is drawn from the end of the vector.
+            stock: self
+                .stock
+                .cards
+                .iter()
+                .rev()
+                .map(|&card| solver_card(card))
+                .collect(),
+            draw: self.draw_mode.count(),
+            recycles: self.passes_remaining(),
+            any_to_empty: self.allow_any_to_empty,
+        }
+    }
+
+    /// Re-deals this game's seed and lays the opening out the way `solve_deck` expects.
     fn to_solver_deck(&self) -> Option<[u8; 52]> {
         if self.rng_seed == 0 {
             return None;
         }
+        solver_deck_for_seed(self.draw_mode, self.rng_seed)
+    }
 
-        let mut deck = create_standard_deck();
-        shuffle_deck(&mut deck, self.rng_seed);
+    /// `solve_deck` deals piles from the front of its array (pile 0 first, each bottom to
+    /// top) and treats the rest as the stock in draw order. Only valid for an untouched deal.
+    fn opening_solver_deck(&self) -> Option<[u8; 52]> {
+        let tableau = self.tableaus.iter().flat_map(|pile| pile.cards.iter());
+        // The stock is drawn from the end of the vector.
+        let stock = self.stock.cards.iter().rev();
+        let cards: Vec<u8> = tableau
+            .chain(stock)
+            .map(|&card| solver_card(card))
+            .collect();
+        cards.try_into().ok()
+    }
 
-        let mut out = [0u8; 52];
-        for (i, card) in deck.iter().enumerate() {
-            out[i] = card.sprite_index;
+    /// A fresh game laid out from a `solve_deck` array, the inverse of
+    /// [`GameState::opening_solver_deck`]. `None` if a card code is out of range. Only the
+    /// command-line solver deals this way.
+    #[cfg(any(test, feature = "solve-cli"))]
+    pub(crate) fn from_solver_deck(
+        deck: &[u8; 52],
+        draw_mode: DrawMode,
+        allow_any_to_empty: bool,
+    ) -> Option<GameState> {
+        let mut cards = deck
+            .iter()
+            .map(|&code| card_from_solver(code))
+            .collect::<Option<Vec<Card>>>()?
+            .into_iter();
+        let mut game = GameState::new();
+        game.draw_mode = draw_mode;
+        game.allow_any_to_empty = allow_any_to_empty;
+        for (column, pile) in game.tableaus.iter_mut().enumerate() {
+            pile.cards = cards.by_ref().take(column + 1).collect();
+            pile.cards.last_mut()?.face_up = true;
         }
-        Some(out)
+        // The stock is drawn from the end of the vector.
+        game.stock.cards = cards.rev().collect();
+        Some(game)
     }
 
-    fn deal_with_seed(&mut self, draw_mode: DrawMode, seed: u64) -> Result<()> {
+    /// Deals the layout produced by `seed`; the same seed always yields the same game.
+    pub fn deal_new_game_with_seed(&mut self, draw_mode: DrawMode, seed: u64) -> Result<()> {
+        let seed = effective_seed(seed);
         let mut deck = create_standard_deck();
         shuffle_deck(&mut deck, seed);
 
         self.draw_mode = draw_mode;
-        self.score = 0;
+        self.score = match self.scoring_mode {
+            ScoringMode::Standard => 0,
+            ScoringMode::Vegas if self.vegas_cumulative => self.score - VEGAS_DEAL_COST,
+            ScoringMode::Vegas => -VEGAS_DEAL_COST,
+        };
+        self.deal_start_score = self.score;
         self.moves = 0;
+        self.passes = 0;
+        self.time_bonus = 0;
+        self.move_log.clear();
         self.rng_seed = seed;
         self.waste.cards.clear();
         self.stock.cards.clear();
@@ -259,88 +969,370 @@ impl GameState {
         Ok(())
     }
     pub fn stock_click(&mut self) -> StockAction {
-        if self.stock.cards.is_empty() {
-            let recycled = self.recycle_stock();
-            if recycled > 0 {
-                StockAction::Recycled(recycled)
-            } else {
-                StockAction::NoOp
+        match self.stock_move() {
+            Some(mv @ Move::StockDraw { count }) if self.apply(mv).is_ok() => {
+                StockAction::Drawn(count)
+            }
+            Some(mv @ Move::StockRecycle { count }) if self.apply(mv).is_ok() => {
+                StockAction::Recycled(count)
             }
+            _ => StockAction::NoOp,
+        }
+    }
+
+    /// What clicking the stock would do right now, if anything.
+    fn stock_move(&self) -> Option<Move> {
+        if self.stock.cards.is_empty() {
+            let count = self.waste.cards.len();
+            (count > 0 && self.can_recycle()).then_some(Move::StockRecycle { count })
         } else {
-            let drawn = self.draw_from_stock();
-            if drawn > 0 {
-                StockAction::Drawn(drawn)
-            } else {
-                StockAction::NoOp
+            let count = self.draw_count().min(self.stock.cards.len());
+            Some(Move::StockDraw { count })
+        }
+    }
+
+    fn draw_count(&self) -> usize {
+        usize::from(self.draw_mode.count())
+    }
+
+    /// Whether the stock and waste hold enough cards for a reshuffle to change anything.
+    pub fn can_reshuffle(&self) -> bool {
+        self.stock.cards.len() + self.waste.cards.len() >= 2
+    }
+
+    /// The reshuffle of the current stock and waste that `seed` would make.
+    fn reshuffle_move(&self, seed: u64) -> Move {
+        Move::Reshuffle {
+            seed,
+            waste: self.waste.cards.len(),
+            passes: self.passes,
+        }
+    }
+
+    /// Seed for the next reshuffle, derived from the deal and how far into it the game is so
+    /// that replaying a game reshuffles the same way.
+    pub fn reshuffle_seed(&self) -> u64 {
+        let step = self.move_log.len() as u64 + 1;
+        effective_seed(self.rng_seed) ^ step.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    /// Forgiving variant: shuffles the stock and waste together into a fresh face-down stock
+    /// and resets the passes. Unlike a recycle the cards come back in a new order.
+    pub fn reshuffle_stock_waste(&mut self, seed: u64) -> bool {
+        self.apply(self.reshuffle_move(seed)).is_ok()
+    }
+
+    /// Stock clicks that would bring a playable card to the top of the waste, when only the
+    /// stock can help: nothing on the board can move and the waste top is stuck too.
+    pub fn draws_until_playable(&self) -> Option<usize> {
+        if self.has_board_move() || self.waste_top_playable() {
+            return None;
+        }
+        let mut game = self.clone();
+        let limit = self.stock.cards.len() + self.waste.cards.len() + 1;
+        for clicks in 1..=limit {
+            if game.stock_click() == StockAction::NoOp {
+                return None;
+            }
+            if game.waste_top_playable() {
+                return Some(clicks);
             }
         }
+        None
+    }
+
+    fn waste_top_playable(&self) -> bool {
+        self.waste.cards.last().is_some_and(|&card| {
+            self.foundation_for(card).is_some()
+                || (0..TABLEAU_PILES).any(|column| self.fits_on_tableau(card, column))
+        })
     }
 
+    /// Turns up a face-down top card on the player's say-so, which counts as a move.
+    /// Automatic reveals go through [`GameState::reveal_tableau_top`] and are not counted.
     pub fn flip_tableau_top(&mut self, column: usize) -> bool {
-        if let Some(pile) = self.tableaus.get_mut(column) {
-            if let Some(card) = pile.cards.last_mut() {
-                if !card.face_up {
+        let flipped = self.apply(Move::FlipTableauTop { column }).is_ok();
+        if flipped {
+            self.moves = self.moves.saturating_add(1);
+        }
+        flipped
+    }
+
+    /// Applies `mv` if it is legal in the current position, updating score and move count.
+    /// Nothing changes when an error is returned.
+    pub fn apply(&mut self, mv: Move) -> Result<()> {
+        self.check_legal(mv)?;
+        self.score += self.move_points(mv);
+        self.move_log.push(mv);
+        match mv {
+            Move::StockDraw { count } => {
+                for _ in 0..count {
+                    let mut card = self.stock.cards.pop().expect("checked stock size");
                     card.face_up = true;
-                    self.moves = self.moves.saturating_add(1);
-                    self.score += 5;
-                    return true;
+                    self.waste.cards.push(card);
+                }
+            }
+            Move::StockRecycle { count } => {
+                for _ in 0..count {
+                    let mut card = self.waste.cards.pop().expect("checked waste size");
+                    card.face_up = false;
+                    self.stock.cards.push(card);
+                }
+                self.passes += 1;
+            }
+            Move::WasteToFoundation { foundation } => {
+                let card = self.waste.cards.pop().expect("checked waste top");
+                self.foundations[foundation].cards.push(card);
+            }
+            Move::WasteToTableau { column } => {
+                let card = self.waste.cards.pop().expect("checked waste top");
+                self.tableaus[column].cards.push(card);
+            }
+            Move::TableauToFoundation { column, foundation } => {
+                let card = self.tableaus[column]
+                    .cards
+                    .pop()
+                    .expect("checked tableau top");
+                self.foundations[foundation].cards.push(card);
+            }
+            Move::TableauToTableau { from, count, to } => {
+                let start = self.tableaus[from].cards.len() - count;
+                let mut run = self.tableaus[from].cards.split_off(start);
+                self.tableaus[to].cards.append(&mut run);
+            }
+            Move::FoundationToTableau { foundation, column } => {
+                let card = self.foundations[foundation]
+                    .cards
+                    .pop()
+                    .expect("checked top");
+                self.tableaus[column].cards.push(card);
+            }
+            Move::FlipTableauTop { column } => {
+                let card = self.tableaus[column].cards.last_mut().expect("checked top");
+                card.face_up = true;
+                return Ok(());
+            }
+            Move::Reshuffle { seed, .. } => {
+                let mut cards = std::mem::take(&mut self.stock.cards);
+                cards.append(&mut self.waste.cards);
+                shuffle_deck(&mut cards, seed);
+                for card in &mut cards {
+                    card.face_up = false;
                 }
+                self.stock.cards = cards;
+                self.passes = 0;
             }
         }
-        false
+        self.moves = self.moves.saturating_add(1);
+        Ok(())
     }
 
-    pub fn move_waste_to_foundation(&mut self, foundation: usize) -> bool {
-        if foundation >= FOUNDATION_PILES {
-            return false;
+    /// Reverses `mv`, which must be the most recent move applied to this game. Any time bonus
+    /// is taken back too, since the game is no longer won.
+    pub fn undo(&mut self, mv: Move) -> Result<()> {
+        let reversed = match mv {
+            Move::StockDraw { count } => {
+                if self.waste.cards.len() < count {
+                    return Err(anyhow!("cannot undo {mv:?}: waste too small"));
+                }
+                for _ in 0..count {
+                    let mut card = self.waste.cards.pop().unwrap();
+                    card.face_up = false;
+                    self.stock.cards.push(card);
+                }
+                true
+            }
+            Move::StockRecycle { count } => {
+                if self.stock.cards.len() != count || !self.waste.cards.is_empty() {
+                    return Err(anyhow!("cannot undo {mv:?}: stock does not match"));
+                }
+                for _ in 0..count {
+                    let mut card = self.stock.cards.pop().unwrap();
+                    card.face_up = true;
+                    self.waste.cards.push(card);
+                }
+                self.passes = self.passes.saturating_sub(1);
+                true
+            }
+            Move::WasteToFoundation { foundation } => {
+                let card = self.take_top(PileRef::Foundation(foundation), mv)?;
+                self.waste.cards.push(card);
+                true
+            }
+            Move::WasteToTableau { column } => {
+                let card = self.take_top(PileRef::Tableau(column), mv)?;
+                self.waste.cards.push(card);
+                true
+            }
+            Move::TableauToFoundation { column, foundation } => {
+                if column >= TABLEAU_PILES {
+                    return Err(anyhow!("cannot undo {mv:?}: no such column"));
+                }
+                let card = self.take_top(PileRef::Foundation(foundation), mv)?;
+                self.tableaus[column].cards.push(card);
+                true
+            }
+            Move::TableauToTableau { from, count, to } => {
+                let len = self.tableau_len(to);
+                if from >= TABLEAU_PILES || count == 0 || len < count {
+                    return Err(anyhow!("cannot undo {mv:?}: run not on destination"));
+                }
+                let mut run = self.tableaus[to].cards.split_off(len - count);
+                self.tableaus[from].cards.append(&mut run);
+                true
+            }
+            Move::FoundationToTableau { foundation, column } => {
+                if foundation >= FOUNDATION_PILES {
+                    return Err(anyhow!("cannot undo {mv:?}: no such foundation"));
+                }
+                let card = self.take_top(PileRef::Tableau(column), mv)?;
+                self.foundations[foundation].cards.push(card);
+                true
+            }
+            Move::FlipTableauTop { column } => {
+                match self
+                    .tableaus
+                    .get_mut(column)
+                    .and_then(|pile| pile.cards.last_mut())
+                {
+                    Some(card) if card.face_up => card.face_up = false,
+                    _ => return Err(anyhow!("cannot undo {mv:?}: top is not face up")),
+                }
+                false
+            }
+            Move::Reshuffle {
+                seed,
+                waste,
+                passes,
+            } => {
+                let total = self.stock.cards.len();
+                if !self.waste.cards.is_empty() || total < waste {
+                    return Err(anyhow!("cannot undo {mv:?}: stock does not match"));
+                }
+                // Replay the shuffle on positions to learn where each card came from.
+                let mut order: Vec<usize> = (0..total).collect();
+                shuffle_deck(&mut order, seed);
+                let mut cards = self.stock.cards.clone();
+                for (&from, &card) in order.iter().zip(&self.stock.cards) {
+                    cards[from] = card;
+                }
+                self.waste.cards = cards.split_off(total - waste);
+                for card in &mut self.waste.cards {
+                    card.face_up = true;
+                }
+                self.stock.cards = cards;
+                self.passes = passes;
+                true
+            }
+        };
+        if reversed {
+            self.moves = self.moves.saturating_sub(1);
+        }
+        self.score -= self.move_points(mv) + std::mem::take(&mut self.time_bonus);
+        if self.move_log.last() == Some(&mv) {
+            self.move_log.pop();
         }
-        let card = match self.waste.cards.pop() {
-            Some(card) => card,
-            None => return false,
+        Ok(())
+    }
+
+    fn take_top(&mut self, pile: PileRef, mv: Move) -> Result<Card> {
+        let cards = match pile {
+            PileRef::Foundation(idx) => self.foundations.get_mut(idx).map(|p| &mut p.cards),
+            PileRef::Tableau(idx) => self.tableaus.get_mut(idx).map(|p| &mut p.cards),
+            PileRef::Stock => Some(&mut self.stock.cards),
+            PileRef::Waste => Some(&mut self.waste.cards),
+        };
+        cards
+            .and_then(|cards| cards.pop())
+            .ok_or_else(|| anyhow!("cannot undo {mv:?}: {pile:?} is empty"))
+    }
+
+    fn check_legal(&self, mv: Move) -> Result<()> {
+        let legal = match mv {
+            Move::StockDraw { count } => {
+                count > 0 && count == self.draw_count().min(self.stock.cards.len())
+            }
+            Move::StockRecycle { count } => {
+                count > 0
+                    && self.stock.cards.is_empty()
+                    && self.waste.cards.len() == count
+                    && self.can_recycle()
+            }
+            Move::WasteToFoundation { foundation } => self
+                .waste
+                .cards
+                .last()
+                .is_some_and(|&card| self.can_accept_foundation(foundation, card)),
+            Move::WasteToTableau { column } => {
+                column < TABLEAU_PILES
+                    && self
+                        .waste
+                        .cards
+                        .last()
+                        .is_some_and(|&card| self.fits_on_tableau(card, column))
+            }
+            Move::TableauToFoundation { column, foundation } => self
+                .tableaus
+                .get(column)
+                .and_then(|pile| pile.cards.last())
+                .is_some_and(|&card| card.face_up && self.can_accept_foundation(foundation, card)),
+            Move::TableauToTableau { from, count, to } => {
+                from != to
+                    && from < TABLEAU_PILES
+                    && count > 0
+                    && count <= self.tableau_len(from)
+                    && self.can_accept_tableau_stack(
+                        to,
+                        &self.tableaus[from].cards[self.tableau_len(from) - count..],
+                    )
+            }
+            Move::FoundationToTableau { foundation, column } => {
+                column < TABLEAU_PILES
+                    && self
+                        .foundations
+                        .get(foundation)
+                        .and_then(|pile| pile.cards.last())
+                        .is_some_and(|&card| self.fits_on_tableau(card, column))
+            }
+            Move::FlipTableauTop { column } => self
+                .tableaus
+                .get(column)
+                .and_then(|pile| pile.cards.last())
+                .is_some_and(|card| !card.face_up),
+            Move::Reshuffle { waste, passes, .. } => {
+                waste == self.waste.cards.len() && passes == self.passes && self.can_reshuffle()
+            }
         };
-        if self.place_on_foundation(foundation, card) {
-            true
+        if legal {
+            Ok(())
         } else {
-            self.waste.cards.push(card);
-            false
+            Err(anyhow!("illegal move: {mv:?}"))
         }
     }
 
+    pub fn move_waste_to_foundation(&mut self, foundation: usize) -> bool {
+        self.apply(Move::WasteToFoundation { foundation }).is_ok()
+    }
+
     pub fn move_waste_to_tableau(&mut self, column: usize) -> bool {
-        if column >= TABLEAU_PILES {
-            return false;
-        }
-        let card = match self.waste.cards.last() {
-            Some(card) => *card,
-            None => return false,
-        };
-        if !can_place_on_tableau(card, self.tableaus[column].cards.last().copied()) {
-            return false;
-        }
-        let card = self.waste.cards.pop().unwrap();
-        self.tableaus[column].cards.push(card);
-        self.moves = self.moves.saturating_add(1);
-        true
+        self.apply(Move::WasteToTableau { column }).is_ok()
+    }
+
+    /// Pulls a foundation's top card back onto a tableau column, giving back its points.
+    pub fn move_foundation_to_tableau(&mut self, foundation: usize, column: usize) -> bool {
+        self.apply(Move::FoundationToTableau { foundation, column })
+            .is_ok()
     }
 
     pub fn move_tableau_to_foundation(&mut self, column: usize, foundation: usize) -> bool {
-        if foundation >= FOUNDATION_PILES || column >= TABLEAU_PILES {
-            return false;
-        }
-        let card = match self.tableaus[column].cards.last().copied() {
-            Some(card) if card.face_up => card,
-            _ => return false,
-        };
-        if !self.can_accept_foundation(foundation, card) {
+        if self
+            .apply(Move::TableauToFoundation { column, foundation })
+            .is_err()
+        {
             return false;
         }
-        let card = self.tableaus[column].cards.pop().unwrap();
-        if self.place_on_foundation(foundation, card) {
-            self.reveal_tableau_top(column);
-            true
-        } else {
-            false
-        }
+        self.reveal_tableau_top(column);
+        true
     }
 
     pub fn tableau_len(&self, column: usize) -> usize {
@@ -385,68 +1377,39 @@ impl GameState {
         if !is_valid_tableau_run(stack) {
             return false;
         }
-        can_place_on_tableau(stack[0], self.tableaus[column].cards.last().copied())
+        self.fits_on_tableau(stack[0], column)
     }
 
-    pub fn place_tableau_stack(&mut self, column: usize, mut stack: Vec<Card>) -> bool {
-        if !self.can_accept_tableau_stack(column, &stack) {
-            return false;
+    /// Whether `card` may go onto `column` under this game's rules.
+    fn fits_on_tableau(&self, card: Card, column: usize) -> bool {
+        can_place_on_tableau(
+            card,
+            self.tableaus[column].cards.last().copied(),
+            self.allow_any_to_empty,
+        )
+    }
+
+    /// Moves the run starting at `start` in column `from` onto column `to`, then turns up
+    /// whatever it uncovered unless flips are manual.
+    pub fn move_tableau_stack(&mut self, from: usize, start: usize, to: usize) -> bool {
+        let count = self.tableau_len(from).saturating_sub(start);
+        if self
+            .apply(Move::TableauToTableau { from, count, to })
+            .is_err()
+        {
+            return false;
         }
-        let pile = &mut self.tableaus[column];
-        pile.cards.append(&mut stack);
-        self.moves = self.moves.saturating_add(1);
+        self.reveal_tableau_top(from);
         true
     }
 
+    /// Turns up the column's top card if it is face down; otherwise, or when
+    /// [`GameState::manual_flips`] is set, does nothing.
     pub fn reveal_tableau_top(&mut self, column: usize) {
-        if column >= TABLEAU_PILES {
+        if self.manual_flips {
             return;
         }
-        if let Some(card) = self.tableaus[column].cards.last_mut() {
-            if !card.face_up {
-                card.face_up = true;
-                self.score += 5;
-            }
-        }
-    }
-
-    fn draw_from_stock(&mut self) -> usize {
-        if self.stock.cards.is_empty() {
-            return 0;
-        }
-        let draw_count = match self.draw_mode {
-            DrawMode::DrawOne => 1,
-            DrawMode::DrawThree => 3,
-        }
-        .min(self.stock.cards.len());
-        let mut moved = 0;
-        for _ in 0..draw_count {
-            if let Some(mut card) = self.stock.cards.pop() {
-                card.face_up = true;
-                self.waste.cards.push(card);
-                moved += 1;
-            }
-        }
-        if moved > 0 {
-            self.moves = self.moves.saturating_add(1);
-        }
-        moved
-    }
-
-    fn recycle_stock(&mut self) -> usize {
-        if self.waste.cards.is_empty() {
-            return 0;
-        }
-        let mut moved = 0;
-        while let Some(mut card) = self.waste.cards.pop() {
-            card.face_up = false;
-            self.stock.cards.push(card);
-            moved += 1;
-        }
-        if moved > 0 {
-            self.moves = self.moves.saturating_add(1);
-        }
-        moved
+        let _ = self.apply(Move::FlipTableauTop { column });
     }
 
     #[allow(dead_code)]
@@ -471,12 +1434,16 @@ impl GameState {
         self.foundations.iter().all(|pile| pile.cards.len() == 13)
     }
 
+    /// How many cards are on the foundations, all four piles together.
+    pub fn foundation_cards(&self) -> usize {
+        self.foundations.iter().map(|pile| pile.cards.len()).sum()
+    }
+
     pub fn force_complete_foundations(&mut self) -> bool {
         if self.is_won() {
             return false;
         }
-        let initial_foundation_cards: usize =
-            self.foundations.iter().map(|pile| pile.cards.len()).sum();
+        let initial_foundation_cards = self.foundation_cards();
         let mut collected = Vec::with_capacity(DECK_SIZE);
         let mut foundation_suits = [None; FOUNDATION_PILES];
         for (idx, foundation) in self.foundations.iter_mut().enumerate() {
@@ -529,7 +1496,7 @@ impl GameState {
         let added_to_foundation = total_cards.saturating_sub(initial_foundation_cards);
         if added_to_foundation > 0 {
             self.moves = self.moves.saturating_add(added_to_foundation as u32);
-            self.score += (added_to_foundation as i32) * 10;
+            self.score_foundation(added_to_foundation as i32);
         }
         for tableau in &mut self.tableaus {
             tableau.cards.clear();
@@ -545,45 +1512,171 @@ impl GameState {
         can_place_on_foundation(card, self.foundations[foundation].cards.last().copied())
     }
 
-    pub fn place_on_foundation(&mut self, foundation: usize, card: Card) -> bool {
-        if !self.can_accept_foundation(foundation, card) {
-            return false;
+    fn score_foundation(&mut self, cards: i32) {
+        self.score += cards * self.foundation_points();
+    }
+
+    fn foundation_points(&self) -> i32 {
+        match self.scoring_mode {
+            ScoringMode::Standard => STANDARD_FOUNDATION_POINTS,
+            ScoringMode::Vegas => VEGAS_FOUNDATION_PAYOUT,
         }
-        self.foundations[foundation].cards.push(card);
-        self.moves = self.moves.saturating_add(1);
-        self.score += 10;
-        true
     }
 
-    pub fn move_waste_to_any_foundation(&mut self) -> bool {
-        if let Some(card) = self.waste.cards.last().copied() {
-            for idx in 0..FOUNDATION_PILES {
-                if self.can_accept_foundation(idx, card) {
-                    let card = self.waste.cards.pop().unwrap();
-                    return self.place_on_foundation(idx, card);
+    /// Score change for `mv` under the current rules; [`GameState::apply`] adds it and
+    /// [`GameState::undo`] takes it away again.
+    fn move_points(&self, mv: Move) -> i32 {
+        match (self.scoring_mode, mv) {
+            (_, Move::WasteToFoundation { .. } | Move::TableauToFoundation { .. }) => {
+                self.foundation_points()
+            }
+            (ScoringMode::Standard, Move::FoundationToTableau { .. }) => {
+                -STANDARD_FOUNDATION_RETURN_PENALTY
+            }
+            (ScoringMode::Vegas, Move::FoundationToTableau { .. }) => -VEGAS_FOUNDATION_PAYOUT,
+            (ScoringMode::Standard, Move::FlipTableauTop { .. }) => STANDARD_REVEAL_POINTS,
+            (ScoringMode::Standard, Move::StockRecycle { .. }) => -STANDARD_RECYCLE_PENALTY,
+            _ => 0,
+        }
+    }
+
+    /// Adds the Standard end-of-game bonus for a win that took `elapsed`, once per deal.
+    /// Returns the points awarded, which are zero for Vegas, short games or repeat calls.
+    pub fn award_time_bonus(&mut self, elapsed: Duration) -> i32 {
+        let secs = elapsed.as_secs();
+        if self.scoring_mode != ScoringMode::Standard
+            || !self.is_won()
+            || self.time_bonus != 0
+            || secs < TIME_BONUS_MIN_SECS
+        {
+            return 0;
+        }
+        let bonus = i32::try_from(TIME_BONUS_NUMERATOR / secs).unwrap_or(i32::MAX);
+        self.time_bonus = bonus;
+        self.score += bonus;
+        bonus
+    }
+
+    /// Sends every waste or tableau-top card that no tableau build could still need up to
+    /// the foundations, repeating until nothing more qualifies. Returns the number moved.
+    pub fn auto_move_safe_to_foundations(&mut self) -> usize {
+        self.auto_move_to_foundations(Self::safe_to_foundation)
+    }
+
+    /// Sends up only the aces and twos within reach, which can never be wanted on the
+    /// tableau. Returns the number moved.
+    pub fn collect_aces_and_twos(&mut self) -> usize {
+        self.auto_move_to_foundations(|_, card| card.rank <= Rank::Two)
+    }
+
+    /// Moves the waste top or a face-up tableau top that `wanted` picks to the foundations,
+    /// again and again until none qualifies. Returns the number moved.
+    fn auto_move_to_foundations(&mut self, wanted: impl Fn(&Self, Card) -> bool) -> usize {
+        let mut moved = 0;
+        loop {
+            if let Some(card) = self.waste.cards.last().copied() {
+                if wanted(self, card) && self.move_waste_to_any_foundation() {
+                    moved += 1;
+                    continue;
                 }
             }
+            let mut progressed = false;
+            for column in 0..TABLEAU_PILES {
+                let wanted = match self.tableaus[column].cards.last() {
+                    Some(card) => card.face_up && wanted(self, *card),
+                    None => false,
+                };
+                if wanted && self.move_tableau_top_to_any_foundation(column) {
+                    moved += 1;
+                    progressed = true;
+                    break;
+                }
+            }
+            if !progressed {
+                return moved;
+            }
+        }
+    }
+
+    /// A card is safe to play up once both opposite-colour cards one rank lower are on the
+    /// foundations: those are the only cards that could ever be built onto it.
+    fn safe_to_foundation(&self, card: Card) -> bool {
+        let rank = card.rank as u8;
+        if rank <= 2 {
+            return true;
+        }
+        SUITS
+            .iter()
+            .filter(|suit| suit.color() != card.suit.color())
+            .all(|&suit| self.foundation_height(suit) + 1 >= rank)
+    }
+
+    fn foundation_height(&self, suit: Suit) -> u8 {
+        self.foundations
+            .iter()
+            .filter_map(|pile| pile.cards.last())
+            .find(|top| top.suit == suit)
+            .map_or(0, |top| top.rank as u8)
+    }
+
+    pub fn move_waste_to_any_foundation(&mut self) -> bool {
+        match self.waste.cards.last().copied() {
+            Some(card) => self
+                .foundation_for(card)
+                .is_some_and(|foundation| self.move_waste_to_foundation(foundation)),
+            None => false,
         }
-        false
     }
 
     pub fn move_tableau_top_to_any_foundation(&mut self, column: usize) -> bool {
-        if column >= TABLEAU_PILES {
-            return false;
+        match self.tableaus.get(column).and_then(|pile| pile.cards.last()) {
+            Some(&card) if card.face_up => self
+                .foundation_for(card)
+                .is_some_and(|foundation| self.move_tableau_to_foundation(column, foundation)),
+            _ => false,
         }
-        let card = match self.tableaus[column].cards.last().copied() {
-            Some(card) if card.face_up => card,
-            _ => return false,
+    }
+
+    /// Tableau move a double-click should make with the waste top (`from` is `None`) or the
+    /// card at `from = Some((column, index))`. A tableau card may take the rest of its face-up
+    /// run along, and a move that turns up a face-down card is preferred; after that occupied
+    /// columns beat empty ones so a free column is not spent needlessly. Shuffling a whole
+    /// column into an empty one achieves nothing and is never offered.
+    pub fn best_tableau_move(&self, from: Option<(usize, usize)>) -> Option<Move> {
+        let occupied = |to: usize| !self.tableaus[to].cards.is_empty();
+        let Some((column, index)) = from else {
+            return (0..TABLEAU_PILES)
+                .filter(|&to| {
+                    self.check_legal(Move::WasteToTableau { column: to })
+                        .is_ok()
+                })
+                .max_by_key(|&to| (occupied(to), Reverse(to)))
+                .map(|column| Move::WasteToTableau { column });
         };
-        if let Some(idx) = (0..FOUNDATION_PILES).find(|&i| self.can_accept_foundation(i, card)) {
-            let card = self.tableaus[column].cards.pop().unwrap();
-            if self.place_on_foundation(idx, card) {
-                self.reveal_tableau_top(column);
-                return true;
+        let cards = self.tableau_column(column)?;
+        if !cards.get(index).is_some_and(|card| card.face_up) {
+            return None;
+        }
+        let run_base = cards.len() - cards.iter().rev().take_while(|card| card.face_up).count();
+        let mut best = None;
+        for start in [run_base, index] {
+            let exposes = start > 0;
+            for to in 0..TABLEAU_PILES {
+                let mv = Move::TableauToTableau {
+                    from: column,
+                    count: cards.len() - start,
+                    to,
+                };
+                if (start == 0 && !occupied(to)) || self.check_legal(mv).is_err() {
+                    continue;
+                }
+                let key = (exposes, occupied(to), Reverse(to));
+                if best.is_none_or(|(best_key, _)| key > best_key) {
+                    best = Some((key, mv));
+                }
             }
-            return false;
         }
-        false
+        best.map(|(_, mv)| mv)
     }
 
     pub fn waste_count(&self) -> usize {
@@ -593,6 +1686,104 @@ impl GameState {
     pub fn tableau_column(&self, column: usize) -> Option<&[Card]> {
         self.tableaus.get(column).map(|pile| pile.cards.as_slice())
     }
+
+    /// The seed, draw mode and move log as plain text, one numbered move per line. Dealing
+    /// the seed and applying the moves in order reproduces the game. Scoring and rule lines
+    /// appear only when they differ from the defaults, so older logs still read the same.
+    pub fn export_move_log(&self) -> String {
+        let mut text = format!("Seed: 0x{:X}\nDraw: {}\n", self.rng_seed, self.draw_count());
+        if self.scoring_mode == ScoringMode::Vegas {
+            text.push_str("Scoring: Vegas\n");
+        }
+        if let Some(limit) = self.pass_limit {
+            let _ = writeln!(text, "Passes: {limit}");
+        }
+        if self.allow_any_to_empty {
+            text.push_str("Empty columns: any card\n");
+        }
+        for (index, mv) in self.move_log.iter().enumerate() {
+            let _ = writeln!(text, "{}. {mv}", index + 1);
+        }
+        text
+    }
+
+    /// Seed, draw mode and rules followed by the solver's view of the board, for pasting into
+    /// bug reports; see [`solver::dump_position`].
+    pub fn dump_board(&self) -> String {
+        let mut text = format!("Seed: 0x{:X}  Draw: {}", self.rng_seed, self.draw_count());
+        if self.allow_any_to_empty {
+            text.push_str("  Empty columns: any card");
+        }
+        text.push('\n');
+        text.push_str(&solver::dump_position(&self.to_solver_position()));
+        text
+    }
+
+    /// Every move that is legal right now, stock clicks and face-down flips included. Each one
+    /// can be passed straight to [`GameState::apply`].
+    pub fn available_moves(&self) -> Vec<Move> {
+        let mut candidates: Vec<Move> = self.stock_move().into_iter().collect();
+        for column in 0..TABLEAU_PILES {
+            candidates.push(Move::FlipTableauTop { column });
+            candidates.push(Move::WasteToTableau { column });
+            for foundation in 0..FOUNDATION_PILES {
+                candidates.push(Move::TableauToFoundation { column, foundation });
+                candidates.push(Move::FoundationToTableau { foundation, column });
+            }
+            let face_up = self.tableaus[column]
+                .cards
+                .iter()
+                .rev()
+                .take_while(|card| card.face_up)
+                .count();
+            for to in 0..TABLEAU_PILES {
+                for count in 1..=face_up {
+                    candidates.push(Move::TableauToTableau {
+                        from: column,
+                        count,
+                        to,
+                    });
+                }
+            }
+        }
+        for foundation in 0..FOUNDATION_PILES {
+            candidates.push(Move::WasteToFoundation { foundation });
+        }
+        candidates.retain(|&mv| self.check_legal(mv).is_ok());
+        candidates
+    }
+
+    /// Cheap dead-end check, not a proof either way. Cycling the stock only counts while one
+    /// of its cards could be played somewhere; moving a whole column to an empty one and
+    /// pulling cards back off the foundations never count.
+    pub fn has_any_legal_move(&self) -> bool {
+        let playable = |card: &Card| {
+            self.foundation_for(*card).is_some()
+                || (0..TABLEAU_PILES).any(|column| self.fits_on_tableau(*card, column))
+        };
+        let stock_helps = self.stock_move().is_some()
+            && self
+                .stock
+                .cards
+                .iter()
+                .chain(&self.waste.cards)
+                .any(playable);
+        stock_helps || self.has_board_move()
+    }
+
+    /// Whether some move other than working the stock could make progress.
+    fn has_board_move(&self) -> bool {
+        self.available_moves().into_iter().any(|mv| match mv {
+            Move::StockDraw { .. }
+            | Move::StockRecycle { .. }
+            | Move::Reshuffle { .. }
+            | Move::FoundationToTableau { .. } => false,
+            Move::TableauToTableau { from, count, to } => {
+                count < self.tableau_len(from) || self.tableau_len(to) > 0
+            }
+            _ => true,
+        })
+    }
 }
 
 impl Default for GameState {
@@ -601,6 +1792,34 @@ impl Default for GameState {
     }
 }
 
+/// Solver suit order (Clubs, Diamonds, Hearts, Spades), which differs from the sprite rows.
+fn solver_suit(suit: Suit) -> u8 {
+    match suit {
+        Suit::Clubs => 0,
+        Suit::Diamonds => 1,
+        Suit::Hearts => 2,
+        Suit::Spades => 3,
+    }
+}
+
+/// Encodes a card as the solver's `suit * 13 + rank`. Not the same as `sprite_index`.
+fn solver_card(card: Card) -> u8 {
+    solver_suit(card.suit) * 13 + card.rank.column()
+}
+
+/// Decodes a solver card code; the inverse of [`solver_card`].
+#[cfg(any(test, feature = "solve-cli"))]
+fn card_from_solver(code: u8) -> Option<Card> {
+    let suit = match code / 13 {
+        0 => Suit::Clubs,
+        1 => Suit::Diamonds,
+        2 => Suit::Hearts,
+        3 => Suit::Spades,
+        _ => return None,
+    };
+    Some(Card::new(suit, RANKS[(code % 13) as usize]))
+}
+
 fn create_standard_deck() -> Vec<Card> {
     let mut deck = Vec::with_capacity(DECK_SIZE);
     for suit in SUITS {
@@ -611,7 +1830,7 @@ fn create_standard_deck() -> Vec<Card> {
     deck
 }
 
-fn shuffle_deck(deck: &mut [Card], seed: u64) {
+fn shuffle_deck<T>(deck: &mut [T], seed: u64) {
     let mut rng = ShuffleRng::new(seed);
     for i in (1..deck.len()).rev() {
         let j = (rng.next_u32() as usize) % (i + 1);
@@ -619,12 +1838,20 @@ fn shuffle_deck(deck: &mut [Card], seed: u64) {
     }
 }
 
-fn random_seed() -> Result<u64> {
+/// The opening layout `seed` deals, in the deck order `solve_deck` expects.
+pub(crate) fn solver_deck_for_seed(draw_mode: DrawMode, seed: u64) -> Option<[u8; 52]> {
+    let mut opening = GameState::new();
+    opening.deal_new_game_with_seed(draw_mode, seed).ok()?;
+    opening.opening_solver_deck()
+}
+
+pub(crate) fn random_seed() -> Result<u64> {
     let mut bytes = [0u8; 8];
     fill_random(&mut bytes)?;
     Ok(u64::from_le_bytes(bytes))
 }
 
+#[cfg(windows)]
 fn fill_random(bytes: &mut [u8]) -> Result<()> {
     let status = unsafe {
         BCryptGenRandom(
@@ -640,6 +1867,30 @@ fn fill_random(bytes: &mut [u8]) -> Result<()> {
     }
 }
 
+/// Non-Windows fallback: `RandomState` is keyed from the OS entropy source, and mixing in
+/// the clock and a counter keeps successive calls distinct.
+#[cfg(not(windows))]
+fn fill_random(bytes: &mut [u8]) -> Result<()> {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    for chunk in bytes.chunks_mut(8) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        let word = hasher.finish().to_le_bytes();
+        chunk.copy_from_slice(&word[..chunk.len()]);
+    }
+    Ok(())
+}
+
 fn is_valid_tableau_run(cards: &[Card]) -> bool {
     if cards.is_empty() {
         return false;
@@ -671,14 +1922,14 @@ fn can_place_on_foundation(card: Card, top: Option<Card>) -> bool {
     }
 }
 
-fn can_place_on_tableau(card: Card, top: Option<Card>) -> bool {
+fn can_place_on_tableau(card: Card, top: Option<Card>, any_to_empty: bool) -> bool {
     match top {
         Some(top_card) => {
             top_card.face_up
                 && card.suit.color() != top_card.suit.color()
                 && rank_value(card.rank) + 1 == rank_value(top_card.rank)
         }
-        None => card.rank == Rank::King,
+        None => any_to_empty || card.rank == Rank::King,
     }
 }
 
@@ -688,10 +1939,29 @@ fn rank_value(rank: Rank) -> u8 {
 
 struct ShuffleRng(u64);
 
+/// xorshift has a fixed point at zero, so a zero seed is swapped for this constant.
+const ZERO_SEED_REPLACEMENT: u64 = 0x4D44_5EED;
+
+/// Reads a seed typed as decimal or as `0x`-prefixed hex.
+pub fn parse_seed(text: &str) -> Option<u64> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn effective_seed(seed: u64) -> u64 {
+    if seed == 0 {
+        ZERO_SEED_REPLACEMENT
+    } else {
+        seed
+    }
+}
+
 impl ShuffleRng {
     fn new(seed: u64) -> Self {
-        let seed = if seed == 0 { 0x4D44_5EED } else { seed };
-        Self(seed)
+        Self(effective_seed(seed))
     }
 
     fn next_u32(&mut self) -> u32 {
@@ -704,3 +1974,1110 @@ impl ShuffleRng {
         ((x.wrapping_mul(0x2545_F491_4F6C_DD1D)) >> 32) as u32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 0x5EED_1234)
+            .unwrap();
+        game.stock_click();
+        game.score = 35;
+        game.moves = 7;
+
+        let path = std::env::temp_dir().join(format!("mdsol-save-{}.json", std::process::id()));
+        game.save_to_path(&path).unwrap();
+        let loaded = GameState::load_from_path(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.score, 35);
+        assert_eq!(loaded.moves, 7);
+        assert_eq!(loaded.rng_seed, 0x5EED_1234);
+        assert_eq!(loaded.draw_mode, DrawMode::DrawThree);
+        assert_eq!(loaded.waste.cards, game.waste.cards);
+        assert_eq!(loaded.stock.cards, game.stock.cards);
+        for (a, b) in loaded.tableaus.iter().zip(&game.tableaus) {
+            assert_eq!(a.cards, b.cards);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_corrupted_states() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 7).unwrap();
+        assert!(game.validate().is_ok());
+        // An empty board is missing every card.
+        assert!(GameState::new().validate().is_err());
+
+        let mut duplicate = game.clone();
+        duplicate.stock.cards[0] = duplicate.stock.cards[1];
+        assert!(duplicate.validate().is_err());
+
+        // A two on a foundation with no ace beneath it.
+        let mut no_ace = game.clone();
+        let two = no_ace
+            .stock
+            .cards
+            .iter()
+            .position(|card| card.rank == Rank::Two)
+            .unwrap();
+        let card = no_ace.stock.cards.remove(two);
+        no_ace.foundations[0]
+            .cards
+            .push(face_up(card.suit, card.rank));
+        assert!(no_ace.validate().is_err());
+
+        let mut wrong_sprite = game.clone();
+        wrong_sprite.tableaus[3].cards[0].sprite_index ^= 1;
+        assert!(wrong_sprite.validate().is_err());
+
+        // A face-down card on top of a face-up one cannot come about in play.
+        let mut buried = game;
+        let top = buried.tableaus[1].cards.pop().unwrap();
+        buried.tableaus[1].cards[0].face_up = true;
+        buried.tableaus[1].cards.push(Card {
+            face_up: false,
+            ..top
+        });
+        assert!(buried.validate().is_err());
+    }
+
+    #[test]
+    fn test_foundation_routing_follows_the_started_suit() {
+        let mut game = GameState::new();
+        game.waste.cards = vec![
+            face_up(Suit::Spades, Rank::Two),
+            face_up(Suit::Hearts, Rank::Two),
+            face_up(Suit::Hearts, Rank::Ace),
+            face_up(Suit::Spades, Rank::Ace),
+        ];
+
+        assert_eq!(
+            game.foundation_for(face_up(Suit::Spades, Rank::Ace)),
+            Some(0)
+        );
+        assert!(game.move_waste_to_any_foundation());
+        assert!(game.move_waste_to_any_foundation());
+        assert_eq!(game.foundations[0].cards[0].suit, Suit::Spades);
+        assert_eq!(game.foundations[1].cards[0].suit, Suit::Hearts);
+
+        // The twos follow their aces, not the first pile that happens to be free.
+        assert_eq!(
+            game.foundation_for(face_up(Suit::Hearts, Rank::Two)),
+            Some(1)
+        );
+        assert!(game.move_waste_to_any_foundation());
+        assert_eq!(game.foundations[1].cards.len(), 2);
+        assert!(game.move_waste_to_any_foundation());
+        assert_eq!(game.foundations[0].cards.len(), 2);
+        assert_eq!(game.foundation_for(face_up(Suit::Clubs, Rank::Two)), None);
+        assert_eq!(
+            game.foundation_for(face_up(Suit::Clubs, Rank::Ace)),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_deal_is_deterministic_per_seed() {
+        let mut a = GameState::new();
+        let mut b = GameState::new();
+        a.deal_new_game_with_seed(DrawMode::DrawOne, 99).unwrap();
+        b.deal_new_game_with_seed(DrawMode::DrawOne, 99).unwrap();
+        for column in 0..TABLEAU_PILES {
+            let cards = &a.tableaus[column].cards;
+            assert_eq!(cards.len(), column + 1);
+            assert_eq!(cards, &b.tableaus[column].cards);
+            assert!(cards.last().unwrap().face_up);
+            assert!(cards[..column].iter().all(|card| !card.face_up));
+        }
+        assert_eq!(a.stock.cards.len(), DECK_SIZE - 28);
+        assert_eq!(a.stock.cards, b.stock.cards);
+        assert!(a.waste.cards.is_empty());
+
+        b.deal_new_game_with_seed(DrawMode::DrawOne, 100).unwrap();
+        assert_ne!(a.stock.cards, b.stock.cards);
+    }
+
+    #[test]
+    fn test_zero_seed_reports_effective_seed() {
+        let mut zero = GameState::new();
+        let mut remapped = GameState::new();
+        zero.deal_new_game_with_seed(DrawMode::DrawOne, 0).unwrap();
+        remapped
+            .deal_new_game_with_seed(DrawMode::DrawOne, ZERO_SEED_REPLACEMENT)
+            .unwrap();
+        assert_eq!(zero.rng_seed, ZERO_SEED_REPLACEMENT);
+        assert_eq!(zero.stock.cards, remapped.stock.cards);
+    }
+
+    #[test]
+    fn test_solver_card_encoding() {
+        assert_eq!(solver_card(Card::new(Suit::Clubs, Rank::Ace)), 0);
+        assert_eq!(solver_card(Card::new(Suit::Diamonds, Rank::Two)), 14);
+        assert_eq!(solver_card(Card::new(Suit::Hearts, Rank::Queen)), 37);
+        assert_eq!(solver_card(Card::new(Suit::Spades, Rank::King)), 51);
+    }
+
+    #[test]
+    fn test_hand_built_deal_is_solver_winnable() {
+        // Sevens..kings fill the tableau so every pile runs high->low towards its top, and
+        // aces..sixes make up the stock: a game that plays itself out.
+        let mut high: Vec<Card> = RANKS[6..]
+            .iter()
+            .rev()
+            .flat_map(|&rank| SUITS.iter().map(move |&suit| Card::new(suit, rank)))
+            .collect();
+        let mut game = GameState::new();
+        for column in 0..TABLEAU_PILES {
+            let mut cards: Vec<Card> = high.drain(..=column).collect();
+            cards.last_mut().unwrap().face_up = true;
+            game.tableaus[column].cards = cards;
+        }
+        game.stock.cards = RANKS[..6]
+            .iter()
+            .flat_map(|&rank| SUITS.iter().map(move |&suit| Card::new(suit, rank)))
+            .collect();
+
+        let deck = game.opening_solver_deck().unwrap();
+        assert_eq!(deck[0], solver_card(game.tableaus[0].cards[0]));
+        assert_eq!(deck[51], solver_card(game.stock.cards[0]));
+        assert_eq!(
+            solve_deck(&deck, 1, false, Duration::from_millis(500)),
+            SolveResult::Winnable
+        );
+    }
+
+    #[test]
+    fn test_solver_deck_path_replays_as_a_move_log() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 77)
+            .unwrap();
+        let deck = game.opening_solver_deck().unwrap();
+        let rebuilt = GameState::from_solver_deck(&deck, DrawMode::DrawThree, false).unwrap();
+        assert_eq!(rebuilt.tableaus, game.tableaus);
+        assert_eq!(rebuilt.stock, game.stock);
+
+        // The hand-built deal from above, read back from its solver deck and played out.
+        let mut high: Vec<Card> = RANKS[6..]
+            .iter()
+            .rev()
+            .flat_map(|&rank| SUITS.iter().map(move |&suit| Card::new(suit, rank)))
+            .collect();
+        for column in 0..TABLEAU_PILES {
+            game.tableaus[column].cards = high.drain(..=column).collect();
+            game.tableaus[column].cards.last_mut().unwrap().face_up = true;
+        }
+        game.stock.cards = RANKS[..6]
+            .iter()
+            .flat_map(|&rank| SUITS.iter().map(move |&suit| Card::new(suit, rank)))
+            .collect();
+        let deck = game.opening_solver_deck().unwrap();
+        let (result, path) =
+            solver::solve_deck_with_path(&deck, 1, false, Duration::from_millis(500));
+        assert_eq!(result, SolveResult::Winnable);
+        let start = GameState::from_solver_deck(&deck, DrawMode::DrawOne, false).unwrap();
+        let (won, _) = start.play_solver_path(&path.unwrap()).unwrap();
+        assert!(won.is_won());
+        assert_eq!(won.move_log.first(), Some(&Move::StockDraw { count: 1 }));
+        assert_eq!(
+            won.move_log.first().map(ToString::to_string).as_deref(),
+            Some("S draw")
+        );
+    }
+
+    #[test]
+    fn test_seeded_solver_deck_matches_deal() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 1234)
+            .unwrap();
+        assert_eq!(game.to_solver_deck(), game.opening_solver_deck());
+    }
+
+    #[test]
+    fn test_restart_returns_to_deal_start() {
+        let mut game = GameState::new();
+        game.set_scoring_mode(ScoringMode::Vegas, true);
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 31)
+            .unwrap();
+        let opening = game.clone();
+        game.stock_click();
+        game.waste.cards.push(face_up(Suit::Clubs, Rank::Ace));
+        assert!(game.move_waste_to_any_foundation());
+        assert_ne!(game, opening);
+
+        game.restart().unwrap();
+        assert_eq!(game, opening);
+        assert_eq!(game.score, -52);
+
+        // Deal Again charges for a fresh deal in cumulative Vegas; Restart does not.
+        game.deal_again().unwrap();
+        assert_eq!(game.score, -104);
+    }
+
+    #[test]
+    fn test_random_deals_differ() {
+        let mut a = GameState::new();
+        let mut b = GameState::new();
+        a.deal_new_game(DrawMode::DrawOne).unwrap();
+        b.deal_new_game(DrawMode::DrawOne).unwrap();
+        assert_ne!(a.rng_seed, b.rng_seed);
+    }
+
+    fn face_up(suit: Suit, rank: Rank) -> Card {
+        Card {
+            face_up: true,
+            ..Card::new(suit, rank)
+        }
+    }
+
+    /// Deals under `mode`, then feeds the ace, two and three of spades to a foundation
+    /// via the waste and reports the score after each placement.
+    fn foundation_run_scores(mode: ScoringMode, cumulative: bool) -> Vec<i32> {
+        let mut game = GameState::new();
+        game.set_scoring_mode(mode, cumulative);
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 42).unwrap();
+        let mut scores = vec![game.score];
+        for rank in [Rank::Ace, Rank::Two, Rank::Three] {
+            game.waste.cards.push(face_up(Suit::Spades, rank));
+            assert!(game.move_waste_to_any_foundation());
+            scores.push(game.score);
+        }
+        scores
+    }
+
+    #[test]
+    fn test_auto_move_safe_to_foundations() {
+        let mut game = GameState::new();
+        game.waste.cards.push(face_up(Suit::Spades, Rank::Ace));
+        game.tableaus[0]
+            .cards
+            .push(face_up(Suit::Hearts, Rank::Ace));
+        game.tableaus[1]
+            .cards
+            .push(face_up(Suit::Spades, Rank::Three));
+        game.tableaus[1]
+            .cards
+            .push(face_up(Suit::Spades, Rank::Two));
+        game.tableaus[2]
+            .cards
+            .push(face_up(Suit::Hearts, Rank::Two));
+
+        // A/2 of spades and hearts go up; the three of spades stays because the two of
+        // diamonds could still need a black three to build on.
+        assert_eq!(game.auto_move_safe_to_foundations(), 4);
+        assert_eq!(
+            game.tableaus[1].cards,
+            vec![face_up(Suit::Spades, Rank::Three)]
+        );
+        assert_eq!(game.auto_move_safe_to_foundations(), 0);
+
+        game.foundations[2]
+            .cards
+            .push(face_up(Suit::Diamonds, Rank::Ace));
+        game.foundations[2]
+            .cards
+            .push(face_up(Suit::Diamonds, Rank::Two));
+        assert_eq!(game.auto_move_safe_to_foundations(), 1);
+        assert!(game.tableaus[1].cards.is_empty());
+    }
+
+    #[test]
+    fn test_collect_aces_and_twos_leaves_everything_else() {
+        let mut game = GameState::new();
+        game.waste.cards.push(face_up(Suit::Clubs, Rank::Two));
+        game.waste.cards.push(face_up(Suit::Clubs, Rank::Ace));
+        game.tableaus[0].cards = vec![
+            face_up(Suit::Diamonds, Rank::Three),
+            face_up(Suit::Diamonds, Rank::Two),
+        ];
+        game.tableaus[1]
+            .cards
+            .push(face_up(Suit::Diamonds, Rank::Ace));
+        game.tableaus[2]
+            .cards
+            .push(face_up(Suit::Hearts, Rank::Two));
+
+        // Both clubs come off the waste in turn, the diamonds follow once their ace is up,
+        // and the three and the unplayable two of hearts stay put.
+        assert_eq!(game.collect_aces_and_twos(), 4);
+        assert_eq!(
+            game.tableaus[0].cards,
+            vec![face_up(Suit::Diamonds, Rank::Three)]
+        );
+        assert_eq!(game.tableaus[2].cards.len(), 1);
+        assert!(game.waste.cards.is_empty());
+        assert_eq!(game.foundation_cards(), 4);
+        assert_eq!(game.collect_aces_and_twos(), 0);
+    }
+
+    #[test]
+    fn test_solve_line_plays_out_endgame() {
+        let mut game = GameState::new();
+        for (idx, suit) in SUITS.iter().enumerate() {
+            let top = if *suit == Suit::Hearts { 11 } else { 12 };
+            game.foundations[idx].cards = RANKS[..top]
+                .iter()
+                .map(|&rank| face_up(*suit, rank))
+                .collect();
+        }
+        game.tableaus[3].cards = vec![
+            Card::new(Suit::Clubs, Rank::King),
+            face_up(Suit::Hearts, Rank::Queen),
+        ];
+        game.waste.cards = vec![face_up(Suit::Hearts, Rank::King)];
+        game.stock.cards = vec![
+            Card::new(Suit::Spades, Rank::King),
+            Card::new(Suit::Diamonds, Rank::King),
+        ];
+
+        let line = game.solve_line(Duration::from_millis(500)).unwrap();
+        assert!(line.iter().any(|step| step.from == PileRef::Stock));
+        let mut replay = game.clone();
+        for step in line {
+            assert!(replay.play_hint(step), "step {step:?} was rejected");
+        }
+        assert!(replay.is_won());
+    }
+
+    #[test]
+    fn test_solve_state_from_nearly_won_game() {
+        let mut game = GameState::new();
+        for (idx, suit) in SUITS.iter().enumerate() {
+            let top = if *suit == Suit::Hearts { 11 } else { 12 };
+            game.foundations[idx].cards = RANKS[..top]
+                .iter()
+                .map(|&rank| face_up(*suit, rank))
+                .collect();
+        }
+        // The club king is still face down under the heart queen.
+        game.tableaus[3].cards = vec![
+            Card::new(Suit::Clubs, Rank::King),
+            face_up(Suit::Hearts, Rank::Queen),
+        ];
+        game.waste.cards = vec![face_up(Suit::Hearts, Rank::King)];
+        game.stock.cards = vec![
+            Card::new(Suit::Spades, Rank::King),
+            Card::new(Suit::Diamonds, Rank::King),
+        ];
+        assert_eq!(
+            solver::solve_state(&game, Duration::from_millis(20)),
+            SolveResult::Winnable
+        );
+        // Drawing three, the last two kings only come up as a short final group.
+        game.draw_mode = DrawMode::DrawThree;
+        assert_eq!(
+            solver::solve_state(&game, Duration::from_millis(20)),
+            SolveResult::Winnable
+        );
+
+        // With the heart king gone the hearts can never be finished.
+        game.waste.cards.clear();
+        assert_eq!(
+            solver::solve_state(&game, Duration::from_millis(20)),
+            SolveResult::Unwinnable
+        );
+    }
+
+    #[test]
+    fn test_move_foundation_to_tableau_refunds_score() {
+        let mut game = GameState::new();
+        game.foundations[0].cards = vec![
+            face_up(Suit::Spades, Rank::Ace),
+            face_up(Suit::Spades, Rank::Two),
+        ];
+        game.tableaus[0].cards = vec![face_up(Suit::Hearts, Rank::Three)];
+        game.score = 20;
+        assert!(!game.move_foundation_to_tableau(0, 1));
+        assert!(game.move_foundation_to_tableau(0, 0));
+        assert_eq!(game.score, 5);
+        assert_eq!(game.foundations[0].cards.len(), 1);
+        assert_eq!(game.tableaus[0].cards.len(), 2);
+    }
+
+    #[test]
+    fn test_standard_scoring_replay_and_undo() {
+        let mut game = GameState::new();
+        game.tableaus[0].cards = vec![
+            Card::new(Suit::Clubs, Rank::Five),
+            face_up(Suit::Spades, Rank::Ace),
+        ];
+        game.tableaus[1].cards = vec![face_up(Suit::Hearts, Rank::Two)];
+        game.waste.cards = vec![face_up(Suit::Hearts, Rank::Ace)];
+        game.stock.cards = vec![Card::new(Suit::Diamonds, Rank::King)];
+        let replay = [
+            (
+                Move::TableauToFoundation {
+                    column: 0,
+                    foundation: 0,
+                },
+                10,
+            ),
+            (Move::FlipTableauTop { column: 0 }, 15),
+            (Move::WasteToFoundation { foundation: 1 }, 25),
+            (Move::StockDraw { count: 1 }, 25),
+            (Move::StockRecycle { count: 1 }, 23),
+            (
+                Move::FoundationToTableau {
+                    foundation: 0,
+                    column: 1,
+                },
+                8,
+            ),
+        ];
+        let mut scores = vec![game.score];
+        for (mv, expected) in replay {
+            game.apply(mv).unwrap();
+            assert_eq!(game.score, expected, "after {mv:?}");
+            scores.push(game.score);
+        }
+        assert_eq!(game.moves, 5);
+        for (mv, _) in replay.iter().rev() {
+            scores.pop();
+            game.undo(*mv).unwrap();
+            assert_eq!(game.score, *scores.last().unwrap(), "undoing {mv:?}");
+        }
+        assert_eq!((game.score, game.moves, game.passes), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_available_moves_lists_every_legal_move() {
+        let mut game = GameState::new();
+        game.tableaus[0].cards = vec![
+            Card::new(Suit::Clubs, Rank::Nine),
+            face_up(Suit::Hearts, Rank::Eight),
+            face_up(Suit::Spades, Rank::Seven),
+        ];
+        game.tableaus[1].cards = vec![face_up(Suit::Spades, Rank::Nine)];
+        game.tableaus[3].cards = vec![Card::new(Suit::Hearts, Rank::Jack)];
+        game.tableaus[4].cards = vec![face_up(Suit::Diamonds, Rank::King)];
+        game.tableaus[5].cards = vec![face_up(Suit::Clubs, Rank::Two)];
+        game.tableaus[6].cards = vec![face_up(Suit::Hearts, Rank::Ace)];
+        game.waste.cards = vec![face_up(Suit::Spades, Rank::Queen)];
+        game.stock.cards = vec![
+            Card::new(Suit::Clubs, Rank::Four),
+            Card::new(Suit::Clubs, Rank::Five),
+        ];
+
+        let mut expected = vec![
+            Move::StockDraw { count: 1 },
+            Move::FlipTableauTop { column: 3 },
+            Move::WasteToTableau { column: 4 },
+            Move::TableauToTableau {
+                from: 0,
+                count: 2,
+                to: 1,
+            },
+            Move::TableauToTableau {
+                from: 4,
+                count: 1,
+                to: 2,
+            },
+            Move::TableauToTableau {
+                from: 6,
+                count: 1,
+                to: 5,
+            },
+        ];
+        expected.extend(
+            (0..FOUNDATION_PILES).map(|foundation| Move::TableauToFoundation {
+                column: 6,
+                foundation,
+            }),
+        );
+        let moves = game.available_moves();
+        assert_eq!(moves.len(), expected.len(), "{moves:?}");
+        assert!(expected.iter().all(|mv| moves.contains(mv)), "{moves:?}");
+
+        // Drawing three takes whatever is left when the stock runs short.
+        game.draw_mode = DrawMode::DrawThree;
+        assert!(game
+            .available_moves()
+            .contains(&Move::StockDraw { count: 2 }));
+        game.stock.cards.clear();
+        game.pass_limit = Some(0);
+        assert!(!game
+            .available_moves()
+            .iter()
+            .any(|mv| matches!(mv, Move::StockDraw { .. } | Move::StockRecycle { .. })));
+    }
+
+    #[test]
+    fn test_saved_games_keep_their_rules() {
+        let path = std::env::temp_dir().join(format!("mdsol-rules-{}.json", std::process::id()));
+        for draw_mode in [DrawMode::DrawOne, DrawMode::DrawTwo, DrawMode::DrawThree] {
+            for scoring_mode in [ScoringMode::Standard, ScoringMode::Vegas] {
+                for pass_limit in [None, Some(1), Some(3)] {
+                    // Each bit switches on one of the yes/no rules.
+                    for flags in 0..8 {
+                        let allow_any_to_empty = flags & 1 != 0;
+                        let vegas_cumulative = flags & 2 != 0;
+                        let manual_flips = flags & 4 != 0;
+                        let mut game = GameState::new();
+                        game.set_scoring_mode(scoring_mode, vegas_cumulative);
+                        game.pass_limit = pass_limit;
+                        game.allow_any_to_empty = allow_any_to_empty;
+                        game.manual_flips = manual_flips;
+                        game.deal_new_game_with_seed(draw_mode, 0x70).unwrap();
+                        game.stock_click();
+                        game.save_to_path(&path).unwrap();
+                        let loaded = GameState::load_from_path(&path).unwrap();
+                        assert_eq!(loaded.draw_mode, draw_mode);
+                        assert_eq!(loaded.scoring_mode, scoring_mode);
+                        assert_eq!(loaded.pass_limit, pass_limit);
+                        assert_eq!(loaded.allow_any_to_empty, allow_any_to_empty);
+                        assert_eq!(loaded.vegas_cumulative, vegas_cumulative);
+                        assert_eq!(loaded.manual_flips, manual_flips);
+                        assert_eq!(loaded, game);
+                    }
+                }
+            }
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_move_log_follows_apply_and_undo() {
+        let mut game = GameState::new();
+        game.rng_seed = 0x2A;
+        game.tableaus[0].cards = vec![
+            Card::new(Suit::Clubs, Rank::Nine),
+            face_up(Suit::Hearts, Rank::Eight),
+            face_up(Suit::Spades, Rank::Seven),
+        ];
+        game.tableaus[1].cards = vec![face_up(Suit::Spades, Rank::Nine)];
+        game.waste.cards = vec![face_up(Suit::Hearts, Rank::Ace)];
+        game.stock.cards = vec![Card::new(Suit::Clubs, Rank::Four)];
+
+        assert!(game.move_tableau_stack(0, 1, 1));
+        assert!(game.move_waste_to_foundation(1));
+        assert_eq!(game.stock_click(), StockAction::Drawn(1));
+        assert_eq!(game.move_log.len(), 4);
+        game.undo(Move::StockDraw { count: 1 }).unwrap();
+        assert_eq!(
+            game.export_move_log(),
+            "Seed: 0x2A\nDraw: 1\n1. T1:2→T2\n2. T1 flip\n3. W→F2\n"
+        );
+
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 7)
+            .unwrap();
+        assert!(game.move_log.is_empty());
+        assert_eq!(game.export_move_log(), "Seed: 0x7\nDraw: 3\n");
+    }
+
+    #[test]
+    fn test_move_log_round_trips_through_export() {
+        for draw_mode in [DrawMode::DrawOne, DrawMode::DrawTwo, DrawMode::DrawThree] {
+            let mut game = GameState::new();
+            game.deal_new_game_with_seed(draw_mode, 0x51).unwrap();
+            for _ in 0..40 {
+                let moves = game.available_moves();
+                let mv = moves
+                    .iter()
+                    .find(|mv| !matches!(mv, Move::StockDraw { .. } | Move::StockRecycle { .. }))
+                    .or(moves.first())
+                    .copied()
+                    .unwrap();
+                game.apply(mv).unwrap();
+            }
+            let log = MoveLog::parse(&game.export_move_log()).unwrap();
+            assert_eq!(log.seed, 0x51);
+            assert_eq!(log.draw_mode, draw_mode);
+            assert_eq!(log.moves, game.move_log);
+        }
+
+        // Rules that decide which moves are legal travel with the log.
+        let mut game = GameState::new();
+        game.set_scoring_mode(ScoringMode::Vegas, false);
+        game.pass_limit = Some(1);
+        game.allow_any_to_empty = true;
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 0x51)
+            .unwrap();
+        let text = game.export_move_log();
+        assert_eq!(
+            text,
+            "Seed: 0x51\nDraw: 1\nScoring: Vegas\nPasses: 1\nEmpty columns: any card\n"
+        );
+        let dealt = MoveLog::parse(&text).unwrap().deal().unwrap();
+        assert_eq!(dealt, game);
+
+        for mv in [
+            Move::FoundationToTableau {
+                foundation: 3,
+                column: 6,
+            },
+            Move::TableauToFoundation {
+                column: 0,
+                foundation: 2,
+            },
+        ] {
+            assert_eq!(parse_move(&mv.to_string()).unwrap(), mv);
+        }
+        assert!(MoveLog::parse("Draw: 1\n1. S draw\n").is_err());
+        assert!(MoveLog::parse("Seed: 0x51\n1. T9→F1\n").is_err());
+        assert!(MoveLog::parse("Seed: 0x51\n1. W→F1\n").is_err());
+    }
+
+    #[test]
+    fn test_has_any_legal_move_spots_dead_ends() {
+        let mut fresh = GameState::new();
+        fresh.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        assert!(fresh.has_any_legal_move());
+
+        let mut game = GameState::new();
+        game.tableaus[0].cards = vec![
+            Card::new(Suit::Clubs, Rank::Nine),
+            face_up(Suit::Hearts, Rank::Five),
+        ];
+        game.tableaus[1].cards = vec![face_up(Suit::Spades, Rank::King)];
+        game.stock.cards = vec![Card::new(Suit::Clubs, Rank::Two)];
+        // Drawing is legal but the two can never be played, and the king has nowhere better.
+        assert!(!game.has_any_legal_move());
+
+        let mut with_parent = game.clone();
+        with_parent.tableaus[2].cards = vec![face_up(Suit::Clubs, Rank::Six)];
+        assert!(with_parent.has_any_legal_move());
+
+        game.stock.cards.push(Card::new(Suit::Diamonds, Rank::Ace));
+        assert!(game.has_any_legal_move());
+    }
+
+    #[test]
+    fn test_time_bonus_is_paid_once_and_undone() {
+        let mut game = GameState::new();
+        for (idx, suit) in SUITS.iter().enumerate() {
+            let top = if *suit == Suit::Spades { 12 } else { 13 };
+            game.foundations[idx].cards = RANKS[..top]
+                .iter()
+                .map(|&rank| face_up(*suit, rank))
+                .collect();
+        }
+        let spades = SUITS.iter().position(|&s| s == Suit::Spades).unwrap();
+        game.waste.cards = vec![face_up(Suit::Spades, Rank::King)];
+        assert_eq!(game.award_time_bonus(Duration::from_secs(100)), 0);
+
+        let mv = Move::WasteToFoundation { foundation: spades };
+        game.apply(mv).unwrap();
+        assert_eq!(game.award_time_bonus(Duration::from_secs(29)), 0);
+        assert_eq!(game.award_time_bonus(Duration::from_secs(100)), 7000);
+        assert_eq!(game.award_time_bonus(Duration::from_secs(100)), 0);
+        assert_eq!(game.score, 7010);
+        game.undo(mv).unwrap();
+        assert_eq!((game.score, game.time_bonus), (0, 0));
+
+        game.set_scoring_mode(ScoringMode::Vegas, false);
+        game.apply(mv).unwrap();
+        assert_eq!(game.award_time_bonus(Duration::from_secs(100)), 0);
+    }
+
+    /// Applies `mv`, checks it changed something, then checks `undo` restores the game exactly.
+    fn assert_round_trip(game: &GameState, mv: Move) {
+        let mut after = game.clone();
+        after.apply(mv).unwrap();
+        assert_ne!(&after, game, "{mv:?} had no effect");
+        after.undo(mv).unwrap();
+        assert_eq!(&after, game, "{mv:?} did not undo cleanly");
+    }
+
+    #[test]
+    fn test_apply_undo_round_trips() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 77)
+            .unwrap();
+        assert_round_trip(&game, Move::StockDraw { count: 3 });
+        game.stock.cards.truncate(2);
+        assert_round_trip(&game, Move::StockDraw { count: 2 });
+
+        let mut game = GameState::new();
+        game.waste.cards = vec![
+            face_up(Suit::Clubs, Rank::Nine),
+            face_up(Suit::Spades, Rank::Ace),
+        ];
+        assert_round_trip(&game, Move::StockRecycle { count: 2 });
+        assert_round_trip(&game, Move::WasteToFoundation { foundation: 2 });
+
+        game.waste.cards.push(face_up(Suit::Hearts, Rank::King));
+        assert_round_trip(&game, Move::WasteToTableau { column: 4 });
+
+        game.tableaus[0].cards = vec![
+            Card::new(Suit::Clubs, Rank::Five),
+            face_up(Suit::Diamonds, Rank::Ace),
+        ];
+        assert_round_trip(
+            &game,
+            Move::TableauToFoundation {
+                column: 0,
+                foundation: 1,
+            },
+        );
+
+        game.tableaus[1].cards = vec![
+            Card::new(Suit::Hearts, Rank::Two),
+            face_up(Suit::Spades, Rank::Queen),
+            face_up(Suit::Hearts, Rank::Jack),
+            face_up(Suit::Clubs, Rank::Ten),
+        ];
+        game.tableaus[2].cards = vec![face_up(Suit::Diamonds, Rank::King)];
+        assert_round_trip(
+            &game,
+            Move::TableauToTableau {
+                from: 1,
+                count: 3,
+                to: 2,
+            },
+        );
+
+        game.foundations[3].cards = vec![face_up(Suit::Hearts, Rank::Ace)];
+        game.tableaus[6].cards = vec![face_up(Suit::Clubs, Rank::Two)];
+        assert_round_trip(
+            &game,
+            Move::FoundationToTableau {
+                foundation: 3,
+                column: 6,
+            },
+        );
+
+        game.tableaus[5].cards = vec![Card::new(Suit::Spades, Rank::Four)];
+        assert_round_trip(&game, Move::FlipTableauTop { column: 5 });
+    }
+
+    #[test]
+    fn test_reshuffle_is_recorded_and_reversible() {
+        let mut game = GameState::new();
+        game.pass_limit = Some(1);
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 9).unwrap();
+        while game.stock_click() != StockAction::NoOp {}
+        assert_eq!(game.passes, 1);
+        for _ in 0..5 {
+            game.stock_click();
+        }
+        let before = game.clone();
+        let mut gathered: Vec<Card> = before
+            .stock
+            .cards
+            .iter()
+            .chain(&before.waste.cards)
+            .map(|&card| Card {
+                face_up: false,
+                ..card
+            })
+            .collect();
+
+        let seed = game.reshuffle_seed();
+        assert!(game.reshuffle_stock_waste(seed));
+        assert!(game.waste.cards.is_empty());
+        assert_eq!(game.passes, 0);
+        assert_eq!(game.moves, before.moves + 1);
+        assert_ne!(game.stock.cards, gathered);
+        let mut shuffled = game.stock.cards.clone();
+        shuffled.sort_by_key(|card| card.sprite_index);
+        gathered.sort_by_key(|card| card.sprite_index);
+        assert_eq!(shuffled, gathered);
+
+        // It travels through the move log, and undoing it restores the old order exactly.
+        let log = MoveLog::parse(&game.export_move_log()).unwrap();
+        assert_eq!(log.moves, game.move_log);
+        let last = *game.move_log.last().unwrap();
+        game.undo(last).unwrap();
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn test_stuck_stock_draws_on_to_a_playable_card() {
+        let mut game = GameState::new();
+        game.tableaus[0].cards = vec![face_up(Suit::Spades, Rank::King)];
+        game.stock.cards = vec![
+            Card::new(Suit::Hearts, Rank::Ace),
+            Card::new(Suit::Clubs, Rank::Nine),
+            Card::new(Suit::Diamonds, Rank::Five),
+        ];
+        assert_eq!(game.draws_until_playable(), Some(3));
+
+        // Once the ace is showing there is nothing to go looking for.
+        for _ in 0..3 {
+            game.stock_click();
+        }
+        assert_eq!(game.draws_until_playable(), None);
+
+        // A move on the board comes first.
+        game.waste.cards.pop();
+        game.tableaus[1].cards = vec![face_up(Suit::Hearts, Rank::Queen)];
+        assert_eq!(game.draws_until_playable(), None);
+    }
+
+    #[test]
+    fn test_apply_rejects_illegal_moves() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        let before = game.clone();
+        assert!(game.apply(Move::StockDraw { count: 3 }).is_err());
+        assert!(game.apply(Move::StockRecycle { count: 1 }).is_err());
+        assert!(game
+            .apply(Move::WasteToFoundation { foundation: 0 })
+            .is_err());
+        assert!(game.apply(Move::FlipTableauTop { column: 3 }).is_err());
+        assert!(game
+            .apply(Move::TableauToTableau {
+                from: 2,
+                count: 2,
+                to: 3
+            })
+            .is_err());
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn test_standard_scoring_foundation_run() {
+        assert_eq!(
+            foundation_run_scores(ScoringMode::Standard, false),
+            vec![0, 10, 20, 30]
+        );
+    }
+
+    #[test]
+    fn test_vegas_scoring_foundation_run() {
+        assert_eq!(
+            foundation_run_scores(ScoringMode::Vegas, false),
+            vec![-52, -47, -42, -37]
+        );
+    }
+
+    #[test]
+    fn test_vegas_reveal_and_force_complete() {
+        let mut game = GameState::new();
+        game.set_scoring_mode(ScoringMode::Vegas, false);
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 7).unwrap();
+        game.tableaus[1].cards.last_mut().unwrap().face_up = false;
+        assert!(game.flip_tableau_top(1));
+        assert_eq!(game.score, -52);
+        assert!(game.force_complete_foundations());
+        assert_eq!(game.score, -52 + 52 * 5);
+    }
+
+    #[test]
+    fn test_manual_flips_leave_uncovered_cards_face_down() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 7).unwrap();
+        game.manual_flips = true;
+        game.stock.cards.clear();
+        game.waste.cards.clear();
+        for pile in &mut game.tableaus {
+            pile.cards.clear();
+        }
+        let hidden = Card {
+            face_up: false,
+            ..face_up(Suit::Spades, Rank::Five)
+        };
+        game.tableaus[0].cards = vec![hidden, face_up(Suit::Hearts, Rank::Ace)];
+
+        assert!(game.move_tableau_top_to_any_foundation(0));
+        assert_eq!(game.score, 10);
+        assert!(game.top_tableau_face_down(0));
+        assert_eq!(game.auto_move_safe_to_foundations(), 0);
+        assert_eq!(
+            game.hint(),
+            Some(Hint {
+                from: PileRef::Tableau(0),
+                to: PileRef::Tableau(0),
+                cards: 1,
+            })
+        );
+        assert_eq!(game.moves, 1);
+        assert!(game.flip_tableau_top(0));
+        assert_eq!(game.score, 15);
+        // The player's own flip is a move; the automatic reveal below is not.
+        assert_eq!(game.moves, 2);
+
+        game.manual_flips = false;
+        game.tableaus[1].cards = vec![hidden, face_up(Suit::Hearts, Rank::Two)];
+        assert!(game.move_tableau_top_to_any_foundation(1));
+        assert!(!game.top_tableau_face_down(1));
+        assert_eq!(game.moves, 3);
+    }
+
+    #[test]
+    fn test_easy_variant_accepts_any_card_on_empty_columns() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 7).unwrap();
+        game.tableaus[0].cards.clear();
+        game.waste.cards.push(face_up(Suit::Clubs, Rank::Seven));
+        assert!(!game.can_accept_tableau_stack(0, &[face_up(Suit::Hearts, Rank::Nine)]));
+        assert!(!game.move_waste_to_tableau(0));
+
+        game.allow_any_to_empty = true;
+        assert!(game.can_accept_tableau_stack(0, &[face_up(Suit::Hearts, Rank::Nine)]));
+        assert!(game.move_waste_to_tableau(0));
+        assert_eq!(game.tableaus[0].cards, [face_up(Suit::Clubs, Rank::Seven)]);
+    }
+
+    #[test]
+    fn test_best_tableau_move_prefers_uncovering_and_occupied_columns() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 7).unwrap();
+        for pile in &mut game.tableaus {
+            pile.cards.clear();
+        }
+        game.tableaus[1].cards = vec![face_up(Suit::Spades, Rank::Ten)];
+        game.tableaus[2].cards = vec![face_up(Suit::Clubs, Rank::King)];
+        game.tableaus[3].cards = vec![face_up(Suit::Hearts, Rank::Queen)];
+        game.tableaus[5].cards = vec![
+            Card::new(Suit::Spades, Rank::Two),
+            face_up(Suit::Spades, Rank::Jack),
+            face_up(Suit::Diamonds, Rank::Ten),
+            face_up(Suit::Clubs, Rank::Nine),
+        ];
+        game.waste.cards.push(face_up(Suit::Spades, Rank::Jack));
+        assert_eq!(
+            game.best_tableau_move(None),
+            Some(Move::WasteToTableau { column: 3 })
+        );
+
+        // Double-clicking the nine takes the whole run along, since that turns up the two.
+        assert_eq!(
+            game.best_tableau_move(Some((5, 3))),
+            Some(Move::TableauToTableau {
+                from: 5,
+                count: 3,
+                to: 3
+            })
+        );
+        // Without a home for the run, the black nine alone has nowhere to go either.
+        game.tableaus[3].cards.clear();
+        assert_eq!(game.best_tableau_move(Some((5, 3))), None);
+        game.tableaus[5].cards.pop();
+        game.tableaus[5]
+            .cards
+            .push(face_up(Suit::Hearts, Rank::Nine));
+        assert_eq!(
+            game.best_tableau_move(Some((5, 3))),
+            Some(Move::TableauToTableau {
+                from: 5,
+                count: 1,
+                to: 1
+            })
+        );
+
+        // A king that is a whole column never moves to an empty one.
+        assert_eq!(game.best_tableau_move(Some((2, 0))), None);
+        assert_eq!(game.best_tableau_move(Some((5, 0))), None);
+    }
+
+    #[test]
+    fn test_vegas_cumulative_carries_balance() {
+        let mut game = GameState::new();
+        game.set_scoring_mode(ScoringMode::Vegas, true);
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 1).unwrap();
+        game.waste.cards.push(face_up(Suit::Hearts, Rank::Ace));
+        assert!(game.move_waste_to_any_foundation());
+        assert_eq!(game.score, -47);
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 2).unwrap();
+        assert_eq!(game.score, -99);
+
+        game.vegas_cumulative = false;
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 3).unwrap();
+        assert_eq!(game.score, -52);
+    }
+
+    #[test]
+    fn test_draw_three_stock_cycle() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 3)
+            .unwrap();
+        let counts = |game: &GameState| (game.stock.cards.len(), game.waste.cards.len());
+        assert_eq!(counts(&game), (24, 0));
+        let face = |card: &Card| (card.suit, card.rank);
+        let first_three: Vec<_> = game.stock.cards.iter().rev().take(3).map(face).collect();
+
+        // Each click turns three cards over; the last one turned is the one in play.
+        assert_eq!(game.stock_click(), StockAction::Drawn(3));
+        assert_eq!(counts(&game), (21, 3));
+        assert!(game.waste.cards.iter().all(|card| card.face_up));
+        let top = game.waste.cards.last().map(face);
+        assert_eq!(top, Some((Suit::Hearts, Rank::Ace)));
+        assert_eq!(top, Some(first_three[2]));
+
+        // Playing it uncovers the card turned just before it.
+        assert!(game.move_waste_to_foundation(0));
+        assert_eq!(counts(&game), (21, 2));
+        assert_eq!(game.score, 10);
+        assert_eq!(game.waste.cards.last().map(face), Some(first_three[1]));
+
+        for _ in 0..5 {
+            assert_eq!(game.stock_click(), StockAction::Drawn(3));
+        }
+        assert_eq!(counts(&game), (6, 17));
+        assert_eq!(
+            game.waste.cards.last().map(face),
+            Some((Suit::Clubs, Rank::Three))
+        );
+        assert!(game.move_waste_to_tableau(2));
+        assert_eq!(counts(&game), (6, 16));
+        assert_eq!(game.score, 10);
+
+        assert_eq!(game.stock_click(), StockAction::Drawn(3));
+        assert_eq!(game.stock_click(), StockAction::Drawn(3));
+        assert_eq!(counts(&game), (0, 22));
+
+        // Turning the waste over keeps its order, so the next pass starts with the two
+        // cards left from the first draw, and costs the Standard recycle penalty.
+        assert_eq!(game.stock_click(), StockAction::Recycled(22));
+        assert_eq!(counts(&game), (22, 0));
+        assert_eq!(game.score, 8);
+        assert_eq!(game.passes, 1);
+        assert!(game.stock.cards.iter().all(|card| !card.face_up));
+        assert_eq!(game.stock_click(), StockAction::Drawn(3));
+        let turned: Vec<_> = game.waste.cards.iter().map(face).collect();
+        assert_eq!(turned[..2], first_three[..2]);
+
+        // Twenty-two cards make seven full draws and a last one of a single card.
+        for _ in 0..6 {
+            assert_eq!(game.stock_click(), StockAction::Drawn(3));
+        }
+        assert_eq!(counts(&game), (1, 21));
+        assert_eq!(game.stock_click(), StockAction::Drawn(1));
+        assert_eq!(counts(&game), (0, 22));
+        assert_eq!(game.moves, 19);
+    }
+
+    #[test]
+    fn test_pass_limit_blocks_recycling() {
+        let mut game = GameState::new();
+        game.pass_limit = Some(1);
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 9)
+            .unwrap();
+        let draw_through = |game: &mut GameState| {
+            while !game.stock.cards.is_empty() {
+                assert!(matches!(game.stock_click(), StockAction::Drawn(_)));
+            }
+        };
+
+        draw_through(&mut game);
+        let waste = game.waste.cards.len();
+        assert_eq!(game.stock_click(), StockAction::Recycled(waste));
+        assert_eq!(game.passes, 1);
+        assert_eq!(game.passes_remaining(), Some(0));
+        let mut rewound = game.clone();
+        rewound.undo(Move::StockRecycle { count: waste }).unwrap();
+        assert_eq!(rewound.passes, 0);
+
+        draw_through(&mut game);
+        let before = game.clone();
+        assert_eq!(game.stock_click(), StockAction::NoOp);
+        assert_eq!(game, before);
+        // Hints and solved lines know the stock can't come round again.
+        assert_eq!(game.to_solver_position().recycles, Some(0));
+        assert!(!matches!(
+            game.hint(),
+            Some(Hint {
+                from: PileRef::Stock,
+                ..
+            })
+        ));
+
+        game.restart().unwrap();
+        assert_eq!(game.passes, 0);
+        assert_eq!(game.pass_limit, Some(1));
+    }
+}
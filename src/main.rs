@@ -2,24 +2,49 @@
 
 mod constants;
 mod engine;
+mod registry;
+mod settings;
 mod solver;
+mod sound;
+mod stats;
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    mem::size_of,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
-use std::{mem::size_of, time::Instant};
-
-use crate::engine::{Card, DrawMode, GameState, Rank, StockAction};
+use crate::engine::{
+    parse_seed, Card, CardColor, DrawMode, GameState, Hint, Move, MoveLog, Pile, PileRef, Rank,
+    ScoringMode, StockAction, Suit,
+};
+use crate::settings::{CardBack, CardSize, FeltColor, OutlineStyle, Settings, VictoryPhysics};
+use crate::solver::SolveResult;
+use crate::sound::Sound;
+use crate::stats::Stats;
 
-use windows::core::{w, PCWSTR};
+use windows::core::{w, PCWSTR, PWSTR};
 
-use windows::Win32::Foundation::{BOOL, COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Foundation::{
+    GlobalFree, BOOL, COLORREF, HANDLE, HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM,
+};
 
 use windows::Win32::Graphics::Gdi::{
-    AlphaBlend, BeginPaint, BitBlt, CreateCompatibleDC, CreateDIBSection, CreatePen,
-    CreateSolidBrush, DeleteDC, DeleteObject, DrawTextW, EndPaint, FillRect, GetStockObject,
-    InvalidateRect, RedrawWindow, RoundRect, SelectObject, SetBkMode, SetTextColor, AC_SRC_ALPHA,
-    AC_SRC_OVER, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, BLENDFUNCTION, DIB_RGB_COLORS, DT_CENTER,
-    DT_SINGLELINE, DT_TOP, DT_VCENTER, HBITMAP, HBRUSH, HDC, HGDIOBJ, HOLLOW_BRUSH, HPEN, HRGN,
-    PAINTSTRUCT, PS_SOLID, RDW_INVALIDATE, RDW_UPDATENOW, REDRAW_WINDOW_FLAGS, SRCCOPY,
-    TRANSPARENT,
+    AlphaBlend, BeginPaint, BitBlt, ClientToScreen, CreateCompatibleDC, CreateDIBSection,
+    CreateFontW, CreatePen, CreateSolidBrush, DeleteDC, DeleteObject, DrawTextW, EndPaint,
+    FillRect, GetMonitorInfoW, GetStockObject, InvalidateRect, MonitorFromRect, RedrawWindow,
+    RoundRect, ScreenToClient, SelectObject, SetBkMode, SetTextColor, AC_SRC_ALPHA, AC_SRC_OVER,
+    ANTIALIASED_QUALITY, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, BLENDFUNCTION, CLIP_DEFAULT_PRECIS,
+    DEFAULT_CHARSET, DIB_RGB_COLORS, DT_CENTER, DT_END_ELLIPSIS, DT_LEFT, DT_SINGLELINE, DT_TOP,
+    DT_VCENTER, FF_SWISS, FW_BOLD, HBITMAP, HBRUSH, HDC, HFONT, HGDIOBJ, HMONITOR, HOLLOW_BRUSH,
+    HPEN, HRGN, MONITORINFO, MONITOR_DEFAULTTOPRIMARY, OUT_DEFAULT_PRECIS, PAINTSTRUCT, PS_SOLID,
+    RDW_INVALIDATE, RDW_UPDATENOW, REDRAW_WINDOW_FLAGS, SRCCOPY, TRANSPARENT,
 };
 
 use windows::Win32::Graphics::Imaging::{
@@ -28,10 +53,18 @@ use windows::Win32::Graphics::Imaging::{
     WICDecodeOptions,
 };
 
-use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+use windows::Win32::System::Com::{CoCreateInstance, CoTaskMemFree, CLSCTX_INPROC_SERVER};
 use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
 
-use windows::Win32::System::Diagnostics::Debug::OutputDebugStringW;
+use windows::Win32::System::Diagnostics::Debug::{MessageBeep, OutputDebugStringW};
+
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+use windows::Win32::System::Ole::CF_UNICODETEXT;
+#[cfg(debug_assertions)]
+use windows::Win32::System::Threading::{GetCurrentProcess, GetGuiResources, GR_GDIOBJECTS};
 
 use windows::Win32::System::LibraryLoader::{
     FindResourceW, GetModuleHandleW, LoadResource, LockResource, SizeofResource,
@@ -44,32 +77,52 @@ use windows::Win32::System::Registry::{
 };
 
 use windows::Win32::UI::Controls::{
-    CreateStatusWindowW, InitCommonControlsEx, ICC_BAR_CLASSES, INITCOMMONCONTROLSEX,
-    SBARS_SIZEGRIP, SB_SETTEXTW,
+    CreateStatusWindowW, InitCommonControlsEx, BST_CHECKED, BST_UNCHECKED, EM_SETSEL,
+    ICC_BAR_CLASSES, INITCOMMONCONTROLSEX, NMHDR, NMMOUSE, NM_CLICK, SBARS_SIZEGRIP, SB_SETPARTS,
+    SB_SETTEXTW, TOOLTIPS_CLASSW, TTF_ABSOLUTE, TTF_TRACK, TTM_ADDTOOLW, TTM_TRACKACTIVATE,
+    TTM_TRACKPOSITION, TTM_UPDATETIPTEXTW, TTS_ALWAYSTIP, TTS_NOPREFIX, TTTOOLINFOW, WM_MOUSELEAVE,
+};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, ReleaseCapture, SetCapture, SetFocus, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT,
+    VK_1, VK_4, VK_DOWN, VK_ESCAPE, VK_LEFT, VK_RETURN, VK_RIGHT, VK_SHIFT, VK_SPACE, VK_TAB,
+    VK_UP,
 };
 
-use windows::Win32::UI::Input::KeyboardAndMouse::{ReleaseCapture, SetCapture};
+use windows::Win32::UI::Accessibility::NotifyWinEvent;
+
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, GetDpiForWindow, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::Shell::{FOLDERID_RoamingAppData, SHGetKnownFolderPath, KF_FLAG_DEFAULT};
 
 use windows::Win32::UI::WindowsAndMessaging::{
     CheckMenuItem, CreateWindowExW, DefWindowProcW, DestroyWindow, DialogBoxParamW,
-    DispatchMessageW, EndDialog, GetClientRect, GetMenu, GetMessageW, GetWindowLongPtrW,
-    GetWindowPlacement, GetWindowRect, KillTimer, LoadAcceleratorsW, LoadCursorW, LoadIconW,
-    LoadMenuW, PostQuitMessage, RegisterClassExW, SendMessageW, SetTimer, SetWindowLongPtrW,
-    SetWindowPos, ShowWindow, SystemParametersInfoW, TranslateAcceleratorW, TranslateMessage,
-    CS_DBLCLKS, CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT, GWLP_USERDATA, HACCEL, HCURSOR, HICON,
-    HMENU, HWND_TOP, IDCANCEL, IDC_ARROW, IDI_APPLICATION, IDOK, MF_BYCOMMAND, MF_CHECKED,
-    MF_UNCHECKED, MSG, SPI_GETWORKAREA, SWP_NOACTIVATE, SWP_NOZORDER, SW_SHOWMAXIMIZED,
-    SW_SHOWNORMAL, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WINDOWPLACEMENT, WINDOW_EX_STYLE,
-    WM_COMMAND, WM_CREATE, WM_CTLCOLORBTN, WM_CTLCOLORDLG, WM_CTLCOLORSTATIC, WM_DESTROY,
-    WM_ERASEBKGND, WM_INITDIALOG, WM_KEYDOWN, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP,
-    WM_MOUSEMOVE, WM_PAINT, WM_SIZE, WM_TIMER, WNDCLASSEXW, WNDCLASS_STYLES, WS_CHILD,
-    WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+    DispatchMessageW, EnableMenuItem, EndDialog, GetClientRect, GetDlgItemTextW, GetMenu,
+    GetMessageW, GetParent, GetWindowLongPtrW, GetWindowPlacement, GetWindowRect, IsWindowVisible,
+    KillTimer, LoadAcceleratorsW, LoadCursorW, LoadIconW, LoadMenuW, MessageBoxW, PostMessageW,
+    PostQuitMessage, RegisterClassExW, SendDlgItemMessageW, SendMessageW, SetDlgItemTextW,
+    SetTimer, SetWindowLongPtrW, SetWindowPos, ShowWindow, SystemParametersInfoW,
+    TranslateAcceleratorW, TranslateMessage, BM_GETCHECK, BM_SETCHECK, CB_ADDSTRING, CB_ERR,
+    CB_GETCURSEL, CB_SETCURSEL, CS_DBLCLKS, CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT,
+    EVENT_OBJECT_NAMECHANGE, GWLP_USERDATA, HACCEL, HCURSOR, HICON, HMENU, HWND_NOTOPMOST,
+    HWND_TOP, HWND_TOPMOST, IDCANCEL, IDC_ARROW, IDI_APPLICATION, IDOK, IDYES, MB_ICONQUESTION,
+    MB_OK, MB_OKCANCEL, MB_YESNO, MB_YESNOCANCEL, MF_BYCOMMAND, MF_CHECKED, MF_ENABLED, MF_GRAYED,
+    MF_UNCHECKED, MINMAXINFO, MSG, OBJID_CLIENT, SIZE_MINIMIZED, SPI_GETWORKAREA, SWP_NOACTIVATE,
+    SWP_NOMOVE, SWP_NOSIZE, SWP_NOZORDER, SW_HIDE, SW_SHOW, SW_SHOWMAXIMIZED, SW_SHOWNORMAL,
+    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WA_INACTIVE, WHEEL_DELTA, WINDOWPLACEMENT,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WM_ACTIVATE, WM_APP, WM_COMMAND, WM_CREATE, WM_CTLCOLORBTN,
+    WM_CTLCOLORDLG, WM_CTLCOLORSTATIC, WM_DESTROY, WM_DPICHANGED, WM_ERASEBKGND, WM_GETMINMAXINFO,
+    WM_INITDIALOG, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP,
+    WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_NOTIFY, WM_PAINT, WM_RBUTTONUP, WM_SIZE, WM_TIMER, WNDCLASSEXW,
+    WNDCLASS_STYLES, WS_CHILD, WS_EX_TOPMOST, WS_OVERLAPPEDWINDOW, WS_POPUP, WS_VISIBLE,
 };
 
 const APP_TITLE: PCWSTR = w!("Solitaire");
 const CLASS_NAME: PCWSTR = w!("SolitaireWindowClass");
 
 const WINDOW_BOUNDS_VALUE: &str = "WindowBounds";
+const SAVED_GAME_FILE: &str = "SavedGame.json";
+const AUTOSAVE_FILE: &str = "AutoSave.json";
+const MOVE_LOG_FILE: &str = "Moves.txt";
 const WINDOW_MIN_WIDTH: i32 = 640;
 const WINDOW_MIN_HEIGHT: i32 = 480;
 #[inline]
@@ -86,6 +139,10 @@ fn loword(value: WPARAM) -> u16 {
     (value.0 & 0xFFFF) as u16
 }
 
+fn hiword(value: WPARAM) -> u16 {
+    ((value.0 >> 16) & 0xFFFF) as u16
+}
+
 fn debug_log(message: &str) {
     let wide = to_wide(message);
     unsafe {
@@ -93,6 +150,23 @@ fn debug_log(message: &str) {
     }
 }
 
+/// Per-user data directory (`%APPDATA%\\<company>\\<product>`), created on demand.
+fn app_data_dir() -> Option<PathBuf> {
+    let base = unsafe {
+        let raw =
+            SHGetKnownFolderPath(&FOLDERID_RoamingAppData, KF_FLAG_DEFAULT, HANDLE::default())
+                .ok()?;
+        let path = raw.to_string();
+        CoTaskMemFree(Some(raw.0 as *const _));
+        path.ok()?
+    };
+    let dir = PathBuf::from(base)
+        .join(constants::COMPANY_NAME)
+        .join(constants::PRODUCT_NAME);
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
 fn lparam_point(lparam: LPARAM) -> (i32, i32) {
     let raw = lparam.0 as u32;
     let x = (raw & 0xFFFF) as i16 as i32;
@@ -109,11 +183,72 @@ const CARD_SPRITE_COLS: i32 = 13;
 const CARD_SPRITE_ROWS: i32 = 4;
 const DEFAULT_CARD_WIDTH: i32 = 120;
 const DEFAULT_CARD_HEIGHT: i32 = 168;
+/// Floor on the layout scale so a tiny window still draws recognisable cards.
+const MIN_LAYOUT_SCALE: f32 = 0.35;
 const MAX_TABLEAU_DRAW_CARDS: i32 = 19;
+const WASTE_FAN_CARDS: usize = 3;
 const FOUNDATION_COLUMNS: usize = 4;
 const TABLEAU_COLUMNS: usize = 7;
 const DRAG_THRESHOLD: i32 = 4;
+const DRAG_GHOST_ALPHA: u8 = 110;
+const STOCK_PREVIEW_ALPHA: u8 = 90;
+const PEEK_FACE_ALPHA: u8 = 120;
 const VICTORY_TIMER_ID: usize = 1;
+const CLOCK_TIMER_ID: usize = 2;
+/// Oldest snapshots are dropped beyond this many undo steps.
+const UNDO_LIMIT: usize = 200;
+const AUTO_FINISH_TIMER_ID: usize = 3;
+const AUTO_FINISH_STEP_MS: u32 = 120;
+const AUTO_FINISH_BUDGET_MS: u64 = 1000;
+const TWEEN_TIMER_ID: usize = 4;
+const TWEEN_FRAME_MS: u32 = 15;
+const TWEEN_DURATION: Duration = Duration::from_millis(120);
+/// Delay between successive cards of one batch, e.g. an auto-play sweep.
+const TWEEN_STAGGER: Duration = Duration::from_millis(60);
+/// Gap between waste cards leaving for the stock when the waste is turned back over.
+const RECYCLE_STAGGER: Duration = Duration::from_millis(40);
+const DEAL_TIMER_ID: usize = 5;
+const DEAL_FRAME_MS: u32 = 15;
+const DEAL_EMIT_INTERVAL: f32 = 0.035;
+const DEAL_FLIGHT_SECS: f32 = 0.2;
+const FLIP_TIMER_ID: usize = 6;
+const FLIP_FRAME_MS: u32 = 15;
+const FLIP_DURATION: Duration = Duration::from_millis(160);
+const HOVER_TIMER_ID: usize = 7;
+const HOVER_DWELL_MS: u32 = 600;
+const AUTO_DEAL_TIMER_ID: usize = 8;
+/// Pause between a win's celebration ending and the next deal, for taking in the finished table.
+const AUTO_DEAL_DELAY_MS: u32 = 2500;
+/// Posted by the solvable-deal worker; `lparam` owns a `Box<Option<GameState>>`.
+const WM_SOLVABLE_DEAL_READY: u32 = WM_APP + 1;
+/// Posted once a real win's celebration is over, to show the summary outside the timer callback.
+const WM_SHOW_WIN_SUMMARY: u32 = WM_APP + 2;
+/// Posted by the winnability worker; `wparam` is the check's generation, `lparam` is nonzero
+/// when the position was proven winnable.
+const WM_WINNABLE_CHECKED: u32 = WM_APP + 3;
+const WINNABLE_CHECK_BUDGET_MS: u64 = 400;
+/// Positions a solver worker expands between looks for a newer request.
+const SOLVER_STEP_NODES: u64 = 512;
+/// Posted by the Go Back search; `wparam` is the search's generation, and `lparam` is how
+/// many undos reach the last winnable position, or -1 when none could be proven winnable.
+const WM_REWIND_FOUND: u32 = WM_APP + 4;
+/// Solver time spent on each position the Go Back search looks at.
+const REWIND_CHECK_BUDGET_MS: u64 = 300;
+/// Posted by the difficulty worker; `wparam` is the rating's generation and `lparam` indexes
+/// `Difficulty::ALL`, which lists the ratings in declaration order.
+const WM_DIFFICULTY_RATED: u32 = WM_APP + 5;
+/// Solver time spent rating each new deal.
+const DIFFICULTY_BUDGET_MS: u64 = 2000;
+/// Most positions the solver may expand on a deal still called easy, and medium.
+const EASY_MAX_NODES: u64 = 5_000;
+const MEDIUM_MAX_NODES: u64 = 200_000;
+/// Width of the status bar's finish button at 96 DPI.
+const WINNABLE_PART_WIDTH: i32 = 170;
+/// Room for the seed at 96 DPI, enough for a full 64-bit value in hex.
+const SEED_PART_WIDTH: i32 = 180;
+/// Narrowest the score line gets at 96 DPI; the announcements give way first.
+const SCORE_PART_MIN_WIDTH: i32 = 280;
+const SOLVABLE_DEAL_ATTEMPTS: usize = 60;
 const ANIM_EMIT_INTERVAL: f32 = 0.16;
 const ANIM_FIXED_DT: f32 = 0.02;
 const ANIM_GRAVITY: f32 = 3000.0;
@@ -167,7 +302,36 @@ impl Drop for ComApartment {
     }
 }
 
-fn load_window_bounds() -> Option<(RECT, bool)> {
+/// Baseline DPI that Windows treats as 100% scaling.
+const BASE_DPI: u32 = 96;
+
+/// Converts a length measured at `from_dpi` to the same physical size at `to_dpi`.
+fn scale_for_dpi(value: i32, from_dpi: u32, to_dpi: u32) -> i32 {
+    if from_dpi == 0 || from_dpi == to_dpi {
+        return value;
+    }
+    ((value as i64 * to_dpi as i64 + from_dpi as i64 / 2) / from_dpi as i64) as i32
+}
+
+/// The monitor holding most of `rect`, or the primary one when `rect` is on no monitor at all,
+/// e.g. because the screen it was saved on has since been disconnected.
+fn monitor_for_rect(rect: &RECT) -> HMONITOR {
+    unsafe { MonitorFromRect(rect, MONITOR_DEFAULTTOPRIMARY) }
+}
+
+/// Effective DPI of the monitor that will hold most of `rect`.
+fn monitor_dpi(rect: &RECT) -> Option<u32> {
+    unsafe {
+        let monitor = monitor_for_rect(rect);
+        let (mut dpi_x, mut dpi_y) = (0u32, 0u32);
+        GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y).ok()?;
+        (dpi_x != 0).then_some(dpi_x)
+    }
+}
+
+/// Saved bounds are `[left, top, width, height, maximized, dpi]`. Older saves lack the DPI,
+/// which reads back as `None`.
+fn load_window_bounds() -> Option<(RECT, bool, Option<u32>)> {
     unsafe {
         let subkey = to_wide(constants::REGISTRY_BASE_KEY);
         let mut hkey = HKEY::default();
@@ -183,7 +347,7 @@ fn load_window_bounds() -> Option<(RECT, bool)> {
             return None;
         }
 
-        let mut data = [0i32; 5];
+        let mut data = [0i32; 6];
         let mut data_size = (data.len() * size_of::<i32>()) as u32;
         let mut value_type = REG_BINARY;
         let value_name = to_wide(WINDOW_BOUNDS_VALUE);
@@ -216,8 +380,10 @@ fn load_window_bounds() -> Option<(RECT, bool)> {
             right: left + width,
             bottom: top + height,
         };
-        let maximized = data.get(4).copied().unwrap_or(0) != 0;
-        Some((rect, maximized))
+        let maximized = data_size >= (5 * size_of::<i32>()) as u32 && data[4] != 0;
+        let dpi =
+            (data_size >= (6 * size_of::<i32>()) as u32 && data[5] > 0).then_some(data[5] as u32);
+        Some((rect, maximized, dpi))
     }
 }
 
@@ -248,6 +414,7 @@ fn save_window_bounds(hwnd: HWND) {
             } else {
                 0
             },
+            GetDpiForWindow(hwnd) as i32,
         ];
 
         let subkey = to_wide(constants::REGISTRY_BASE_KEY);
@@ -283,9 +450,17 @@ fn save_window_bounds(hwnd: HWND) {
 }
 
 fn apply_saved_window_bounds(hwnd: HWND) {
-    if let Some((mut rect, maximized)) = load_window_bounds() {
-        let mut width = (rect.right - rect.left).max(WINDOW_MIN_WIDTH);
-        let mut height = (rect.bottom - rect.top).max(WINDOW_MIN_HEIGHT);
+    if let Some((mut rect, maximized, saved_dpi)) = load_window_bounds() {
+        let mut width = rect.right - rect.left;
+        let mut height = rect.bottom - rect.top;
+        // Keep the physical size when the target monitor scales differently from the one
+        // the bounds were saved on.
+        if let (Some(from), Some(to)) = (saved_dpi, monitor_dpi(&rect)) {
+            width = scale_for_dpi(width, from, to);
+            height = scale_for_dpi(height, from, to);
+        }
+        let mut width = width.max(WINDOW_MIN_WIDTH);
+        let mut height = height.max(WINDOW_MIN_HEIGHT);
         clamp_rect_to_work_area(&mut rect, &mut width, &mut height);
 
         unsafe {
@@ -310,84 +485,270 @@ fn apply_saved_window_bounds(hwnd: HWND) {
     }
 }
 
-fn clamp_rect_to_work_area(rect: &mut RECT, width: &mut i32, height: &mut i32) {
+/// Work area of the monitor `rect` lands on (see `monitor_for_rect`), falling back to the
+/// primary monitor's as reported by `SPI_GETWORKAREA`.
+fn work_area_for(rect: &RECT) -> Option<RECT> {
     unsafe {
+        let mut info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if GetMonitorInfoW(monitor_for_rect(rect), &mut info).as_bool() {
+            return Some(info.rcWork);
+        }
         let mut work = RECT::default();
-        if SystemParametersInfoW(
+        SystemParametersInfoW(
             SPI_GETWORKAREA,
             0,
             Some(&mut work as *mut _ as *mut _),
             SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
         )
-        .is_ok()
-        {
-            let work_width = work.right - work.left;
-            if work_width > 0 {
-                let min_width = WINDOW_MIN_WIDTH.min(work_width);
-                *width = (*width).clamp(min_width, work_width);
-            }
-
-            let work_height = work.bottom - work.top;
-            if work_height > 0 {
-                let min_height = WINDOW_MIN_HEIGHT.min(work_height);
-                *height = (*height).clamp(min_height, work_height);
-            }
-
-            let max_left = work.right - *width;
-            let max_top = work.bottom - *height;
-            rect.left = rect.left.clamp(work.left, max_left.max(work.left));
-            rect.top = rect.top.clamp(work.top, max_top.max(work.top));
-        }
+        .ok()?;
+        Some(work)
     }
+}
 
+fn clamp_rect_to_work_area(rect: &mut RECT, width: &mut i32, height: &mut i32) {
+    if let Some(work) = work_area_for(rect) {
+        clamp_rect_to(rect, width, height, work);
+    }
     rect.right = rect.left + *width;
     rect.bottom = rect.top + *height;
 }
 
+/// Shrinks the window to fit `work` and slides it inside, keeping it on that monitor.
+fn clamp_rect_to(rect: &mut RECT, width: &mut i32, height: &mut i32, work: RECT) {
+    let work_width = work.right - work.left;
+    if work_width > 0 {
+        let min_width = WINDOW_MIN_WIDTH.min(work_width);
+        *width = (*width).clamp(min_width, work_width);
+    }
+
+    let work_height = work.bottom - work.top;
+    if work_height > 0 {
+        let min_height = WINDOW_MIN_HEIGHT.min(work_height);
+        *height = (*height).clamp(min_height, work_height);
+    }
+
+    let max_left = work.right - *width;
+    let max_top = work.bottom - *height;
+    rect.left = rect.left.clamp(work.left, max_left.max(work.left));
+    rect.top = rect.top.clamp(work.top, max_top.max(work.top));
+}
+
+/// Puts a check mark on menu item `id`, or takes it off.
+unsafe fn check_menu_item(menu: HMENU, id: u16, checked: bool) {
+    let mark = if checked { MF_CHECKED } else { MF_UNCHECKED };
+    let _ = CheckMenuItem(menu, id as u32, (MF_BYCOMMAND | mark).0);
+}
+
+/// Brings every menu that mirrors the game in progress (draw, scoring, pass limit and
+/// rules) up to date, for when the game is swapped for another one.
+unsafe fn refresh_rule_menus(hwnd: HWND, game: &GameState) {
+    update_draw_menu(hwnd, game.draw_mode);
+    update_scoring_menu(hwnd, game);
+    update_pass_limit_menu(hwnd, game);
+    update_rules_menu(hwnd, game);
+}
+
 unsafe fn update_draw_menu(hwnd: HWND, draw_mode: DrawMode) {
     let menu = GetMenu(hwnd);
     if menu.0 != 0 {
-        let draw1_flags = MF_BYCOMMAND.0
-            | if matches!(draw_mode, DrawMode::DrawOne) {
-                MF_CHECKED.0
-            } else {
-                MF_UNCHECKED.0
-            };
-        let draw3_flags = MF_BYCOMMAND.0
-            | if matches!(draw_mode, DrawMode::DrawThree) {
-                MF_CHECKED.0
-            } else {
-                MF_UNCHECKED.0
-            };
-        let _ = CheckMenuItem(menu, constants::IDM_GAME_DRAW1 as u32, draw1_flags);
-        let _ = CheckMenuItem(menu, constants::IDM_GAME_DRAW3 as u32, draw3_flags);
+        let items = [
+            (constants::IDM_GAME_DRAW1, DrawMode::DrawOne),
+            (constants::IDM_GAME_DRAW2, DrawMode::DrawTwo),
+            (constants::IDM_GAME_DRAW3, DrawMode::DrawThree),
+        ];
+        for (id, mode) in items {
+            check_menu_item(menu, id, mode == draw_mode);
+        }
+    }
+}
+
+unsafe fn update_scoring_menu(hwnd: HWND, game: &GameState) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        let vegas = game.scoring_mode == ScoringMode::Vegas;
+        let items = [
+            (constants::IDM_GAME_SCORING_STANDARD, !vegas),
+            (
+                constants::IDM_GAME_SCORING_VEGAS,
+                vegas && !game.vegas_cumulative,
+            ),
+            (
+                constants::IDM_GAME_SCORING_VEGAS_CUMULATIVE,
+                vegas && game.vegas_cumulative,
+            ),
+        ];
+        for (id, checked) in items {
+            check_menu_item(menu, id, checked);
+        }
+    }
+}
+
+unsafe fn update_preferences_menu(hwnd: HWND, settings: &Settings) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        let items = [
+            (constants::IDM_GAME_ANIMATE_DEAL, settings.animate_deal),
+            (constants::IDM_GAME_ANIMATE_FLIPS, settings.animate_flips),
+            (constants::IDM_GAME_SOUND, settings.sound),
+            (
+                constants::IDM_GAME_HIGHLIGHT_MOVES,
+                settings.highlight_moves,
+            ),
+            (constants::IDM_GAME_LEFT_HANDED, settings.left_handed),
+            (constants::IDM_GAME_ANNOUNCE, settings.announce),
+            (constants::IDM_GAME_CARD_SHADOWS, settings.card_shadows),
+            (constants::IDM_GAME_REWIND_ASSIST, settings.rewind_assist),
+            (
+                constants::IDM_GAME_RESHUFFLE_ASSIST,
+                settings.reshuffle_assist,
+            ),
+            (constants::IDM_GAME_STOCK_BADGE, settings.stock_count_badge),
+            (constants::IDM_GAME_STOCK_PREVIEW, settings.stock_preview),
+            (
+                constants::IDM_GAME_VICTORY_ANIMATION,
+                settings.victory_animation,
+            ),
+            (constants::IDM_GAME_AUTO_DEAL, settings.auto_deal),
+            (constants::IDM_VIEW_ALWAYS_ON_TOP, settings.always_on_top),
+            (constants::IDM_VIEW_COMPACT, settings.compact),
+            (constants::IDM_VIEW_SCORE_OVERLAY, settings.score_overlay),
+        ];
+        for (id, checked) in items {
+            check_menu_item(menu, id, checked);
+        }
+        let rewind = if settings.rewind_assist {
+            MF_ENABLED
+        } else {
+            MF_GRAYED
+        };
+        let _ = EnableMenuItem(
+            menu,
+            constants::IDM_GAME_REWIND_WINNABLE as u32,
+            MF_BYCOMMAND | rewind,
+        );
+    }
+}
+
+/// The default depends on whether the sprite sheet loaded, so this is kept apart from
+/// `update_preferences_menu`.
+unsafe fn update_corner_indices_menu(hwnd: HWND, state: &WindowState) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        let checked = state.settings.corner_indices_enabled(state.card.is_some());
+        check_menu_item(menu, constants::IDM_GAME_CORNER_INDICES, checked);
+    }
+}
+
+const PASS_LIMIT_MENU: [(u16, Option<u32>); 3] = [
+    (constants::IDM_GAME_PASSES_UNLIMITED, None),
+    (constants::IDM_GAME_PASSES_ONE, Some(1)),
+    (constants::IDM_GAME_PASSES_THREE, Some(3)),
+];
+
+unsafe fn update_pass_limit_menu(hwnd: HWND, game: &GameState) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        for (id, limit) in PASS_LIMIT_MENU {
+            check_menu_item(menu, id, limit == game.pass_limit);
+        }
+    }
+}
+
+/// Checks the rule toggles that belong to the game in progress rather than the settings.
+unsafe fn update_rules_menu(hwnd: HWND, game: &GameState) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        let items = [
+            (constants::IDM_GAME_AUTO_FLIP, !game.manual_flips),
+            (constants::IDM_GAME_ANY_TO_EMPTY, game.allow_any_to_empty),
+        ];
+        for (id, checked) in items {
+            check_menu_item(menu, id, checked);
+        }
+    }
+}
+
+const FELT_MENU: [(u16, FeltColor); 4] = [
+    (constants::IDM_GAME_FELT_GREEN, FeltColor::Green),
+    (constants::IDM_GAME_FELT_BLUE, FeltColor::Blue),
+    (constants::IDM_GAME_FELT_BURGUNDY, FeltColor::Burgundy),
+    (constants::IDM_GAME_FELT_CHARCOAL, FeltColor::Charcoal),
+];
+
+unsafe fn update_felt_menu(hwnd: HWND, felt: FeltColor) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        for (id, color) in FELT_MENU {
+            check_menu_item(menu, id, color == felt);
+        }
+    }
+}
+
+fn felt_color(felt: FeltColor) -> COLORREF {
+    match felt {
+        FeltColor::Green => rgb(0, 128, 0),
+        FeltColor::Blue => rgb(16, 72, 140),
+        FeltColor::Burgundy => rgb(112, 24, 40),
+        FeltColor::Charcoal => rgb(54, 58, 62),
+    }
+}
+
+fn felt_slot_color(felt: FeltColor) -> COLORREF {
+    match felt {
+        FeltColor::Green => rgb(8, 96, 24),
+        FeltColor::Blue => rgb(10, 50, 104),
+        FeltColor::Burgundy => rgb(80, 14, 28),
+        FeltColor::Charcoal => rgb(36, 40, 44),
+    }
+}
+
+/// Drop shadow under each card, darker again than the empty slots.
+fn felt_shadow_color(felt: FeltColor) -> COLORREF {
+    match felt {
+        FeltColor::Green => rgb(4, 52, 12),
+        FeltColor::Blue => rgb(6, 26, 58),
+        FeltColor::Burgundy => rgb(44, 6, 14),
+        FeltColor::Charcoal => rgb(16, 18, 20),
+    }
+}
+
+/// Swaps the background brush for `felt` and remembers the choice.
+fn change_felt(hwnd: HWND, state: &mut WindowState, felt: FeltColor) {
+    if state.settings.felt == felt && state.bg_brush.0 != 0 {
+        return;
+    }
+    unsafe {
+        let brush = CreateSolidBrush(felt_color(felt));
+        if brush.0 == 0 {
+            return;
+        }
+        if state.bg_brush.0 != 0 {
+            let _ = DeleteObject(state.bg_brush);
+        }
+        state.bg_brush = brush;
+        update_felt_menu(hwnd, felt);
     }
+    state.settings.felt = felt;
+    settings::save_settings(&state.settings);
+    state.layout_metrics = None;
+    force_redraw(hwnd);
 }
 
 unsafe fn update_victory_menu(hwnd: HWND, style: VictoryStyle) {
     let menu = GetMenu(hwnd);
     if menu.0 != 0 {
-        let classic_flags = MF_BYCOMMAND.0
-            | if matches!(style, VictoryStyle::Classic) {
-                MF_CHECKED.0
-            } else {
-                MF_UNCHECKED.0
-            };
-        let modern_flags = MF_BYCOMMAND.0
-            | if matches!(style, VictoryStyle::Modern) {
-                MF_CHECKED.0
-            } else {
-                MF_UNCHECKED.0
-            };
-        let _ = CheckMenuItem(
+        check_menu_item(
             menu,
-            constants::IDM_GAME_VICTORY_CLASSIC as u32,
-            classic_flags,
+            constants::IDM_GAME_VICTORY_CLASSIC,
+            matches!(style, VictoryStyle::Classic),
         );
-        let _ = CheckMenuItem(
+        check_menu_item(
             menu,
-            constants::IDM_GAME_VICTORY_MODERN as u32,
-            modern_flags,
+            constants::IDM_GAME_VICTORY_MODERN,
+            matches!(style, VictoryStyle::Modern),
         );
     }
 }
@@ -397,19 +758,54 @@ fn update_status_bar(state: &mut WindowState) {
         return;
     }
 
-    let draw_label = match state.game.draw_mode {
-        DrawMode::DrawOne => "Draw 1",
-        DrawMode::DrawThree => "Draw 3",
-    };
+    if state.difficulty_deal != Some(DealKey::of(&state.game)) {
+        start_difficulty_rating(state);
+    }
+    if state
+        .rewind_from
+        .as_ref()
+        .is_some_and(|from| *from != state.game)
+    {
+        cancel_rewind_search(state);
+    }
+
+    let draw_label = format!("Draw {}", state.game.draw_mode.count());
 
-    let text = format!(
-        "{}   Stock: {}   Waste: {}   Score: {}   Moves: {}",
+    let score = format_score(&state.game);
+    let passes = match state.game.passes_remaining() {
+        Some(left) => format!("   Redeals left: {left}"),
+        None => String::new(),
+    };
+    let elapsed = state.clock.elapsed().as_secs();
+    let mut text = format!(
+        "{}   Stock: {}   Waste: {}{}   Score: {}   Moves: {}   Time: {:02}:{:02}",
         draw_label,
         state.game.stock_count(),
         state.game.waste_count(),
-        state.game.score,
-        state.game.moves
+        passes,
+        score,
+        state.game.moves,
+        elapsed / 60,
+        elapsed % 60,
     );
+    if state.game.rng_seed != 0 {
+        let rating = state.difficulty.map_or("Analyzing...", Difficulty::label);
+        text.push_str("   Difficulty: ");
+        text.push_str(rating);
+    }
+    let replay = state
+        .replay
+        .as_ref()
+        .map(|replay| format!("Replay: move {} of {}", replay.position, replay.moves.len()));
+    let note = state
+        .status_note
+        .clone()
+        .or(replay)
+        .or_else(|| dead_end_note(state));
+    if let Some(note) = note {
+        text.push_str("   ");
+        text.push_str(&note);
+    }
 
     let wide = to_wide(&text);
     unsafe {
@@ -420,6 +816,224 @@ fn update_status_bar(state: &mut WindowState) {
             LPARAM(wide.as_ptr() as isize),
         );
     }
+
+    if let Some(part) = seed_part(&state.settings) {
+        let seed = to_wide(&format!("Seed: {}", format_seed(state.game.rng_seed)));
+        unsafe {
+            SendMessageW(
+                state.status,
+                SB_SETTEXTW,
+                WPARAM(part),
+                LPARAM(seed.as_ptr() as isize),
+            );
+        }
+    }
+
+    // Mid-drag the game is missing the cards in hand, so leave the last verdict standing
+    // until they land.
+    if state.drag.is_none() && state.winnable_checked.as_ref() != Some(&state.game) {
+        start_winnable_check(state);
+    }
+    let button = to_wide(if state.winnable {
+        "Winnable: click to finish"
+    } else {
+        ""
+    });
+    unsafe {
+        SendMessageW(
+            state.status,
+            SB_SETTEXTW,
+            WPARAM(winnable_part(&state.settings)),
+            LPARAM(button.as_ptr() as isize),
+        );
+    }
+}
+
+/// Share of the status bar given to screen reader announcements when they are on.
+const ANNOUNCE_PART_DIVISOR: i32 = 3;
+
+/// Splits the status bar into the score line, the announcements when they are on, the seed
+/// when it is shown, and the finish button at the right-hand end.
+fn layout_status_parts(state: &WindowState) {
+    if state.status.0 == 0 {
+        return;
+    }
+    unsafe {
+        let mut client = RECT::default();
+        let _ = GetClientRect(state.status, &mut client);
+        let parts = status_part_edges(
+            client.right - client.left,
+            scale_for_dpi(WINNABLE_PART_WIDTH, BASE_DPI, state.dpi),
+            scale_for_dpi(SEED_PART_WIDTH, BASE_DPI, state.dpi),
+            scale_for_dpi(SCORE_PART_MIN_WIDTH, BASE_DPI, state.dpi),
+            &state.settings,
+        );
+        SendMessageW(
+            state.status,
+            SB_SETPARTS,
+            WPARAM(parts.len()),
+            LPARAM(parts.as_ptr() as isize),
+        );
+    }
+}
+
+/// Right edges of the status bar parts for a bar `width` pixels wide, ending with -1 for the
+/// finish button, which takes whatever is left. The score line keeps at least `score_min`
+/// pixels, so on a narrow bar the announcements and then the seed shrink, down to nothing
+/// if need be.
+fn status_part_edges(
+    width: i32,
+    button: i32,
+    seed: i32,
+    score_min: i32,
+    settings: &Settings,
+) -> Vec<i32> {
+    let button_left = width - button;
+    let seed_left = if settings.show_seed {
+        (button_left - seed).max(score_min.min(button_left))
+    } else {
+        button_left
+    };
+    let mut parts = Vec::with_capacity(4);
+    if settings.announce {
+        let announce = width / ANNOUNCE_PART_DIVISOR;
+        parts.push((seed_left - announce).max(score_min.min(seed_left)));
+    }
+    parts.push(seed_left);
+    if settings.show_seed {
+        parts.push(button_left);
+    }
+    parts.push(-1);
+    parts
+}
+
+/// Status bar part that shows the seed, if it is shown at all.
+fn seed_part(settings: &Settings) -> Option<usize> {
+    settings.show_seed.then_some(1 + settings.announce as usize)
+}
+
+/// Status bar part that acts as the finish button.
+fn winnable_part(settings: &Settings) -> usize {
+    1 + settings.announce as usize + settings.show_seed as usize
+}
+
+/// What the keyboard focus rests on, phrased for a screen reader.
+fn describe_focus(game: &GameState, target: HitTarget) -> Option<String> {
+    let name = |card: Option<&Card>| card.map_or_else(|| "empty".to_string(), card_name);
+    Some(match target {
+        HitTarget::Stock => match game.stock_count() {
+            0 => "Focus: stock, empty".to_string(),
+            count => format!("Focus: stock, {count} cards"),
+        },
+        HitTarget::Waste => format!("Focus: waste, {}", name(game.waste.cards.last())),
+        HitTarget::Foundation(index) => format!(
+            "Focus: foundation {}, {}",
+            index + 1,
+            name(game.foundations.get(index)?.cards.last())
+        ),
+        HitTarget::Tableau {
+            column,
+            card_index: Some(index),
+        } => {
+            let card = game.tableau_card(column, index)?;
+            if card.face_up {
+                format!("Focus: {}, column {}", card_name(card), column + 1)
+            } else {
+                format!("Focus: face-down card, column {}", column + 1)
+            }
+        }
+        HitTarget::Tableau {
+            column,
+            card_index: None,
+        } => format!("Focus: empty column {}", column + 1),
+        HitTarget::None => return None,
+    })
+}
+
+/// Spells out the moves that took `before` to `after`, e.g. "Moved Queen of Hearts to
+/// foundation. Turned up Three of Clubs". `None` when nothing was played, as after a deal.
+fn describe_moves(before: &GameState, after: &GameState) -> Option<String> {
+    let played = after.move_log.get(before.move_log.len()..)?;
+    let mut game = before.clone();
+    let mut lines = Vec::new();
+    for &mv in played {
+        let moved = |card: Option<&Card>, to: &str| {
+            card.map(|card| format!("Moved {} to {to}", card_name(card)))
+        };
+        let line = match mv {
+            Move::StockDraw { .. } => None,
+            Move::StockRecycle { .. } => Some("Turned the waste over".to_string()),
+            Move::Reshuffle { .. } => Some("Reshuffled the stock".to_string()),
+            Move::WasteToFoundation { .. } => moved(game.waste.cards.last(), "foundation"),
+            Move::WasteToTableau { column } => {
+                moved(game.waste.cards.last(), &format!("column {}", column + 1))
+            }
+            Move::TableauToFoundation { column, .. } => moved(
+                game.tableau_column(column).and_then(|cards| cards.last()),
+                "foundation",
+            ),
+            Move::TableauToTableau { from, count, to } => moved(
+                game.tableau_len(from)
+                    .checked_sub(count)
+                    .and_then(|start| game.tableau_card(from, start)),
+                &format!("column {}", to + 1),
+            ),
+            Move::FoundationToTableau { foundation, column } => moved(
+                game.foundations
+                    .get(foundation)
+                    .and_then(|pile| pile.cards.last()),
+                &format!("column {}", column + 1),
+            ),
+            Move::FlipTableauTop { .. } => None,
+        };
+        game.apply(mv).ok()?;
+        let line = line.or_else(|| match mv {
+            Move::StockDraw { .. } => game
+                .waste
+                .cards
+                .last()
+                .map(|card| format!("Drew {}", card_name(card))),
+            Move::FlipTableauTop { column } => game
+                .tableau_column(column)
+                .and_then(|cards| cards.last())
+                .map(|card| format!("Turned up {}", card_name(card))),
+            _ => None,
+        });
+        lines.extend(line);
+    }
+    (!lines.is_empty()).then(|| lines.join(". "))
+}
+
+/// Nudge shown once the board has run out of useful moves. Stays quiet while a deal is still
+/// flying out or a win is being celebrated.
+fn dead_end_note(state: &WindowState) -> Option<String> {
+    let busy = state.win_anim.is_some() || state.deal_anim.is_some() || state.game.is_won();
+    if busy || state.game.has_any_legal_move() {
+        None
+    } else if state.settings.reshuffle_assist && state.game.can_reshuffle() {
+        Some("No moves left: click the stock to reshuffle it".to_string())
+    } else {
+        Some(format!(
+            "No moves left: undo or start a new game ({})",
+            foundation_progress(state.best_foundation)
+        ))
+    }
+}
+
+/// How far a deal got, for the loss and new-game messages.
+fn foundation_progress(cards: usize) -> String {
+    match cards {
+        1 => "best: 1 card home".to_string(),
+        _ => format!("best: {cards} cards home"),
+    }
+}
+
+fn format_score(game: &GameState) -> String {
+    match game.scoring_mode {
+        ScoringMode::Standard => game.score.to_string(),
+        ScoringMode::Vegas if game.score < 0 => format!("-${}", -game.score),
+        ScoringMode::Vegas => format!("${}", game.score),
+    }
 }
 
 fn request_redraw(hwnd: HWND) {
@@ -451,10 +1065,130 @@ impl Default for VictoryStyle {
     }
 }
 
+/// Elapsed play time. Starts on the first move after a deal, freezes once the game
+/// is won, and does not advance while the window is inactive.
+#[derive(Default)]
+struct GameClock {
+    banked: Duration,
+    resumed_at: Option<Instant>,
+    started: bool,
+    stopped: bool,
+    paused: bool,
+    /// Paused by the player, as opposed to the window losing focus.
+    held: bool,
+}
+
+impl GameClock {
+    fn reset(&mut self) {
+        *self = GameClock {
+            paused: self.paused,
+            ..GameClock::default()
+        };
+    }
+
+    fn start(&mut self) {
+        if !self.started {
+            self.started = true;
+            self.sync();
+        }
+    }
+
+    fn stop(&mut self) {
+        self.stopped = true;
+        self.sync();
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.sync();
+    }
+
+    fn set_held(&mut self, held: bool) {
+        self.held = held;
+        self.sync();
+    }
+
+    fn is_running(&self) -> bool {
+        self.started && !self.stopped && !self.paused && !self.held
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.banked + self.resumed_at.map_or(Duration::ZERO, |at| at.elapsed())
+    }
+
+    fn sync(&mut self) {
+        if self.is_running() {
+            self.resumed_at.get_or_insert_with(Instant::now);
+        } else if let Some(at) = self.resumed_at.take() {
+            self.banked += at.elapsed();
+        }
+    }
+}
+
+/// A recorded game being stepped through; `position` moves have been applied so far.
+struct Replay {
+    moves: Vec<Move>,
+    position: usize,
+}
+
+/// Undo/redo snapshots. Undo keeps at most `UNDO_LIMIT` entries, dropping the oldest.
+#[derive(Default)]
+struct UndoHistory {
+    undo: VecDeque<GameState>,
+    redo: Vec<GameState>,
+}
+
+impl UndoHistory {
+    /// Records the state before a new move; this invalidates anything that could be redone.
+    fn push(&mut self, snapshot: GameState) {
+        self.record(snapshot);
+        self.redo.clear();
+    }
+
+    fn record(&mut self, snapshot: GameState) {
+        self.undo.push_back(snapshot);
+        while self.undo.len() > UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Steps `game` back one snapshot, keeping the current state for redo. Returns `false`
+    /// when there is nothing to undo.
+    fn undo(&mut self, game: &mut GameState) -> bool {
+        let Some(snapshot) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push(std::mem::replace(game, snapshot));
+        true
+    }
+
+    fn redo(&mut self, game: &mut GameState) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(game, snapshot);
+        self.record(current);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
 #[derive(Default)]
 struct WindowState {
     status: HWND,
+    /// Tracking tooltip that names the hovered card.
+    tooltip: HWND,
+    tooltip_visible: bool,
+    /// Pile under the pointer while no button is held; drives the hover outline and tooltip.
+    hover: HitTarget,
+    /// Whether `TrackMouseEvent` is armed to report the pointer leaving the window.
+    mouse_tracked: bool,
     bg_brush: HBRUSH,
+    gdi: GdiCache,
     back: Option<BackBuffer>,
     card: Option<CardImage>,
     card_dc: HDC,
@@ -462,37 +1196,155 @@ struct WindowState {
     game: GameState,
     layout_metrics: Option<CardMetrics>,
     client_size: (i32, i32),
+    /// DPI of the monitor the window is on; layout minimums are scaled by it.
+    dpi: u32,
     tableau_slots: [Vec<CardSlot>; TABLEAU_COLUMNS],
     drag: Option<DragContext>,
     mouse_down: Option<MouseDownContext>,
+    /// Downward wheel travel not yet spent on a draw, so fine-grained wheels still draw per notch.
+    wheel_accum: i32,
     pending_selection: Option<Selection>,
     focus: Option<HitTarget>,
+    /// Set by the keyboard, cleared by the mouse; the focus outline only shows while set.
+    keyboard_active: bool,
+    /// Ctrl+Shift+P debug view: face-down tableau cards show a dimmed face. Drawing only.
+    peek: bool,
+    /// Shift is held over the stock with the preview setting on: the cards the next click
+    /// would turn up show faintly over the waste. Drawing only.
+    stock_preview: bool,
+    /// Board hidden behind the Pause panel: the clock is held and board input is ignored.
+    paused: bool,
+    /// Set while stepping through an exported move log; the board ignores the mouse.
+    replay: Option<Replay>,
     win_anim: Option<VictoryAnimation>,
     victory_timer_active: bool,
     victory_style: VictoryStyle,
-    undo_stack: Vec<GameState>,
-    redo_stack: Vec<GameState>,
+    history: UndoHistory,
     pointer_pos: (i32, i32),
     pointer_speed: f32,
     pointer_last: Option<Instant>,
+    hint: Option<Hint>,
+    clock: GameClock,
+    solvable_pending: bool,
+    /// The background solver proved the position in `winnable_checked` winnable.
+    winnable: bool,
+    winnable_checked: Option<GameState>,
+    winnable_generation: u32,
+    /// Started by the first check and kept for the life of the window.
+    winnable_worker: Option<SolverWorker<solver::Position>>,
+    /// Rating of the deal in `difficulty_deal`; `None` while the solver is still on it.
+    difficulty: Option<Difficulty>,
+    difficulty_deal: Option<DealKey>,
+    difficulty_generation: u32,
+    /// Started by the first rating and kept for the life of the window.
+    difficulty_worker: Option<SolverWorker<GameState>>,
+    /// Position a Go Back search started from; the search is called off once play moves on.
+    rewind_from: Option<GameState>,
+    rewind_generation: u32,
+    /// Started by the first Go Back search and kept for the life of the window.
+    rewind_worker: Option<SolverWorker<Vec<solver::Position>>>,
+    status_note: Option<String>,
+    /// Remaining steps of an Auto Finish run, played one per timer tick.
+    auto_finish: Option<VecDeque<Hint>>,
+    /// Cards still flying to the pile they were moved to; purely cosmetic.
+    tweens: Vec<MoveTween>,
+    /// Opening deal in progress; mouse input is ignored until it lands.
+    deal_anim: Option<DealAnimation>,
+    /// Per-column turn-over of a freshly exposed tableau card.
+    flips: [Option<FlipAnimation>; TABLEAU_COLUMNS],
+    settings: Settings,
+    stats: Stats,
+    /// Set once the current deal has been counted in `stats`, so it is never counted twice.
+    result_recorded: bool,
+    /// A win played out by hand is waiting for its summary dialog until the celebration ends.
+    win_summary_pending: bool,
+    /// Most cards the current deal has had on its foundations, even if some came back down.
+    best_foundation: usize,
+    /// The last deal asked for was a solvable one, so an automatic deal after a win finds
+    /// another.
+    solvable_deals: bool,
+    /// A win's celebration is over and the next deal is on its timer.
+    auto_deal_pending: bool,
 }
 
 impl WindowState {
     fn push_undo(&mut self, snapshot: GameState) {
-        self.undo_stack.push(snapshot);
-        self.redo_stack.clear();
+        if let Some(text) = describe_moves(&snapshot, &self.game) {
+            self.announce(&text);
+        }
+        self.history.push(snapshot);
+        self.best_foundation = self.best_foundation.max(self.game.foundation_cards());
+        self.hint = None;
+        self.status_note = None;
+        self.clock.start();
     }
 
-    fn clear_transients(&mut self) {
-        self.drag = None;
-        self.mouse_down = None;
-        self.pending_selection = None;
-        self.layout_metrics = None;
-        self.focus = Some(HitTarget::Stock);
+    /// Shows `text` in the status bar's announcement part and tells screen readers its name
+    /// changed, so they read it out. Silent when announcements are off.
+    fn announce(&self, text: &str) {
+        if !self.settings.announce || self.status.0 == 0 {
+            return;
+        }
+        let wide = to_wide(text);
+        unsafe {
+            SendMessageW(
+                self.status,
+                SB_SETTEXTW,
+                WPARAM(1),
+                LPARAM(wide.as_ptr() as isize),
+            );
+            // Status bar parts are accessible children numbered from 1.
+            NotifyWinEvent(EVENT_OBJECT_NAMECHANGE, self.status, OBJID_CLIENT.0, 2);
+        }
     }
-}
 
-unsafe fn set_state(hwnd: HWND, state: Box<WindowState>) {
+    fn play_sound(&self, sound: Sound) {
+        if self.settings.sound {
+            sound::play(sound);
+        }
+    }
+
+    /// Counts `previous` as a loss if it was abandoned mid-game, then arms stats for the new deal.
+    /// Peeking never carries over into a new deal.
+    fn settle_previous_game(&mut self, previous: &GameState) {
+        self.peek = false;
+        if !self.result_recorded && previous.moves > 0 && !previous.is_won() {
+            let best = self.best_foundation.max(previous.foundation_cards());
+            self.stats.record_loss(best as u32);
+            stats::save_stats(&self.stats);
+        }
+        self.result_recorded = false;
+        self.best_foundation = self.game.foundation_cards();
+    }
+
+    fn card_art(&self) -> CardArt<'_> {
+        CardArt {
+            image: self.card.as_ref(),
+            dc: self.card_dc,
+        }
+    }
+
+    /// Brings the board back from a pause; only a new, loaded or re-dealt game does this
+    /// behind the player's back.
+    fn unpause(&mut self) {
+        self.paused = false;
+        self.clock.set_held(false);
+    }
+
+    fn clear_transients(&mut self) {
+        self.drag = None;
+        self.mouse_down = None;
+        self.pending_selection = None;
+        self.layout_metrics = None;
+        self.focus = Some(HitTarget::Stock);
+        self.hint = None;
+        self.tweens.clear();
+        self.flips = Default::default();
+        self.win_summary_pending = false;
+    }
+}
+
+unsafe fn set_state(hwnd: HWND, state: Box<WindowState>) {
     let ptr = Box::into_raw(state) as isize;
     SetWindowLongPtrW(hwnd, GWLP_USERDATA, ptr);
 }
@@ -521,7 +1373,12 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                 // Allocate per-window state
                 let mut state = Box::new(WindowState {
                     status: HWND(0),
+                    tooltip: HWND(0),
+                    tooltip_visible: false,
+                    hover: HitTarget::None,
+                    mouse_tracked: false,
                     bg_brush: HBRUSH(0),
+                    gdi: GdiCache::default(),
                     back: None,
                     card: None,
                     card_dc: HDC(0),
@@ -529,23 +1386,59 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                     game: GameState::default(),
                     layout_metrics: None,
                     client_size: (0, 0),
+                    dpi: BASE_DPI,
                     tableau_slots: Default::default(),
                     drag: None,
                     mouse_down: None,
+                    wheel_accum: 0,
                     pending_selection: None,
                     focus: Some(HitTarget::Stock),
+                    keyboard_active: false,
+                    peek: false,
+                    stock_preview: false,
+                    paused: false,
+                    replay: None,
                     win_anim: None,
                     victory_timer_active: false,
                     victory_style: VictoryStyle::Classic,
-                    undo_stack: Vec::new(),
-                    redo_stack: Vec::new(),
+                    history: UndoHistory::default(),
                     pointer_pos: (0, 0),
                     pointer_speed: 0.0,
                     pointer_last: None,
+                    hint: None,
+                    clock: GameClock::default(),
+                    solvable_pending: false,
+                    winnable: false,
+                    winnable_checked: None,
+                    winnable_generation: 0,
+                    winnable_worker: None,
+                    difficulty: None,
+                    difficulty_deal: None,
+                    difficulty_generation: 0,
+                    difficulty_worker: None,
+                    rewind_from: None,
+                    rewind_generation: 0,
+                    rewind_worker: None,
+                    status_note: None,
+                    auto_finish: None,
+                    tweens: Vec::new(),
+                    deal_anim: None,
+                    flips: Default::default(),
+                    settings: Settings::default(),
+                    stats: Stats::default(),
+                    result_recorded: false,
+                    win_summary_pending: false,
+                    best_foundation: 0,
+                    solvable_deals: false,
+                    auto_deal_pending: false,
                 });
 
-                // Create background brush (green felt)
-                state.bg_brush = CreateSolidBrush(rgb(0, 128, 0));
+                state.settings = settings::load_settings();
+                state.dpi = match GetDpiForWindow(hwnd) {
+                    0 => BASE_DPI,
+                    dpi => dpi,
+                };
+                state.bg_brush = CreateSolidBrush(felt_color(state.settings.felt));
 
                 // Init common controls and create status bar
                 let icc = INITCOMMONCONTROLSEX {
@@ -555,14 +1448,37 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                 InitCommonControlsEx(&icc);
                 let style = (WS_CHILD.0 | WS_VISIBLE.0 | SBARS_SIZEGRIP) as i32;
                 state.status = CreateStatusWindowW(style, w!(""), hwnd, constants::STATUS_BAR_ID);
+                if !status_bar_shown(&state.settings) {
+                    ShowWindow(state.status, SW_HIDE);
+                }
+                state.tooltip = create_card_tooltip(hwnd);
 
-                if let Err(err) = state.game.deal_new_game(DrawMode::DrawOne) {
-                    debug_log(&format!("deal_new_game failed: {err:?}"));
+                state.stats = stats::load_stats();
+                if let Some(game) = restore_autosave() {
+                    state.game = game;
+                } else {
+                    state.game.set_scoring_mode(
+                        state.settings.scoring_mode,
+                        state.settings.vegas_cumulative,
+                    );
+                    state.game.pass_limit = state.settings.pass_limit;
+                    state.game.manual_flips = !state.settings.auto_flip;
+                    state.game.allow_any_to_empty = state.settings.allow_any_to_empty;
+                    if let Err(err) = state.game.deal_new_game(state.settings.draw_mode) {
+                        debug_log(&format!("deal_new_game failed: {err:?}"));
+                    }
                 }
 
-                update_draw_menu(hwnd, state.game.draw_mode);
+                refresh_rule_menus(hwnd, &state.game);
                 update_victory_menu(hwnd, state.victory_style);
+                update_preferences_menu(hwnd, &state.settings);
+                update_felt_menu(hwnd, state.settings.felt);
+                update_card_back_menu(hwnd, state.settings.card_back);
+                update_outline_menu(hwnd, &state.settings.outlines);
+                update_card_size_menu(hwnd, state.settings.card_size);
+                update_victory_physics_menu(hwnd, state.settings.victory_physics);
                 update_status_bar(&mut state);
+                apply_always_on_top(hwnd, state.settings.always_on_top);
 
                 // Try to load embedded card PNG (optional)
                 match load_card_bitmap_from_resource(constants::IDB_CARDS) {
@@ -579,15 +1495,59 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                     }
                 }
 
+                update_corner_indices_menu(hwnd, &state);
+                SetTimer(hwnd, CLOCK_TIMER_ID, 1000, None);
+
                 set_state(hwnd, state);
                 LRESULT(0)
             }
+            WM_GETMINMAXINFO => {
+                // Arrives before WM_CREATE, so this must not depend on window state.
+                let info = &mut *(lparam.0 as *mut MINMAXINFO);
+                let dpi = match GetDpiForWindow(hwnd) {
+                    0 => BASE_DPI,
+                    dpi => dpi,
+                };
+                info.ptMinTrackSize.x = scale_for_dpi(WINDOW_MIN_WIDTH, BASE_DPI, dpi);
+                info.ptMinTrackSize.y = scale_for_dpi(WINDOW_MIN_HEIGHT, BASE_DPI, dpi);
+                LRESULT(0)
+            }
+            WM_ACTIVATE => {
+                if let Some(state) = get_state(hwnd) {
+                    state.clock.set_paused(loword(wparam) as u32 == WA_INACTIVE);
+                }
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
             WM_SIZE => {
                 if let Some(state) = get_state(hwnd) {
+                    if wparam.0 as u32 == SIZE_MINIMIZED {
+                        state.clock.set_paused(true);
+                    }
                     // Let the status bar auto-size itself and resize backbuffer
                     SendMessageW(state.status, msg, wparam, lparam);
+                    layout_status_parts(state);
                     ensure_backbuffer(hwnd, state, 0, 0);
+                    forget_layout(hwnd, state);
+                }
+                LRESULT(0)
+            }
+            WM_DPICHANGED => {
+                if let Some(state) = get_state(hwnd) {
+                    state.dpi = (loword(wparam) as u32).max(1);
+                    state.layout_metrics = None;
                 }
+                // Windows suggests a rect that keeps the window's physical size on the new monitor.
+                let suggested = &*(lparam.0 as *const RECT);
+                let _ = SetWindowPos(
+                    hwnd,
+                    HWND_TOP,
+                    suggested.left,
+                    suggested.top,
+                    suggested.right - suggested.left,
+                    suggested.bottom - suggested.top,
+                    SWP_NOZORDER | SWP_NOACTIVATE,
+                );
+                force_redraw(hwnd);
                 LRESULT(0)
             }
             WM_TIMER => {
@@ -597,12 +1557,70 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                         request_redraw(hwnd);
                     }
                     LRESULT(0)
+                } else if wparam.0 == AUTO_DEAL_TIMER_ID {
+                    if let Some(state) = get_state(hwnd) {
+                        deal_after_win(hwnd, state);
+                    }
+                    LRESULT(0)
+                } else if wparam.0 == HOVER_TIMER_ID {
+                    let _ = KillTimer(hwnd, HOVER_TIMER_ID);
+                    if let Some(state) = get_state(hwnd) {
+                        show_card_tooltip(hwnd, state);
+                    }
+                    LRESULT(0)
+                } else if wparam.0 == DEAL_TIMER_ID {
+                    if let Some(state) = get_state(hwnd) {
+                        update_deal_animation(hwnd, state);
+                    }
+                    LRESULT(0)
+                } else if wparam.0 == FLIP_TIMER_ID {
+                    if let Some(state) = get_state(hwnd) {
+                        update_flip_animations(hwnd, state);
+                    }
+                    LRESULT(0)
+                } else if wparam.0 == TWEEN_TIMER_ID {
+                    if let Some(state) = get_state(hwnd) {
+                        update_tweens(hwnd, state);
+                    }
+                    LRESULT(0)
+                } else if wparam.0 == AUTO_FINISH_TIMER_ID {
+                    if let Some(state) = get_state(hwnd) {
+                        step_auto_finish(hwnd, state);
+                    }
+                    LRESULT(0)
+                } else if wparam.0 == CLOCK_TIMER_ID {
+                    if let Some(state) = get_state(hwnd) {
+                        if state.clock.is_running() {
+                            if state.game.is_won() {
+                                state.clock.stop();
+                            }
+                            update_status_bar(state);
+                            if state.settings.score_overlay {
+                                request_redraw(hwnd);
+                            }
+                        }
+                    }
+                    LRESULT(0)
                 } else {
                     DefWindowProcW(hwnd, msg, wparam, lparam)
                 }
             }
             WM_LBUTTONDOWN => {
                 if let Some(state) = get_state(hwnd) {
+                    if cancel_auto_deal(hwnd, state, true) {
+                        return LRESULT(0);
+                    }
+                    if state.deal_anim.is_some() || state.replay.is_some() || state.paused {
+                        return LRESULT(0);
+                    }
+                    stop_auto_finish(hwnd, state);
+                    update_hover(hwnd, state, HitTarget::None);
+                    if state.keyboard_active {
+                        // Drop a half-finished keyboard pickup rather than let the mouse place it.
+                        state.keyboard_active = false;
+                        state.pending_selection = None;
+                        request_redraw(hwnd);
+                    }
                     let position = lparam_point(lparam);
                     let target = hit_test(&*state, position.0, position.1);
                     state.mouse_down = Some(MouseDownContext { target, position });
@@ -629,6 +1647,18 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                             state.mouse_down = None;
                             request_redraw(hwnd);
                         }
+                    } else {
+                        let target = hit_test(&*state, mx, my);
+                        update_hover(hwnd, state, target);
+                    }
+                    if !state.mouse_tracked {
+                        let mut track = TRACKMOUSEEVENT {
+                            cbSize: size_of::<TRACKMOUSEEVENT>() as u32,
+                            dwFlags: TME_LEAVE,
+                            hwndTrack: hwnd,
+                            dwHoverTime: 0,
+                        };
+                        state.mouse_tracked = TrackMouseEvent(&mut track).is_ok();
                     }
                     let now = Instant::now();
                     if let Some(last) = state.pointer_last {
@@ -646,6 +1676,13 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                 }
                 LRESULT(0)
             }
+            WM_MOUSELEAVE => {
+                if let Some(state) = get_state(hwnd) {
+                    state.mouse_tracked = false;
+                    update_hover(hwnd, state, HitTarget::None);
+                }
+                LRESULT(0)
+            }
             WM_LBUTTONUP => {
                 if let Some(state) = get_state(hwnd) {
                     let (mx, my) = lparam_point(lparam);
@@ -656,10 +1693,18 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
                             other => other,
                         };
                         let snapshot = drag.snapshot.clone();
+                        let source = drag.source;
                         if finalize_drag(state, drag, drop_target) {
+                            state.play_sound(match drop_target {
+                                HitTarget::Foundation(_) => Sound::Foundation,
+                                _ => Sound::Tableau,
+                            });
+                            start_flip_animations(hwnd, state, &snapshot);
                             state.push_undo(snapshot);
                             update_status_bar(state);
                             check_for_victory(hwnd, state);
+                        } else if is_rejected_drop(source, drop_target) {
+                            state.play_sound(Sound::Invalid);
                         }
                         force_redraw(hwnd);
                     } else if let Some(mouse) = state.mouse_down.take() {
@@ -676,223 +1721,701 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
             }
             WM_LBUTTONDBLCLK => {
                 if let Some(state) = get_state(hwnd) {
+                    if state.deal_anim.is_some() || state.replay.is_some() || state.paused {
+                        return LRESULT(0);
+                    }
                     state.mouse_down = None;
                     state.pending_selection = None;
-                    if let Some(drag) = state.drag.take() {
-                        match drag.source {
-                            DragSource::Waste => state.game.waste.cards.extend(drag.cards),
-                            DragSource::Tableau { column } => {
-                                state.game.cancel_tableau_stack(column, drag.cards);
-                            }
-                        }
-                        let _ = ReleaseCapture();
+                    cancel_drag(state);
+                    let (mx, my) = lparam_point(lparam);
+                    let target = hit_test(&*state, mx, my);
+                    if !send_to_any_foundation(hwnd, state, target) {
+                        send_to_best_tableau(hwnd, state, target);
+                    }
+                    request_redraw(hwnd);
+                }
+                LRESULT(0)
+            }
+            WM_RBUTTONUP => {
+                if let Some(state) = get_state(hwnd) {
+                    if state.deal_anim.is_some()
+                        || state.drag.is_some()
+                        || state.replay.is_some()
+                        || state.paused
+                    {
+                        return LRESULT(0);
                     }
+                    stop_auto_finish(hwnd, state);
                     let (mx, my) = lparam_point(lparam);
                     let target = hit_test(&*state, mx, my);
-                    let mut moved = false;
-                    let mut snapshot: Option<GameState> = None;
-                    match target {
-                        HitTarget::Waste => {
-                            let snap = state.game.clone();
-                            if state.game.move_waste_to_any_foundation() {
-                                snapshot = Some(snap);
-                                moved = true;
-                            }
-                        }
-                        HitTarget::Tableau {
-                            column,
-                            card_index: Some(idx),
-                        } if idx + 1 == state.game.tableau_len(column) => {
-                            let snap = state.game.clone();
-                            if state.game.move_tableau_top_to_any_foundation(column) {
-                                snapshot = Some(snap);
-                                moved = true;
-                            }
-                        }
-                        _ => {}
+                    if send_to_any_foundation(hwnd, state, target) {
+                        state.pending_selection = None;
+                        request_redraw(hwnd);
                     }
-                    if moved {
-                        if let Some(snap) = snapshot {
-                            state.push_undo(snap);
-                        }
-                        update_status_bar(state);
-                        check_for_victory(hwnd, state);
+                }
+                LRESULT(0)
+            }
+            WM_MOUSEWHEEL => {
+                if let Some(state) = get_state(hwnd) {
+                    if state.deal_anim.is_some()
+                        || state.drag.is_some()
+                        || state.replay.is_some()
+                        || state.paused
+                    {
+                        return LRESULT(0);
+                    }
+                    stop_auto_finish(hwnd, state);
+                    // Wheel messages carry screen coordinates.
+                    let (sx, sy) = lparam_point(lparam);
+                    let mut point = POINT { x: sx, y: sy };
+                    let _ = ScreenToClient(hwnd, &mut point);
+                    if hit_test(&*state, point.x, point.y) != HitTarget::Stock {
+                        state.wheel_accum = 0;
+                        return LRESULT(0);
+                    }
+                    let delta = hiword(wparam) as i16;
+                    for _ in 0..wheel_notches(&mut state.wheel_accum, delta) {
+                        handle_click(hwnd, state, HitTarget::Stock);
                     }
-                    request_redraw(hwnd);
                 }
                 LRESULT(0)
             }
-            WM_KEYDOWN => DefWindowProcW(hwnd, msg, wparam, lparam),
+            WM_KEYDOWN | WM_KEYUP if loword(wparam) == VK_SHIFT.0 => {
+                if let Some(state) = get_state(hwnd) {
+                    refresh_stock_preview(hwnd, state);
+                }
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            WM_KEYDOWN => {
+                if let Some(state) = get_state(hwnd) {
+                    if cancel_auto_deal(hwnd, state, true) {
+                        return LRESULT(0);
+                    }
+                    if handle_key_down(hwnd, state, loword(wparam)) {
+                        return LRESULT(0);
+                    }
+                }
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
             WM_COMMAND => {
                 let id = (wparam.0 & 0xFFFF) as u16;
+                // Any other command means the player has taken over again.
+                if let Some(state) = get_state(hwnd) {
+                    cancel_auto_deal(hwnd, state, false);
+                    stop_deal_animation(hwnd, state);
+                    if state.replay.take().is_some() {
+                        update_status_bar(state);
+                    }
+                    if state.auto_finish.is_some() {
+                        stop_auto_finish(hwnd, state);
+                        if id == constants::IDM_GAME_AUTOCOMPLETE {
+                            return LRESULT(0);
+                        }
+                    }
+                }
                 if id == constants::IDM_FILE_EXIT {
                     let _ = DestroyWindow(hwnd);
                     return LRESULT(0);
                 }
+                if PAUSE_BLOCKED_COMMANDS.contains(&id) && get_state(hwnd).is_some_and(|s| s.paused)
+                {
+                    return LRESULT(0);
+                }
+                if DISCARDING_COMMANDS.contains(&id) {
+                    if let Some(state) = get_state(hwnd) {
+                        if !confirm_discard(hwnd, state, id) {
+                            return LRESULT(0);
+                        }
+                    }
+                }
                 match id {
                     constants::IDM_FILE_NEW => {
                         if let Some(state) = get_state(hwnd) {
-                            stop_victory_animation(hwnd, state);
-                            let snapshot = state.game.clone();
-                            let draw_mode = state.game.draw_mode;
-                            match state.game.deal_new_game(draw_mode) {
-                                Ok(()) => {
-                                    state.push_undo(snapshot);
-                                    state.clear_transients();
-                                    state.layout_metrics = None;
-                                    update_status_bar(state);
-                                }
-                                Err(err) => {
-                                    debug_log(&format!("deal_new_game failed: {err:?}"));
-                                }
-                            }
+                            state.solvable_deals = false;
+                            start_new_deal(hwnd, state, "deal_new_game", |game| {
+                                game.deal_new_game(game.draw_mode)
+                            });
                         }
-                        request_redraw(hwnd);
                     }
                     constants::IDM_FILE_DEALAGAIN => {
                         if let Some(state) = get_state(hwnd) {
-                            stop_victory_animation(hwnd, state);
-                            let snapshot = state.game.clone();
-                            match state.game.deal_again() {
-                                Ok(()) => {
-                                    state.push_undo(snapshot);
-                                    state.clear_transients();
-                                    state.layout_metrics = None;
-                                    update_status_bar(state);
-                                }
-                                Err(err) => {
-                                    debug_log(&format!("deal_again failed: {err:?}"));
-                                }
+                            start_new_deal(hwnd, state, "deal_again", GameState::deal_again);
+                        }
+                    }
+                    constants::IDM_GAME_RESTART => {
+                        if let Some(state) = get_state(hwnd) {
+                            start_new_deal(hwnd, state, "restart", GameState::restart);
+                        }
+                    }
+                    constants::IDM_FILE_SAVE => {
+                        if let Some(state) = get_state(hwnd) {
+                            save_game(state);
+                        }
+                    }
+                    constants::IDM_FILE_LOAD => {
+                        if let Some(state) = get_state(hwnd) {
+                            load_game(hwnd, state);
+                        }
+                    }
+                    constants::IDM_FILE_REPLAY => {
+                        if let Some(state) = get_state(hwnd) {
+                            start_replay(hwnd, state);
+                        }
+                    }
+                    constants::IDM_FILE_EXPORT_MOVES => {
+                        if let Some(state) = get_state(hwnd) {
+                            export_moves(state);
+                            update_status_bar(state);
+                        }
+                    }
+                    constants::IDM_GAME_NEW_SOLVABLE => {
+                        if let Some(state) = get_state(hwnd) {
+                            start_solvable_deal(hwnd, state);
+                        }
+                    }
+                    constants::IDM_GAME_DEAL_BY_SEED => {
+                        if let Some(state) = get_state(hwnd) {
+                            if let Some(seed) = show_seed_dialog(hwnd, state.game.rng_seed) {
+                                state.solvable_deals = false;
+                                start_new_deal(hwnd, state, "deal_new_game_with_seed", |game| {
+                                    game.deal_new_game_with_seed(game.draw_mode, seed)
+                                });
                             }
                         }
-                        request_redraw(hwnd);
                     }
-                    constants::IDM_GAME_DRAW1 => {
+                    constants::IDM_GAME_DRAW1
+                    | constants::IDM_GAME_DRAW2
+                    | constants::IDM_GAME_DRAW3 => {
+                        let mode = match id {
+                            constants::IDM_GAME_DRAW1 => DrawMode::DrawOne,
+                            constants::IDM_GAME_DRAW2 => DrawMode::DrawTwo,
+                            _ => DrawMode::DrawThree,
+                        };
                         if let Some(state) = get_state(hwnd) {
-                            if state.game.draw_mode != DrawMode::DrawOne {
-                                state.game.draw_mode = DrawMode::DrawOne;
+                            if state.game.draw_mode != mode {
+                                state.game.draw_mode = mode;
+                                state.settings.draw_mode = mode;
+                                settings::save_settings(&state.settings);
                                 state.pending_selection = None;
-                                update_draw_menu(hwnd, DrawMode::DrawOne);
+                                update_draw_menu(hwnd, mode);
                                 update_status_bar(state);
                             }
                         }
                     }
-                    constants::IDM_GAME_VICTORY => {
+                    constants::IDM_GAME_AUTOPLAY => {
                         if let Some(state) = get_state(hwnd) {
-                            stop_victory_animation(hwnd, state);
-                            let mut snapshot: Option<GameState> = None;
-                            if !state.game.is_won() {
-                                let snap = state.game.clone();
-                                if state.game.force_complete_foundations() {
-                                    snapshot = Some(snap);
-                                    state.drag = None;
-                                    state.mouse_down = None;
-                                    state.pending_selection = None;
-                                    set_focus(state, HitTarget::Foundation(0));
-                                    update_status_bar(state);
-                                }
-                            }
-                            let _ = force_victory_animation(hwnd, state);
-                            if let Some(snap) = snapshot {
-                                state.push_undo(snap);
+                            run_batch_foundation_move(
+                                hwnd,
+                                state,
+                                GameState::auto_move_safe_to_foundations,
+                                |_| None,
+                            );
+                        }
+                    }
+                    constants::IDM_GAME_COLLECT_LOW => {
+                        if let Some(state) = get_state(hwnd) {
+                            run_batch_foundation_move(
+                                hwnd,
+                                state,
+                                GameState::collect_aces_and_twos,
+                                |moved| {
+                                    Some(match moved {
+                                        1 => "Collected 1 card".to_string(),
+                                        _ => format!("Collected {moved} cards"),
+                                    })
+                                },
+                            );
+                        }
+                    }
+                    constants::IDM_GAME_ANIMATE_DEAL => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.animate_deal = !state.settings.animate_deal;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                        }
+                    }
+                    constants::IDM_GAME_PASSES_UNLIMITED
+                    | constants::IDM_GAME_PASSES_ONE
+                    | constants::IDM_GAME_PASSES_THREE => {
+                        if let Some(state) = get_state(hwnd) {
+                            if let Some(&(_, limit)) =
+                                PASS_LIMIT_MENU.iter().find(|(item, _)| *item == id)
+                            {
+                                // Applies to the game in progress; passes already used still count.
+                                state.game.pass_limit = limit;
+                                state.settings.pass_limit = limit;
+                                settings::save_settings(&state.settings);
+                                update_pass_limit_menu(hwnd, &state.game);
+                                update_status_bar(state);
+                                request_redraw(hwnd);
                             }
+                        }
+                    }
+                    constants::IDM_GAME_AUTO_FLIP => {
+                        if let Some(state) = get_state(hwnd) {
+                            // Like the pass limit, this applies to the game in progress.
+                            state.settings.auto_flip = !state.settings.auto_flip;
+                            state.game.manual_flips = !state.settings.auto_flip;
+                            settings::save_settings(&state.settings);
+                            update_rules_menu(hwnd, &state.game);
+                        }
+                    }
+                    constants::IDM_GAME_ANY_TO_EMPTY => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.allow_any_to_empty = !state.settings.allow_any_to_empty;
+                            state.game.allow_any_to_empty = state.settings.allow_any_to_empty;
+                            settings::save_settings(&state.settings);
+                            update_rules_menu(hwnd, &state.game);
+                            update_status_bar(state);
                             request_redraw(hwnd);
                         }
                     }
-                    constants::IDM_GAME_VICTORY_CLASSIC => {
+                    constants::IDM_GAME_FELT_GREEN
+                    | constants::IDM_GAME_FELT_BLUE
+                    | constants::IDM_GAME_FELT_BURGUNDY
+                    | constants::IDM_GAME_FELT_CHARCOAL => {
                         if let Some(state) = get_state(hwnd) {
-                            if state.victory_style != VictoryStyle::Classic {
-                                stop_victory_animation(hwnd, state);
-                                state.victory_style = VictoryStyle::Classic;
-                                update_victory_menu(hwnd, state.victory_style);
-                                request_redraw(hwnd);
+                            if let Some(&(_, felt)) = FELT_MENU.iter().find(|(item, _)| *item == id)
+                            {
+                                change_felt(hwnd, state, felt);
                             }
                         }
                     }
-                    constants::IDM_GAME_VICTORY_MODERN => {
+                    constants::IDM_GAME_BACK_CLASSIC
+                    | constants::IDM_GAME_BACK_DIAMOND
+                    | constants::IDM_GAME_BACK_LATTICE
+                    | constants::IDM_GAME_BACK_PLAIN => {
                         if let Some(state) = get_state(hwnd) {
-                            if state.victory_style != VictoryStyle::Modern {
-                                stop_victory_animation(hwnd, state);
-                                state.victory_style = VictoryStyle::Modern;
-                                update_victory_menu(hwnd, state.victory_style);
-                                request_redraw(hwnd);
+                            if let Some(&(_, back)) =
+                                CARD_BACK_MENU.iter().find(|(item, _)| *item == id)
+                            {
+                                state.settings.card_back = back;
+                                settings::save_settings(&state.settings);
+                                update_card_back_menu(hwnd, back);
+                                force_redraw(hwnd);
                             }
                         }
                     }
-                    constants::IDM_GAME_CANCEL_VICTORY => {
+                    constants::IDM_GAME_SIZE_SMALL
+                    | constants::IDM_GAME_SIZE_MEDIUM
+                    | constants::IDM_GAME_SIZE_LARGE => {
                         if let Some(state) = get_state(hwnd) {
-                            if state.win_anim.is_some() {
-                                stop_victory_animation(hwnd, state);
-                                request_redraw(hwnd);
+                            if let Some(&(_, size)) =
+                                CARD_SIZE_MENU.iter().find(|(item, _)| *item == id)
+                            {
+                                state.settings.card_size = size;
+                                settings::save_settings(&state.settings);
+                                update_card_size_menu(hwnd, size);
+                                state.layout_metrics = None;
+                                force_redraw(hwnd);
                             }
                         }
                     }
-                    constants::IDM_EDIT_UNDO => {
+                    constants::IDM_GAME_PHYSICS_CALM
+                    | constants::IDM_GAME_PHYSICS_CLASSIC
+                    | constants::IDM_GAME_PHYSICS_CHAOTIC => {
                         if let Some(state) = get_state(hwnd) {
-                            stop_victory_animation(hwnd, state);
-                            if let Some(snapshot) = state.undo_stack.pop() {
-                                let current = state.game.clone();
-                                state.redo_stack.push(current);
-                                state.game = snapshot;
-                                state.clear_transients();
-                                update_status_bar(state);
-                                update_draw_menu(hwnd, state.game.draw_mode);
-                                check_for_victory(hwnd, state);
-                                request_redraw(hwnd);
+                            if let Some(&(_, physics)) =
+                                VICTORY_PHYSICS_MENU.iter().find(|(item, _)| *item == id)
+                            {
+                                state.settings.victory_physics = physics;
+                                settings::save_settings(&state.settings);
+                                update_victory_physics_menu(hwnd, physics);
                             }
                         }
                     }
-                    constants::IDM_EDIT_REDO => {
+                    constants::IDM_GAME_OUTLINES_STANDARD
+                    | constants::IDM_GAME_OUTLINES_HIGH_CONTRAST => {
                         if let Some(state) = get_state(hwnd) {
-                            stop_victory_animation(hwnd, state);
-                            if let Some(snapshot) = state.redo_stack.pop() {
-                                let current = state.game.clone();
-                                state.undo_stack.push(current);
-                                state.game = snapshot;
-                                state.clear_transients();
-                                update_status_bar(state);
-                                update_draw_menu(hwnd, state.game.draw_mode);
-                                check_for_victory(hwnd, state);
+                            if let Some(&(_, style)) =
+                                OUTLINE_MENU.iter().find(|(item, _)| *item == id)
+                            {
+                                state.settings.outlines = style;
+                                settings::save_settings(&state.settings);
+                                update_outline_menu(hwnd, &style);
                                 request_redraw(hwnd);
                             }
                         }
                     }
-                    constants::IDM_HELP_ABOUT => {
-                        show_about_dialog(hwnd);
-                    }
-                    _ => {}
-                }
-
-                LRESULT(0)
-            }
-            WM_ERASEBKGND => {
-                // Avoid flicker; we paint in WM_PAINT
-                LRESULT(1)
-            }
-            WM_PAINT => {
-                let mut ps = PAINTSTRUCT::default();
-                let hdc = BeginPaint(hwnd, &mut ps);
-                if let Some(state) = get_state(hwnd) {
-                    paint_window(hwnd, hdc, state);
-                }
-                EndPaint(hwnd, &ps);
-                LRESULT(0)
-            }
-            WM_DESTROY => {
-                save_window_bounds(hwnd);
-                if let Some(state) = get_state(hwnd) {
-                    stop_victory_animation(hwnd, state);
-                    if state.bg_brush.0 != 0 {
-                        let _ = DeleteObject(state.bg_brush);
-                    }
-                    if let Some(mut back) = state.back.take() {
-                        back.destroy();
+                    constants::IDM_GAME_PAUSE => {
+                        if let Some(state) = get_state(hwnd) {
+                            toggle_pause(hwnd, state);
+                        }
                     }
-                    if state.card_dc.0 != 0 {
-                        if state.card_old.0 != 0 {
-                            let _ = SelectObject(state.card_dc, state.card_old);
+                    constants::IDM_GAME_COPY_SEED => {
+                        if let Some(state) = get_state(hwnd) {
+                            copy_seed(hwnd, state);
                         }
-                        DeleteDC(state.card_dc);
+                    }
+                    constants::IDM_GAME_DUMP_BOARD => {
+                        // Hidden (Ctrl+Shift+D): the board as text in the debugger output.
+                        if let Some(state) = get_state(hwnd) {
+                            debug_log(&state.game.dump_board());
+                        }
+                    }
+                    constants::IDM_GAME_PEEK => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.peek = !state.peek;
+                            request_redraw(hwnd);
+                        }
+                    }
+                    constants::IDM_GAME_CORNER_INDICES => {
+                        if let Some(state) = get_state(hwnd) {
+                            let has_sprites = state.card.is_some();
+                            let enabled = state.settings.corner_indices_enabled(has_sprites);
+                            state.settings.corner_indices = Some(!enabled);
+                            settings::save_settings(&state.settings);
+                            update_corner_indices_menu(hwnd, state);
+                            state.layout_metrics = None;
+                            force_redraw(hwnd);
+                        }
+                    }
+                    constants::IDM_GAME_LEFT_HANDED => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.left_handed = !state.settings.left_handed;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            state.layout_metrics = None;
+                            force_redraw(hwnd);
+                        }
+                    }
+                    constants::IDM_GAME_CARD_SHADOWS => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.card_shadows = !state.settings.card_shadows;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            state.layout_metrics = None;
+                            force_redraw(hwnd);
+                        }
+                    }
+                    constants::IDM_GAME_ANNOUNCE => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.announce = !state.settings.announce;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            layout_status_parts(state);
+                            update_status_bar(state);
+                        }
+                    }
+                    constants::IDM_GAME_HIGHLIGHT_MOVES => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.highlight_moves = !state.settings.highlight_moves;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            request_redraw(hwnd);
+                        }
+                    }
+                    constants::IDM_GAME_SOUND => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.sound = !state.settings.sound;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                        }
+                    }
+                    constants::IDM_GAME_ANIMATE_FLIPS => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.animate_flips = !state.settings.animate_flips;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                        }
+                    }
+                    constants::IDM_GAME_AUTOCOMPLETE => {
+                        if let Some(state) = get_state(hwnd) {
+                            start_auto_finish(hwnd, state);
+                        }
+                    }
+                    constants::IDM_GAME_REWIND_WINNABLE => {
+                        if let Some(state) = get_state(hwnd) {
+                            start_rewind_search(hwnd, state);
+                        }
+                    }
+                    constants::IDM_GAME_REWIND_ASSIST => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.rewind_assist = !state.settings.rewind_assist;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                        }
+                    }
+                    constants::IDM_GAME_STOCK_BADGE => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.stock_count_badge = !state.settings.stock_count_badge;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            request_redraw(hwnd);
+                        }
+                    }
+                    constants::IDM_VIEW_ALWAYS_ON_TOP => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.always_on_top = !state.settings.always_on_top;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            apply_always_on_top(hwnd, state.settings.always_on_top);
+                        }
+                    }
+                    constants::IDM_VIEW_COMPACT => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.compact = !state.settings.compact;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            apply_status_bar_visibility(hwnd, state);
+                        }
+                    }
+                    constants::IDM_VIEW_SCORE_OVERLAY => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.score_overlay = !state.settings.score_overlay;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            apply_status_bar_visibility(hwnd, state);
+                        }
+                    }
+                    constants::IDM_GAME_STOCK_PREVIEW => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.stock_preview = !state.settings.stock_preview;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            refresh_stock_preview(hwnd, state);
+                        }
+                    }
+                    constants::IDM_GAME_RESHUFFLE_ASSIST => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.reshuffle_assist = !state.settings.reshuffle_assist;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            update_status_bar(state);
+                        }
+                    }
+                    constants::IDM_GAME_SCORING_STANDARD => {
+                        if let Some(state) = get_state(hwnd) {
+                            change_scoring_mode(hwnd, state, ScoringMode::Standard, false);
+                        }
+                    }
+                    constants::IDM_GAME_SCORING_VEGAS => {
+                        if let Some(state) = get_state(hwnd) {
+                            change_scoring_mode(hwnd, state, ScoringMode::Vegas, false);
+                        }
+                    }
+                    constants::IDM_GAME_SCORING_VEGAS_CUMULATIVE => {
+                        if let Some(state) = get_state(hwnd) {
+                            change_scoring_mode(hwnd, state, ScoringMode::Vegas, true);
+                        }
+                    }
+                    constants::IDM_GAME_HINT => {
+                        if let Some(state) = get_state(hwnd) {
+                            if state.win_anim.is_none() && state.drag.is_none() {
+                                state.hint = state.game.hint();
+                                if state.hint.is_none() {
+                                    let _ = MessageBeep(MB_OK);
+                                }
+                                request_redraw(hwnd);
+                            }
+                        }
+                    }
+                    constants::IDM_GAME_VICTORY => {
+                        if let Some(state) = get_state(hwnd) {
+                            stop_victory_animation(hwnd, state);
+                            let mut snapshot: Option<GameState> = None;
+                            if !state.game.is_won() {
+                                let snap = state.game.clone();
+                                if state.game.force_complete_foundations() {
+                                    snapshot = Some(snap);
+                                    state.drag = None;
+                                    state.mouse_down = None;
+                                    state.pending_selection = None;
+                                    set_focus(state, HitTarget::Foundation(0));
+                                    update_status_bar(state);
+                                }
+                            }
+                            let _ = force_victory_animation(hwnd, state);
+                            if let Some(snap) = snapshot {
+                                state.push_undo(snap);
+                            }
+                            request_redraw(hwnd);
+                        }
+                    }
+                    constants::IDM_GAME_VICTORY_CLASSIC => {
+                        if let Some(state) = get_state(hwnd) {
+                            if state.victory_style != VictoryStyle::Classic {
+                                stop_victory_animation(hwnd, state);
+                                state.victory_style = VictoryStyle::Classic;
+                                update_victory_menu(hwnd, state.victory_style);
+                                request_redraw(hwnd);
+                            }
+                        }
+                    }
+                    constants::IDM_GAME_VICTORY_MODERN => {
+                        if let Some(state) = get_state(hwnd) {
+                            if state.victory_style != VictoryStyle::Modern {
+                                stop_victory_animation(hwnd, state);
+                                state.victory_style = VictoryStyle::Modern;
+                                update_victory_menu(hwnd, state.victory_style);
+                                request_redraw(hwnd);
+                            }
+                        }
+                    }
+                    constants::IDM_GAME_VICTORY_ANIMATION => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.victory_animation = !state.settings.victory_animation;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                            if !state.settings.victory_animation && state.win_anim.is_some() {
+                                stop_victory_animation(hwnd, state);
+                                post_win_summary(hwnd, state);
+                                request_redraw(hwnd);
+                            }
+                        }
+                    }
+                    constants::IDM_GAME_AUTO_DEAL => {
+                        if let Some(state) = get_state(hwnd) {
+                            state.settings.auto_deal = !state.settings.auto_deal;
+                            settings::save_settings(&state.settings);
+                            update_preferences_menu(hwnd, &state.settings);
+                        }
+                    }
+                    constants::IDM_GAME_CANCEL_VICTORY => {
+                        if let Some(state) = get_state(hwnd) {
+                            if state.win_anim.is_some() {
+                                stop_victory_animation(hwnd, state);
+                                post_win_summary(hwnd, state);
+                                request_redraw(hwnd);
+                            }
+                        }
+                    }
+                    constants::IDM_EDIT_UNDO
+                    | constants::IDM_EDIT_REDO
+                    | constants::IDM_EDIT_UNDO_ALL
+                    | constants::IDM_EDIT_REDO_ALL => {
+                        if let Some(state) = get_state(hwnd) {
+                            let redo = id == constants::IDM_EDIT_REDO
+                                || id == constants::IDM_EDIT_REDO_ALL;
+                            let all = id == constants::IDM_EDIT_UNDO_ALL
+                                || id == constants::IDM_EDIT_REDO_ALL;
+                            step_history(hwnd, state, redo, all);
+                        }
+                    }
+                    constants::IDM_HELP_ABOUT => {
+                        show_about_dialog(hwnd);
+                    }
+                    constants::IDM_HELP_STATISTICS => {
+                        if let Some(state) = get_state(hwnd) {
+                            show_stats_dialog(hwnd, &state.stats);
+                        }
+                    }
+                    constants::IDM_FILE_OPTIONS => {
+                        if let Some(state) = get_state(hwnd) {
+                            // A resumed game may use a different draw mode than the saved default.
+                            let current = Settings {
+                                draw_mode: state.game.draw_mode,
+                                ..state.settings.clone()
+                            };
+                            if let Some(settings) = show_options_dialog(hwnd, current) {
+                                apply_options(hwnd, state, settings);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+
+                LRESULT(0)
+            }
+            WM_SHOW_WIN_SUMMARY => {
+                if let Some(state) = get_state(hwnd) {
+                    if state.game.is_won() {
+                        let summary = format_win_summary(&state.game, state.clock.elapsed());
+                        let choice = show_win_dialog(hwnd, &summary);
+                        state.keyboard_active = false;
+                        let _ = SetFocus(hwnd);
+                        let command = match choice {
+                            constants::IDC_WIN_NEW => Some(constants::IDM_FILE_NEW),
+                            constants::IDC_WIN_REPLAY => Some(constants::IDM_FILE_DEALAGAIN),
+                            _ => None,
+                        };
+                        if let Some(command) = command {
+                            let _ =
+                                PostMessageW(hwnd, WM_COMMAND, WPARAM(command as usize), LPARAM(0));
+                        }
+                        request_redraw(hwnd);
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_WINNABLE_CHECKED => {
+                if let Some(state) = get_state(hwnd) {
+                    if wparam.0 == state.winnable_generation as usize {
+                        state.winnable = lparam.0 != 0;
+                        update_status_bar(state);
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_DIFFICULTY_RATED => {
+                if let Some(state) = get_state(hwnd) {
+                    if wparam.0 == state.difficulty_generation as usize {
+                        state.difficulty = Difficulty::ALL.get(lparam.0 as usize).copied();
+                        update_status_bar(state);
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_REWIND_FOUND => {
+                if let Some(state) = get_state(hwnd) {
+                    if wparam.0 == state.rewind_generation as usize {
+                        finish_rewind_search(hwnd, state, usize::try_from(lparam.0).ok());
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_NOTIFY => {
+                let header = &*(lparam.0 as *const NMHDR);
+                if header.idFrom == constants::STATUS_BAR_ID as usize && header.code == NM_CLICK {
+                    let part = (*(lparam.0 as *const NMMOUSE)).dwItemSpec;
+                    if let Some(state) = get_state(hwnd) {
+                        if state.winnable && !state.paused && part == winnable_part(&state.settings)
+                        {
+                            start_auto_finish(hwnd, state);
+                        }
+                    }
+                }
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            WM_SOLVABLE_DEAL_READY => {
+                let result = Box::from_raw(lparam.0 as *mut Option<GameState>);
+                if let Some(state) = get_state(hwnd) {
+                    finish_solvable_deal(hwnd, state, *result);
+                }
+                LRESULT(0)
+            }
+            WM_ERASEBKGND => {
+                // Avoid flicker; we paint in WM_PAINT
+                LRESULT(1)
+            }
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd, &mut ps);
+                if let Some(state) = get_state(hwnd) {
+                    paint_window(hwnd, hdc, state);
+                }
+                EndPaint(hwnd, &ps);
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                save_window_bounds(hwnd);
+                if let Some(state) = get_state(hwnd) {
+                    write_autosave(state);
+                    stop_auto_finish(hwnd, state);
+                    let _ = KillTimer(hwnd, CLOCK_TIMER_ID);
+                    stop_victory_animation(hwnd, state);
+                    if state.bg_brush.0 != 0 {
+                        let _ = DeleteObject(state.bg_brush);
+                    }
+                    state.gdi.release();
+                    if let Some(mut back) = state.back.take() {
+                        back.destroy();
+                    }
+                    if state.card_dc.0 != 0 {
+                        if state.card_old.0 != 0 {
+                            let _ = SelectObject(state.card_dc, state.card_old);
+                        }
+                        DeleteDC(state.card_dc);
                     }
                     if let Some(card) = state.card.take() {
                         if card.hbm.0 != 0 {
@@ -995,49 +2518,162 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-// ------------ Back buffer ------------
-struct BackBuffer {
-    dc: HDC,
-    bmp: HBITMAP,
-    old: HGDIOBJ,
-    bits: *mut u8,
-    stride: i32,
-    w: i32,
-    h: i32,
+// ------------ GDI cache ------------
+/// Solid brushes, pens and fonts for the card drawing, created on first use and kept until
+/// `WM_DESTROY` so a frame of the victory animation does not churn GDI handles.
+#[derive(Default)]
+struct GdiCache {
+    brushes: RefCell<Vec<(COLORREF, HBRUSH)>>,
+    pens: RefCell<Vec<(COLORREF, HPEN)>>,
+    /// Oldest first; sizes follow the card size, so a resize retires the earliest ones.
+    fonts: RefCell<Vec<(UiFont, i32, HFONT)>>,
 }
 
-impl BackBuffer {
-    unsafe fn new(width: i32, height: i32) -> anyhow::Result<Self> {
-        let dc = CreateCompatibleDC(HDC(0));
+/// Most fonts kept at once: a handful of sizes for the current layout and a few spare.
+const FONT_CACHE_LIMIT: usize = 12;
 
-        let bi = BITMAPINFO {
-            bmiHeader: BITMAPINFOHEADER {
-                biSize: size_of::<BITMAPINFOHEADER>() as u32,
-                biWidth: width,
-                biHeight: -height, // top-down
-                biPlanes: 1,
-                biBitCount: 32,
-                biCompression: BI_RGB.0,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-        let mut bits: *mut core::ffi::c_void = core::ptr::null_mut();
-        let bmp = CreateDIBSection(HDC(0), &bi, DIB_RGB_COLORS, &mut bits, None, 0)?;
-        if bmp.is_invalid() {
-            return Err(anyhow::anyhow!("CreateDIBSection failed"));
-        }
-        let old = SelectObject(dc, bmp);
-        let stride = width.max(1) * 4;
-        let mut buffer = Self {
-            dc,
-            bmp,
-            old,
-            bits: bits as *mut u8,
-            stride,
-            w: width,
-            h: height,
-        };
+/// Typefaces the board draws text in, all bold.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum UiFont {
+    /// Segoe UI, for numbers and words.
+    Text,
+    /// Segoe UI Symbol, which has the suit glyphs.
+    Symbol,
+}
+
+/// A bold, antialiased `face` with characters `height` pixels tall.
+fn make_ui_font(face: UiFont, height: i32) -> HFONT {
+    let name = match face {
+        UiFont::Text => w!("Segoe UI"),
+        UiFont::Symbol => w!("Segoe UI Symbol"),
+    };
+    unsafe {
+        CreateFontW(
+            -height,
+            0,
+            0,
+            0,
+            FW_BOLD.0 as i32,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET.0 as u32,
+            OUT_DEFAULT_PRECIS.0 as u32,
+            CLIP_DEFAULT_PRECIS.0 as u32,
+            ANTIALIASED_QUALITY.0 as u32,
+            FF_SWISS.0 as u32,
+            name,
+        )
+    }
+}
+
+impl GdiCache {
+    fn brush(&self, color: COLORREF) -> HBRUSH {
+        let mut brushes = self.brushes.borrow_mut();
+        if let Some(&(_, brush)) = brushes.iter().find(|(key, _)| *key == color) {
+            return brush;
+        }
+        let brush = unsafe { CreateSolidBrush(color) };
+        if brush.0 != 0 {
+            brushes.push((color, brush));
+        }
+        brush
+    }
+
+    /// One-pixel solid pen.
+    fn pen(&self, color: COLORREF) -> HPEN {
+        let mut pens = self.pens.borrow_mut();
+        if let Some(&(_, pen)) = pens.iter().find(|(key, _)| *key == color) {
+            return pen;
+        }
+        let pen = unsafe { CreatePen(PS_SOLID, 1, color) };
+        if pen.0 != 0 {
+            pens.push((color, pen));
+        }
+        pen
+    }
+
+    /// `face` at `height` pixels. Callers select it only for the text they draw and put the
+    /// old font back, so the oldest can be deleted once the cache is full.
+    fn font(&self, face: UiFont, height: i32) -> HFONT {
+        let mut fonts = self.fonts.borrow_mut();
+        if let Some(&(_, _, font)) = fonts
+            .iter()
+            .find(|(key, size, _)| *key == face && *size == height)
+        {
+            return font;
+        }
+        if fonts.len() >= FONT_CACHE_LIMIT {
+            let (_, _, oldest) = fonts.remove(0);
+            unsafe {
+                let _ = DeleteObject(HGDIOBJ(oldest.0));
+            }
+        }
+        let font = make_ui_font(face, height);
+        if font.0 != 0 {
+            fonts.push((face, height, font));
+        }
+        font
+    }
+
+    fn release(&self) {
+        unsafe {
+            for (_, brush) in self.brushes.borrow_mut().drain(..) {
+                let _ = DeleteObject(HGDIOBJ(brush.0));
+            }
+            for (_, pen) in self.pens.borrow_mut().drain(..) {
+                let _ = DeleteObject(HGDIOBJ(pen.0));
+            }
+            for (_, _, font) in self.fonts.borrow_mut().drain(..) {
+                let _ = DeleteObject(HGDIOBJ(font.0));
+            }
+        }
+    }
+}
+
+// ------------ Back buffer ------------
+struct BackBuffer {
+    dc: HDC,
+    bmp: HBITMAP,
+    old: HGDIOBJ,
+    bits: *mut u8,
+    stride: i32,
+    w: i32,
+    h: i32,
+}
+
+impl BackBuffer {
+    unsafe fn new(width: i32, height: i32) -> anyhow::Result<Self> {
+        let dc = CreateCompatibleDC(HDC(0));
+
+        let bi = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height, // top-down
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut bits: *mut core::ffi::c_void = core::ptr::null_mut();
+        let bmp = CreateDIBSection(HDC(0), &bi, DIB_RGB_COLORS, &mut bits, None, 0)?;
+        if bmp.is_invalid() {
+            return Err(anyhow::anyhow!("CreateDIBSection failed"));
+        }
+        let old = SelectObject(dc, bmp);
+        let stride = width.max(1) * 4;
+        let mut buffer = Self {
+            dc,
+            bmp,
+            old,
+            bits: bits as *mut u8,
+            stride,
+            w: width,
+            h: height,
+        };
         buffer.clear();
         Ok(buffer)
     }
@@ -1084,11 +2720,36 @@ impl BackBuffer {
     }
 }
 
+/// Compact mode and the score overlay both do without the status bar.
+fn status_bar_shown(settings: &Settings) -> bool {
+    !settings.compact && !settings.score_overlay
+}
+
+fn apply_status_bar_visibility(hwnd: HWND, state: &mut WindowState) {
+    let show = if status_bar_shown(&state.settings) {
+        SW_SHOW
+    } else {
+        SW_HIDE
+    };
+    // The table gains or loses the status bar's strip, and a victory animation in progress
+    // bounces off the new floor.
+    unsafe {
+        ShowWindow(state.status, show);
+        ensure_backbuffer(hwnd, state, 0, 0);
+    }
+    forget_layout(hwnd, state);
+    request_redraw(hwnd);
+}
+
 fn status_bar_height(status: HWND) -> i32 {
     if status.0 == 0 {
         return 0;
     }
     unsafe {
+        // Compact mode hides the bar, and the table takes its strip.
+        if !IsWindowVisible(status).as_bool() {
+            return 0;
+        }
         let mut rect = RECT::default();
         if GetWindowRect(status, &mut rect).is_err() {
             return 0;
@@ -1128,6 +2789,22 @@ struct CardImage {
     hbm: HBITMAP,
     cell_w: i32,
     cell_h: i32,
+    /// The sheet has a card back in the first cell of an extra row below the faces.
+    has_back: bool,
+}
+
+/// The sprite sheet and the memory DC it is selected into. Cards come from the sheet only
+/// when both are there; otherwise they are drawn in code.
+#[derive(Clone, Copy)]
+struct CardArt<'a> {
+    image: Option<&'a CardImage>,
+    dc: HDC,
+}
+
+impl<'a> CardArt<'a> {
+    fn sheet(self) -> Option<&'a CardImage> {
+        self.image.filter(|_| self.dc.0 != 0)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -1139,7 +2816,18 @@ struct CardMetrics {
     face_down_offset: i32,
     face_up_offset: i32,
     face_inset: i32,
+    waste_fan_offset: i32,
     margin: i32,
+    /// Lowest the bottom edge of a tableau card may reach before its column starts to close up.
+    tableau_bottom: i32,
+    /// Fill for empty pile slots, a shade darker than the felt.
+    slot_fill: COLORREF,
+    /// Overlay rank and suit in the corner of every face-up card.
+    corner_indices: bool,
+    /// Stock and waste on the right, foundations on the left.
+    left_handed: bool,
+    /// Colour of the drop shadow under every card, or `None` when shadows are off.
+    shadow: Option<COLORREF>,
 }
 
 #[derive(Clone, Copy, Default)]
@@ -1167,6 +2855,7 @@ impl CardMetrics {
         let face_down_offset_base = (card_base_h / 6).max(12);
         let face_up_offset_base = (card_base_h / 4).max(20);
         let face_inset_base = (card_base_w / 24).max(4);
+        let waste_fan_base = (card_base_w / 4).max(12);
 
         let required_width = margin_base * 2 + card_base_w * 7 + column_gap_base * 6;
         let mut max_tableau_height = card_base_h;
@@ -1197,22 +2886,39 @@ impl CardMetrics {
         let height = height.max(1);
         let scale_w = width as f32 / required_width as f32;
         let scale_h = height as f32 / required_height as f32;
-        let mut scale = scale_w.min(scale_h);
-        scale = scale.clamp(0.35, 4.0);
+        let dpi_scale = state.dpi.max(1) as f32 / BASE_DPI as f32;
+        let scale = layout_scale(scale_w, scale_h, dpi_scale, state.settings.card_size);
 
+        // Minimums are in 96-DPI pixels so small layouts stay legible on high-DPI screens.
         let scale_i32 = |value: i32, minimum: i32| -> i32 {
+            let minimum = (minimum as f32 * dpi_scale).round() as i32;
             ((value as f32 * scale).round() as i32).max(minimum)
         };
 
+        let card_w = scale_i32(card_base_w, 8);
+        let column_gap = scale_i32(column_gap_base, 6);
+        // The fanned waste spills into the unused column beside it; cap the offset so the
+        // fan never reaches the neighbouring foundation or stock.
+        let max_fan = ((card_w + column_gap) / 2).max(1);
+
         Self {
-            card_w: scale_i32(card_base_w, 8),
+            card_w,
             card_h: scale_i32(card_base_h, 12),
-            column_gap: scale_i32(column_gap_base, 6),
+            column_gap,
             row_gap: scale_i32(row_gap_base, 8),
             face_down_offset: scale_i32(face_down_offset_base, 6),
             face_up_offset: scale_i32(face_up_offset_base, 10),
             face_inset: scale_i32(face_inset_base, 2),
+            waste_fan_offset: scale_i32(waste_fan_base, 4).min(max_fan),
             margin: scale_i32(margin_base, 12),
+            tableau_bottom: height - scale_i32(margin_base, 12),
+            slot_fill: felt_slot_color(state.settings.felt),
+            corner_indices: state.settings.corner_indices_enabled(state.card.is_some()),
+            left_handed: state.settings.left_handed,
+            shadow: state
+                .settings
+                .card_shadows
+                .then(|| felt_shadow_color(state.settings.felt)),
         }
     }
 
@@ -1220,6 +2926,25 @@ impl CardMetrics {
         self.margin + column as i32 * (self.card_w + self.column_gap)
     }
 
+    fn stock_x(&self) -> i32 {
+        self.column_x(top_row_column(PileRef::Stock, self.left_handed))
+    }
+
+    fn foundation_x(&self, index: usize) -> i32 {
+        self.column_x(top_row_column(PileRef::Foundation(index), self.left_handed))
+    }
+
+    /// Left edge of the lowest of `fanned` extra waste cards. The fan always opens to the
+    /// right, so the left-handed layout starts it that much short of the waste column.
+    fn waste_x(&self, fanned: usize) -> i32 {
+        let x = self.column_x(top_row_column(PileRef::Waste, self.left_handed));
+        if self.left_handed {
+            x - fanned as i32 * self.waste_fan_offset
+        } else {
+            x
+        }
+    }
+
     fn top_y(&self) -> i32 {
         self.margin
     }
@@ -1227,6 +2952,251 @@ impl CardMetrics {
     fn tableau_y(&self) -> i32 {
         self.margin + self.card_h + self.row_gap
     }
+
+    /// Top edge of each card in a tableau column. When the column would run past
+    /// `tableau_bottom`, the face-up steps close up like an accordion, down to a sliver, so
+    /// the bottom card stays in view; face-down steps keep their size.
+    fn tableau_tops(&self, cards: &[Card]) -> Vec<i32> {
+        let steps = &cards[..cards.len().saturating_sub(1)];
+        let face_up = steps.iter().filter(|card| card.face_up).count() as i32;
+        let face_down = steps.len() as i32 - face_up;
+        let room = self.tableau_bottom
+            - self.tableau_y()
+            - self.card_h
+            - face_down * self.face_down_offset;
+        let mut face_up_step = self.face_up_offset;
+        if face_up > 0 && face_up * face_up_step > room {
+            let sliver = (self.face_down_offset / 2).max(1);
+            face_up_step = (room / face_up).clamp(sliver, self.face_up_offset);
+        }
+        let mut y = self.tableau_y();
+        cards
+            .iter()
+            .map(|card| {
+                let top = y;
+                y += if card.face_up {
+                    face_up_step
+                } else {
+                    self.face_down_offset
+                };
+                top
+            })
+            .collect()
+    }
+}
+
+/// The card of a tableau column drawn at height `y`, given each card's top edge. Later cards
+/// cover earlier ones, so the last card whose face spans `y` wins.
+fn tableau_index_at(tops: &[i32], card_h: i32, y: i32) -> Option<usize> {
+    tops.iter().rposition(|&top| y >= top && y < top + card_h)
+}
+
+/// Scale that fits the table into the window, capped by the card size preset. The fit always
+/// wins over the preset, so seven columns stay on screen down to the minimum window size.
+fn layout_scale(scale_w: f32, scale_h: f32, dpi_scale: f32, size: CardSize) -> f32 {
+    scale_w
+        .min(scale_h)
+        .min(size.max_scale() * dpi_scale)
+        .max(MIN_LAYOUT_SCALE)
+}
+
+/// Number of waste cards shown side by side: as many as a click draws, up to the fan's width.
+fn waste_fan_len(game: &GameState) -> usize {
+    let visible = usize::from(game.draw_mode.count()).min(WASTE_FAN_CARDS);
+    game.waste_count().min(visible)
+}
+
+/// Board column a top-row pile sits above. The left-handed layout mirrors the stock and waste
+/// to the right edge and moves the foundations, still in order, to the left.
+fn top_row_column(pile: PileRef, left_handed: bool) -> usize {
+    match (pile, left_handed) {
+        (PileRef::Stock, false) => 0,
+        (PileRef::Waste, false) => 1,
+        (PileRef::Foundation(index), false) => 3 + index,
+        (PileRef::Stock, true) => TABLEAU_COLUMNS - 1,
+        (PileRef::Waste, true) => TABLEAU_COLUMNS - 2,
+        (PileRef::Foundation(index), true) => index,
+        (PileRef::Tableau(column), _) => column,
+    }
+}
+
+/// Left edge of the topmost (playable) waste card.
+fn waste_top_x(game: &GameState, metrics: &CardMetrics) -> i32 {
+    let fanned = waste_fan_len(game).saturating_sub(1);
+    metrics.waste_x(fanned) + fanned as i32 * metrics.waste_fan_offset
+}
+
+/// Where each movable card of `game` is drawn, keyed by pile. Stock cards and the waste cards
+/// under the top one are left out since nothing can move them directly.
+fn card_positions(game: &GameState, metrics: &CardMetrics) -> Vec<(Card, PileRef, (i32, i32))> {
+    let top_y = metrics.top_y();
+    let mut positions = Vec::new();
+    if let Some(card) = game.waste.cards.last() {
+        positions.push((*card, PileRef::Waste, (waste_top_x(game, metrics), top_y)));
+    }
+    for (index, pile) in game.foundations.iter().enumerate() {
+        let origin = (metrics.foundation_x(index), top_y);
+        for card in &pile.cards {
+            positions.push((*card, PileRef::Foundation(index), origin));
+        }
+    }
+    for (column, pile) in game.tableaus.iter().enumerate() {
+        let x = metrics.column_x(column);
+        let tops = metrics.tableau_tops(&pile.cards);
+        for (card, y) in pile.cards.iter().zip(tops) {
+            positions.push((*card, PileRef::Tableau(column), (x, y)));
+        }
+    }
+    positions
+}
+
+/// Starts tweens for every card that changed pile between `before` and the current game.
+/// Cards bound for the same tableau column travel together; everything else is staggered.
+fn animate_moves(hwnd: HWND, state: &mut WindowState, before: &GameState) {
+    let metrics = state.layout_metrics.unwrap_or_else(|| {
+        let (w, h) = state.client_size;
+        CardMetrics::compute(state, w.max(1), h.max(1))
+    });
+    let old = card_positions(before, &metrics);
+    let now = Instant::now();
+    let mut batch: Option<(PileRef, Instant)> = None;
+    for (card, pile, to) in card_positions(&state.game, &metrics) {
+        if !matches!(pile, PileRef::Foundation(_) | PileRef::Tableau(_)) {
+            continue;
+        }
+        let Some(&(_, old_pile, from)) = old
+            .iter()
+            .find(|(old_card, _, _)| old_card.sprite_index == card.sprite_index)
+        else {
+            continue;
+        };
+        if old_pile == pile {
+            continue;
+        }
+        let start = match batch {
+            None => now,
+            Some((last_pile, last_start)) => {
+                if last_pile == pile && matches!(pile, PileRef::Tableau(_)) {
+                    last_start
+                } else {
+                    last_start + TWEEN_STAGGER
+                }
+            }
+        };
+        batch = Some((pile, start));
+        state.tweens.push(MoveTween {
+            card,
+            from,
+            to,
+            start,
+        });
+    }
+    if batch.is_some() {
+        unsafe {
+            SetTimer(hwnd, TWEEN_TIMER_ID, TWEEN_FRAME_MS, None);
+        }
+    }
+    start_flip_animations(hwnd, state, before);
+}
+
+/// Sweeps the waste cards that were showing in `before` back onto the stock, top card first,
+/// as the reverse of drawing them. The game has already turned them over, so they fly face down.
+fn recycle_tweens(before: &GameState, metrics: &CardMetrics, now: Instant) -> Vec<MoveTween> {
+    let fan_len = waste_fan_len(before);
+    let waste_x = metrics.waste_x(fan_len.saturating_sub(1));
+    let to = (metrics.stock_x(), metrics.top_y());
+    let waste = &before.waste.cards;
+    waste[waste.len() - fan_len..]
+        .iter()
+        .enumerate()
+        .rev()
+        .zip(0u32..)
+        .map(|((offset, &card), order)| MoveTween {
+            card: Card {
+                face_up: false,
+                ..card
+            },
+            from: (
+                waste_x + offset as i32 * metrics.waste_fan_offset,
+                metrics.top_y(),
+            ),
+            to,
+            start: now + RECYCLE_STAGGER * order,
+        })
+        .collect()
+}
+
+fn animate_recycle(hwnd: HWND, state: &mut WindowState, before: &GameState) {
+    if !state.settings.animate_deal {
+        return;
+    }
+    let metrics = state.layout_metrics.unwrap_or_else(|| {
+        let (w, h) = state.client_size;
+        CardMetrics::compute(state, w.max(1), h.max(1))
+    });
+    let tweens = recycle_tweens(before, &metrics, Instant::now());
+    if !tweens.is_empty() {
+        state.tweens.extend(tweens);
+        unsafe {
+            SetTimer(hwnd, TWEEN_TIMER_ID, TWEEN_FRAME_MS, None);
+        }
+    }
+}
+
+/// Starts a flip for each column whose top card was face down in `before` and is face up now.
+fn start_flip_animations(hwnd: HWND, state: &mut WindowState, before: &GameState) {
+    if !state.settings.animate_flips || state.win_anim.is_some() {
+        return;
+    }
+    let now = Instant::now();
+    let mut started = false;
+    for (column, pile) in state.game.tableaus.iter().enumerate() {
+        let Some(top) = pile.cards.last().filter(|card| card.face_up) else {
+            continue;
+        };
+        let was_hidden = before.tableaus[column]
+            .cards
+            .iter()
+            .any(|card| card.sprite_index == top.sprite_index && !card.face_up);
+        if was_hidden {
+            state.flips[column] = Some(FlipAnimation {
+                sprite_index: top.sprite_index,
+                start: now,
+            });
+            started = true;
+        }
+    }
+    if started {
+        unsafe {
+            SetTimer(hwnd, FLIP_TIMER_ID, FLIP_FRAME_MS, None);
+        }
+    }
+}
+
+fn update_flip_animations(hwnd: HWND, state: &mut WindowState) {
+    let now = Instant::now();
+    for flip in &mut state.flips {
+        if flip.as_ref().is_some_and(|anim| anim.finished(now)) {
+            *flip = None;
+        }
+    }
+    if state.flips.iter().all(Option::is_none) {
+        unsafe {
+            let _ = KillTimer(hwnd, FLIP_TIMER_ID);
+        }
+    }
+    request_redraw(hwnd);
+}
+
+fn update_tweens(hwnd: HWND, state: &mut WindowState) {
+    let now = Instant::now();
+    state.tweens.retain(|tween| !tween.finished(now));
+    if state.tweens.is_empty() {
+        unsafe {
+            let _ = KillTimer(hwnd, TWEEN_TIMER_ID);
+        }
+    }
+    request_redraw(hwnd);
 }
 
 fn make_rect(x: i32, y: i32, w: i32, h: i32) -> RECT {
@@ -1238,7 +3208,7 @@ fn make_rect(x: i32, y: i32, w: i32, h: i32) -> RECT {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 enum HitTarget {
     Stock,
     Waste,
@@ -1247,13 +3217,15 @@ enum HitTarget {
         column: usize,
         card_index: Option<usize>,
     },
+    #[default]
     None,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum Selection {
     Waste,
     Tableau { column: usize, index: usize },
+    Foundation(usize),
 }
 
 struct DragContext {
@@ -1275,6 +3247,111 @@ struct MouseDownContext {
 enum DragSource {
     Waste,
     Tableau { column: usize },
+    Foundation(usize),
+}
+
+/// A card sliding from where it was to the pile it now belongs to. The game state has already
+/// changed; the destination pile hides the card until the tween ends.
+struct MoveTween {
+    card: Card,
+    from: (i32, i32),
+    to: (i32, i32),
+    start: Instant,
+}
+
+impl MoveTween {
+    fn position(&self, now: Instant) -> (i32, i32) {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f32();
+        let t = (elapsed / TWEEN_DURATION.as_secs_f32()).min(1.0);
+        let eased = 1.0 - (1.0 - t) * (1.0 - t);
+        let lerp = |a: i32, b: i32| a + ((b - a) as f32 * eased).round() as i32;
+        (lerp(self.from.0, self.to.0), lerp(self.from.1, self.to.1))
+    }
+
+    fn finished(&self, now: Instant) -> bool {
+        now >= self.start + TWEEN_DURATION
+    }
+}
+
+/// A tableau top card turning face up: the back narrows to nothing, then the face widens.
+struct FlipAnimation {
+    sprite_index: u8,
+    start: Instant,
+}
+
+impl FlipAnimation {
+    /// Visible width as a fraction of the card, and whether the face side is showing.
+    fn phase(&self, now: Instant) -> (f32, bool) {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f32();
+        let t = (elapsed / FLIP_DURATION.as_secs_f32()).min(1.0);
+        if t < 0.5 {
+            (1.0 - t * 2.0, false)
+        } else {
+            (t * 2.0 - 1.0, true)
+        }
+    }
+
+    fn finished(&self, now: Instant) -> bool {
+        now >= self.start + FLIP_DURATION
+    }
+}
+
+/// One tableau card of the opening deal, flown out from the stock.
+struct DealCard {
+    column: usize,
+    index: usize,
+    /// Flight progress from 0 (at the stock) to 1 (in its slot).
+    progress: f32,
+    emitted: bool,
+}
+
+/// Lays out the tableau one card at a time, in the same order the engine deals it.
+struct DealAnimation {
+    cards: Vec<DealCard>,
+    emit_timer: f32,
+    last_tick: Instant,
+}
+
+impl DealAnimation {
+    fn new() -> Self {
+        let cards = (0..TABLEAU_COLUMNS)
+            .flat_map(|column| (0..=column).map(move |index| (column, index)))
+            .map(|(column, index)| DealCard {
+                column,
+                index,
+                progress: 0.0,
+                emitted: false,
+            })
+            .collect();
+        Self {
+            cards,
+            emit_timer: DEAL_EMIT_INTERVAL,
+            last_tick: Instant::now(),
+        }
+    }
+
+    /// Advances by the time since the last tick; returns true once every card has landed.
+    fn tick(&mut self, now: Instant) -> bool {
+        let delta = (now - self.last_tick).as_secs_f32().min(ANIM_MAX_DELTA);
+        self.last_tick = now;
+        self.emit_timer += delta;
+        for card in &mut self.cards {
+            if card.emitted {
+                card.progress = (card.progress + delta / DEAL_FLIGHT_SECS).min(1.0);
+            } else if self.emit_timer >= DEAL_EMIT_INTERVAL {
+                self.emit_timer -= DEAL_EMIT_INTERVAL;
+                card.emitted = true;
+            }
+        }
+        self.cards.iter().all(|card| card.progress >= 1.0)
+    }
+
+    fn landed(&self, column: usize, index: usize) -> bool {
+        self.cards
+            .iter()
+            .find(|card| card.column == column && card.index == index)
+            .is_none_or(|card| card.progress >= 1.0)
+    }
 }
 
 struct AnimCard {
@@ -1400,12 +3477,7 @@ impl ClassicVictoryAnimation {
         }
     }
 
-    fn flush_pending(
-        &mut self,
-        card_image: Option<&CardImage>,
-        card_dc: HDC,
-        metrics: &CardMetrics,
-    ) {
+    fn flush_pending(&mut self, art: CardArt, metrics: &CardMetrics, gdi: &GdiCache) {
         if self.pending.is_empty() {
             return;
         }
@@ -1414,7 +3486,7 @@ impl ClassicVictoryAnimation {
             for clone in self.pending.drain(..) {
                 let x = clone.pos.0.round() as i32;
                 let y = clone.pos.1.round() as i32;
-                draw_card_face_up_to_dc(card_image, card_dc, metrics, layer.dc, &clone.card, x, y);
+                draw_card_face_up_to_dc(art, metrics, layer.dc, gdi, &clone.card, x, y);
                 let rect = make_rect(x, y, metrics.card_w, metrics.card_h);
                 unsafe {
                     layer.fill_alpha(rect, 255);
@@ -1461,18 +3533,59 @@ fn point_in_rect(x: i32, y: i32, left: i32, top: i32, width: i32, height: i32) -
     x >= left && x < left + width && y >= top && y < top + height
 }
 
-fn stop_victory_animation(hwnd: HWND, state: &mut WindowState) {
-    if state.victory_timer_active {
-        unsafe {
-            let _ = KillTimer(hwnd, VICTORY_TIMER_ID);
-        }
-        state.victory_timer_active = false;
+fn start_deal_animation(hwnd: HWND, state: &mut WindowState) {
+    stop_deal_animation(hwnd, state);
+    if !state.settings.animate_deal {
+        return;
+    }
+    state.deal_anim = Some(DealAnimation::new());
+    unsafe {
+        SetTimer(hwnd, DEAL_TIMER_ID, DEAL_FRAME_MS, None);
+    }
+}
+
+fn stop_deal_animation(hwnd: HWND, state: &mut WindowState) {
+    if state.deal_anim.take().is_some() {
+        unsafe {
+            let _ = KillTimer(hwnd, DEAL_TIMER_ID);
+        }
+        request_redraw(hwnd);
+    }
+}
+
+fn update_deal_animation(hwnd: HWND, state: &mut WindowState) {
+    let finished = state
+        .deal_anim
+        .as_mut()
+        .is_none_or(|anim| anim.tick(Instant::now()));
+    if finished {
+        stop_deal_animation(hwnd, state);
+    }
+    request_redraw(hwnd);
+}
+
+fn stop_victory_animation(hwnd: HWND, state: &mut WindowState) {
+    if state.victory_timer_active {
+        unsafe {
+            let _ = KillTimer(hwnd, VICTORY_TIMER_ID);
+        }
+        state.victory_timer_active = false;
+    }
+    if state.win_anim.take().is_some() {
+        // The animation is the heaviest GDI user; a count that climbs from one win to the
+        // next means something in the drawing path is leaking handles.
+        #[cfg(debug_assertions)]
+        debug_log(&format!(
+            "GDI objects after the victory animation: {}",
+            unsafe { GetGuiResources(GetCurrentProcess(), GR_GDIOBJECTS) }
+        ));
     }
-    state.win_anim = None;
 }
 
 fn start_victory_animation_internal(hwnd: HWND, state: &mut WindowState, force: bool) -> bool {
-    if state.win_anim.is_some() {
+    // Turned off, nothing is built and no timer is set, so callers go straight on to the
+    // win summary.
+    if state.win_anim.is_some() || !state.settings.victory_animation {
         return false;
     }
 
@@ -1551,11 +3664,11 @@ fn force_victory_animation(hwnd: HWND, state: &mut WindowState) -> bool {
 fn gather_animation_seeds(state: &WindowState, metrics: &CardMetrics) -> Vec<AnimationSeed> {
     let mut seeds = Vec::new();
     let top_y = metrics.top_y() as f32;
-    let waste_x = metrics.column_x(1) as f32;
+    let waste_x = metrics.waste_x(0) as f32;
 
-    // Foundations emit from the top-right stacks.
+    // Foundations emit from their stacks along the top row.
     for (idx, pile) in state.game.foundations.iter().enumerate() {
-        let base_x = metrics.column_x(3 + idx) as f32;
+        let base_x = metrics.foundation_x(idx) as f32;
         for (offset, card) in pile.cards.iter().enumerate() {
             let mut c = *card;
             c.face_up = true;
@@ -1579,7 +3692,7 @@ fn gather_animation_seeds(state: &WindowState, metrics: &CardMetrics) -> Vec<Ani
     }
 
     // Stock pile
-    let stock_x = metrics.column_x(0) as f32;
+    let stock_x = metrics.stock_x() as f32;
     for (offset, card) in state.game.stock.cards.iter().enumerate() {
         let mut c = *card;
         c.face_up = true;
@@ -1591,24 +3704,18 @@ fn gather_animation_seeds(state: &WindowState, metrics: &CardMetrics) -> Vec<Ani
     }
 
     // Tableau columns
-    let tableau_top = metrics.tableau_y() as f32;
     for column in 0..TABLEAU_COLUMNS {
         let x = metrics.column_x(column) as f32;
-        let mut y = tableau_top;
         if let Some(pile) = state.game.tableaus.get(column) {
-            for card in &pile.cards {
+            let tops = metrics.tableau_tops(&pile.cards);
+            for (card, y) in pile.cards.iter().zip(tops) {
                 let mut c = *card;
                 c.face_up = true;
                 seeds.push(AnimationSeed {
                     card: c,
-                    pos: (x, y),
+                    pos: (x, y as f32),
                     foundation: None,
                 });
-                y += if card.face_up {
-                    metrics.face_up_offset as f32
-                } else {
-                    metrics.face_down_offset as f32
-                };
             }
         }
     }
@@ -1803,6 +3910,7 @@ fn update_victory_animation(hwnd: HWND, state: &mut WindowState) {
     let metrics = CardMetrics::compute(state, width.max(1), height.max(1));
     let card_dc = state.card_dc;
     let card_image_ptr = state.card.as_ref().map(|img| img as *const CardImage);
+    let gdi = &state.gdi;
     let Some(animation) = state.win_anim.as_mut() else {
         return;
     };
@@ -1822,25 +3930,34 @@ fn update_victory_animation(hwnd: HWND, state: &mut WindowState) {
             let speed_scale =
                 1.0 + (state.pointer_speed * ANIM_POINTER_SCALE).min(ANIM_MAX_POINTER_SCALE);
 
-            anim.emit_timer += delta * speed_scale;
             anim.accumulator += delta * speed_scale;
 
+            // Read every tick, so a preset picked mid-animation changes only the forces and
+            // spacing from here on; positions and velocities carry straight over.
+            let physics = state.settings.victory_physics;
+            let gravity = ANIM_GRAVITY * physics.gravity_scale();
+            let interval = ANIM_EMIT_INTERVAL * physics.emit_interval_scale();
+            let launch = speed_scale * physics.launch_scale();
+
             let card_w = metrics.card_w as f32;
             let card_h = metrics.card_h as f32;
             let width_f = width.max(1) as f32;
             let height_f = height.max(1) as f32;
             let floor_y = (height_f - card_h).max(0.0);
-            while anim.emit_timer >= ANIM_EMIT_INTERVAL && anim.next_emit < anim.cards.len() {
-                emit_victory_card(anim, anim.next_emit, speed_scale, card_w, width_f);
-                anim.next_emit += 1;
-                anim.emit_timer -= ANIM_EMIT_INTERVAL;
-            }
-
             while anim.accumulator >= ANIM_FIXED_DT {
                 anim.accumulator -= ANIM_FIXED_DT;
+                // Launching on the fixed step rather than per frame starts each card at the
+                // same simulated moment however the timer ticks happen to land.
+                anim.emit_timer += ANIM_FIXED_DT;
+                while anim.emit_timer >= interval && anim.next_emit < anim.cards.len() {
+                    emit_victory_card(anim, anim.next_emit, launch, card_w, width_f);
+                    anim.next_emit += 1;
+                    anim.emit_timer -= interval;
+                }
                 integrate_victory_cards(
                     &mut anim.cards,
                     ANIM_FIXED_DT,
+                    gravity,
                     floor_y,
                     card_w,
                     card_h,
@@ -1896,7 +4013,11 @@ fn update_victory_animation(hwnd: HWND, state: &mut WindowState) {
             }
 
             let card_image = unsafe { card_image_ptr.map(|ptr| &*ptr) };
-            anim.flush_pending(card_image, card_dc, &metrics);
+            let art = CardArt {
+                image: card_image,
+                dc: card_dc,
+            };
+            anim.flush_pending(art, &metrics, gdi);
 
             anim.next_emit >= anim.emitters.len()
                 && anim
@@ -1911,6 +4032,7 @@ fn update_victory_animation(hwnd: HWND, state: &mut WindowState) {
 
     if finished {
         stop_victory_animation(hwnd, state);
+        post_win_summary(hwnd, state);
         request_redraw(hwnd);
     }
 }
@@ -1947,6 +4069,7 @@ fn emit_victory_card(
 fn integrate_victory_cards(
     cards: &mut [AnimCard],
     dt: f32,
+    gravity: f32,
     floor_y: f32,
     card_w: f32,
     card_h: f32,
@@ -1959,7 +4082,7 @@ fn integrate_victory_cards(
             continue;
         }
 
-        card.vel.1 += ANIM_GRAVITY * dt;
+        card.vel.1 += gravity * dt;
         card.pos.0 += card.vel.0 * dt;
         card.pos.1 += card.vel.1 * dt;
 
@@ -1994,377 +4117,2647 @@ fn integrate_victory_cards(
     }
 }
 
-fn check_for_victory(hwnd: HWND, state: &mut WindowState) {
-    if state.win_anim.is_some() {
+/// Switching scoring rules starts a fresh deal so the running total is never a mix of both.
+fn change_scoring_mode(hwnd: HWND, state: &mut WindowState, mode: ScoringMode, cumulative: bool) {
+    if state.game.scoring_mode == mode && state.game.vegas_cumulative == cumulative {
         return;
     }
-    if state.game.is_won() {
-        start_victory_animation(hwnd, state);
+    stop_victory_animation(hwnd, state);
+    state.settings.scoring_mode = mode;
+    state.settings.vegas_cumulative = cumulative;
+    settings::save_settings(&state.settings);
+    let snapshot = state.game.clone();
+    state.game.set_scoring_mode(mode, cumulative);
+    let draw_mode = state.game.draw_mode;
+    match state.game.deal_new_game(draw_mode) {
+        Ok(()) => {
+            state.settle_previous_game(&snapshot);
+            state.push_undo(snapshot);
+            state.clock.reset();
+            state.clear_transients();
+            state.unpause();
+            start_deal_animation(hwnd, state);
+        }
+        Err(err) => {
+            debug_log(&format!("deal_new_game failed: {err:?}"));
+            state.game = snapshot;
+        }
     }
+    unsafe {
+        update_scoring_menu(hwnd, &state.game);
+    }
+    update_status_bar(state);
+    request_redraw(hwnd);
 }
-fn hit_test(state: &WindowState, x: i32, y: i32) -> HitTarget {
-    let metrics = state.layout_metrics.unwrap_or_else(|| {
-        let (w, h) = state.client_size;
-        CardMetrics::compute(state, w.max(1), h.max(1))
-    });
 
-    let card_w = metrics.card_w;
-    let card_h = metrics.card_h;
-    let top_y = metrics.top_y();
+/// Solves the position on the winnability worker and lights the status bar's finish button
+/// once it is proven winnable. Each check is numbered so a result for an older position is
+/// dropped, and a newer check makes the worker abandon the one in hand.
+fn start_winnable_check(state: &mut WindowState) {
+    state.winnable = false;
+    state.winnable_generation = state.winnable_generation.wrapping_add(1);
+    let generation = state.winnable_generation;
+    let busy = state.game.is_won()
+        || state.win_anim.is_some()
+        || state.replay.is_some()
+        || state.auto_finish.is_some();
+    if busy || state.status.0 == 0 {
+        // Nothing is checked, so the position is looked at again once the way is clear.
+        state.winnable_checked = None;
+        if let Some(worker) = &state.winnable_worker {
+            worker.request(generation, None);
+        }
+        return;
+    }
+    // The status bar is a child of the main window, which owns the message.
+    let owner = unsafe { GetParent(state.status) };
+    state
+        .winnable_worker
+        .get_or_insert_with(|| {
+            SolverWorker::spawn(owner, WM_WINNABLE_CHECKED, |position, ticket| {
+                let budget = Duration::from_millis(WINNABLE_CHECK_BUDGET_MS);
+                check_winnable(&position, ticket, budget).map(isize::from)
+            })
+        })
+        .request(generation, Some(state.game.to_solver_position()));
+    state.winnable_checked = Some(state.game.clone());
+}
 
-    let stock_x = metrics.column_x(0);
-    let stock_height = card_h;
-    if point_in_rect(x, y, stock_x, top_y, card_w, stock_height) {
-        return HitTarget::Stock;
+/// A job for a [`SolverWorker`], tagged with the generation of the request it came from.
+type SolverJob<J> = (u32, J);
+
+/// One background solver thread. It holds only the newest job it has been handed, so a
+/// burst of requests costs one search rather than one each, and the job in hand gives up
+/// as soon as a newer one comes in.
+struct SolverWorker<J> {
+    /// The job waiting to be run and its generation; the worker takes it out.
+    pending: Arc<(Mutex<Option<SolverJob<J>>>, Condvar)>,
+    /// Generation of the newest request; a job for any other one stops early.
+    latest: Arc<AtomicU32>,
+}
+
+impl<J: Send + 'static> SolverWorker<J> {
+    /// Starts the thread. `run` works through each job and gives up with `None` once its
+    /// ticket goes stale; an answer it does reach is posted to `owner` as `message`, with the
+    /// job's generation in `wparam` and the answer in `lparam`.
+    fn spawn(owner: HWND, message: u32, run: fn(J, &Ticket) -> Option<isize>) -> Self {
+        let worker = SolverWorker {
+            pending: Arc::new((Mutex::new(None), Condvar::new())),
+            latest: Arc::new(AtomicU32::new(0)),
+        };
+        let pending = Arc::clone(&worker.pending);
+        let latest = Arc::clone(&worker.latest);
+        std::thread::spawn(move || loop {
+            let (generation, job) = {
+                let (slot, ready) = &*pending;
+                let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+                loop {
+                    if let Some(job) = slot.take() {
+                        break job;
+                    }
+                    slot = ready.wait(slot).unwrap_or_else(|e| e.into_inner());
+                }
+            };
+            let ticket = Ticket {
+                generation,
+                latest: &latest,
+            };
+            if let Some(answer) = run(job, &ticket) {
+                unsafe {
+                    let _ =
+                        PostMessageW(owner, message, WPARAM(generation as usize), LPARAM(answer));
+                }
+            }
+        });
+        worker
     }
 
-    let waste_x = metrics.column_x(1);
-    if point_in_rect(x, y, waste_x, top_y, card_w, card_h) && state.game.waste_count() > 0 {
-        return HitTarget::Waste;
+    /// Hands the worker `job` as request `generation`, replacing any still waiting. With no
+    /// job the worker just drops what it has.
+    fn request(&self, generation: u32, job: Option<J>) {
+        self.latest.store(generation, Ordering::Relaxed);
+        let (slot, ready) = &*self.pending;
+        *slot.lock().unwrap_or_else(|e| e.into_inner()) = job.map(|job| (generation, job));
+        ready.notify_one();
     }
+}
 
-    for foundation in 0..FOUNDATION_COLUMNS {
-        let fx = metrics.column_x(3 + foundation);
-        if point_in_rect(x, y, fx, top_y, card_w, card_h) {
-            return HitTarget::Foundation(foundation);
+/// The generation a worker's job was requested as, and where the newest request's is kept.
+struct Ticket<'a> {
+    generation: u32,
+    latest: &'a AtomicU32,
+}
+
+impl Ticket<'_> {
+    fn is_current(&self) -> bool {
+        self.latest.load(Ordering::Relaxed) == self.generation
+    }
+}
+
+/// Steps `solver` a few hundred nodes at a time until it has a verdict or `budget` runs out,
+/// which gives `Timeout`. `None` once `ticket` has been overtaken by a newer request.
+fn solve_while_current(
+    solver: &mut solver::Solver,
+    ticket: &Ticket,
+    budget: Duration,
+) -> Option<SolveResult> {
+    let deadline = Instant::now() + budget;
+    loop {
+        if !ticket.is_current() {
+            return None;
+        }
+        match solver.step(SOLVER_STEP_NODES) {
+            Some(true) => return Some(SolveResult::Winnable),
+            Some(false) => return Some(SolveResult::Unwinnable),
+            None if Instant::now() >= deadline => return Some(SolveResult::Timeout),
+            None => {}
         }
     }
+}
 
-    let tableau_top = metrics.tableau_y();
-    for column in 0..TABLEAU_COLUMNS {
-        let col_x = metrics.column_x(column);
-        if x < col_x || x >= col_x + card_w {
-            continue;
+/// Whether `position` can be proven winnable within `budget`; running out of time counts as
+/// not winnable, as far as the finish button is concerned. `None` once `ticket` is stale.
+fn check_winnable(position: &solver::Position, ticket: &Ticket, budget: Duration) -> Option<bool> {
+    let mut solver = solver::Solver::from_position(position);
+    solve_while_current(&mut solver, ticket, budget).map(|result| result == SolveResult::Winnable)
+}
+
+/// What makes two games the same deal as far as its difficulty goes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct DealKey {
+    seed: u64,
+    draw_mode: DrawMode,
+    allow_any_to_empty: bool,
+}
+
+impl DealKey {
+    fn of(game: &GameState) -> Self {
+        Self {
+            seed: game.rng_seed,
+            draw_mode: game.draw_mode,
+            allow_any_to_empty: game.allow_any_to_empty,
         }
+    }
+}
 
-        let cards = match state.game.tableau_column(column) {
-            Some(cards) => cards,
-            None => continue,
-        };
-        let slots = &state.tableau_slots[column];
+/// How hard a deal's opening is, judged by how many positions the solver had to expand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Unwinnable,
+    /// The solver ran out of time before settling the deal either way.
+    Unknown,
+}
 
-        if cards.is_empty() {
-            let slot = slots.first().copied().unwrap_or(CardSlot {
-                top: tableau_top,
-                height: card_h,
-            });
-            if point_in_rect(x, y, col_x, slot.top, card_w, slot.height.max(card_h)) {
-                return HitTarget::Tableau {
-                    column,
-                    card_index: None,
-                };
-            }
-            continue;
+impl Difficulty {
+    const ALL: [Difficulty; 5] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Unwinnable,
+        Difficulty::Unknown,
+    ];
+
+    /// Rates a deal from the solver's verdict and the `nodes` it expanded reaching it.
+    fn rate(result: SolveResult, nodes: u64) -> Self {
+        match result {
+            SolveResult::Winnable if nodes <= EASY_MAX_NODES => Difficulty::Easy,
+            SolveResult::Winnable if nodes <= MEDIUM_MAX_NODES => Difficulty::Medium,
+            SolveResult::Winnable => Difficulty::Hard,
+            SolveResult::Unwinnable => Difficulty::Unwinnable,
+            SolveResult::Timeout => Difficulty::Unknown,
         }
+    }
 
-        if slots.len() == cards.len() {
-            for (idx, slot) in slots.iter().enumerate().rev() {
-                let height = if idx + 1 == cards.len() {
-                    card_h
-                } else {
-                    slot.height
-                };
-                if point_in_rect(x, y, col_x, slot.top, card_w, height.max(1)) {
-                    return HitTarget::Tableau {
-                        column,
-                        card_index: Some(idx),
-                    };
-                }
-            }
-            if let Some(last) = slots.last() {
-                let bottom = last.top + card_h;
-                if y >= last.top && y < bottom {
-                    return HitTarget::Tableau {
-                        column,
-                        card_index: Some(cards.len() - 1),
-                    };
-                }
-            }
-            continue;
+    fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Unwinnable => "Unwinnable",
+            Difficulty::Unknown => "Unknown",
         }
+    }
+}
 
-        let mut y_pos = tableau_top;
-        for (idx, card) in cards.iter().enumerate() {
-            let height = if idx + 1 == cards.len() {
-                card_h
-            } else if card.face_up {
-                metrics.face_up_offset
-            } else {
-                metrics.face_down_offset
-            };
-            if point_in_rect(x, y, col_x, y_pos, card_w, height.max(1)) {
-                return HitTarget::Tableau {
-                    column,
-                    card_index: Some(idx),
-                };
-            }
-            let offset = if card.face_up {
-                metrics.face_up_offset
-            } else {
-                metrics.face_down_offset
-            };
-            y_pos += offset;
+/// Rates the deal in play on the difficulty worker and posts the result as
+/// `WM_DIFFICULTY_RATED`. Only the opening is solved, so moves made since the deal do not
+/// restart the rating, but a new deal calls off the rating of the last one.
+fn start_difficulty_rating(state: &mut WindowState) {
+    state.difficulty = None;
+    state.difficulty_generation = state.difficulty_generation.wrapping_add(1);
+    state.difficulty_deal = Some(DealKey::of(&state.game));
+    let generation = state.difficulty_generation;
+    if state.game.rng_seed == 0 || state.status.0 == 0 {
+        if let Some(worker) = &state.difficulty_worker {
+            worker.request(generation, None);
         }
-        if y >= tableau_top && y < y_pos + card_h {
-            return HitTarget::Tableau {
-                column,
-                card_index: Some(cards.len() - 1),
-            };
+        return;
+    }
+    let owner = unsafe { GetParent(state.status) };
+    state
+        .difficulty_worker
+        .get_or_insert_with(|| {
+            SolverWorker::spawn(owner, WM_DIFFICULTY_RATED, |game, ticket| {
+                let Some(mut solver) = game.opening_solver() else {
+                    return Some(Difficulty::Unknown as isize);
+                };
+                let budget = Duration::from_millis(DIFFICULTY_BUDGET_MS);
+                let result = solve_while_current(&mut solver, ticket, budget)?;
+                Some(Difficulty::rate(result, solver.nodes()) as isize)
+            })
+        })
+        .request(generation, Some(state.game.clone()));
+}
+
+/// Undoes (or with `redo`, redoes) one move, or with `all` every move to the start or end of
+/// the line, then brings the menus, status bar and victory check up to date once.
+fn step_history(hwnd: HWND, state: &mut WindowState, redo: bool, all: bool) {
+    stop_victory_animation(hwnd, state);
+    let mut stepped = false;
+    loop {
+        let moved = if redo {
+            state.history.redo(&mut state.game)
+        } else {
+            state.history.undo(&mut state.game)
+        };
+        stepped |= moved;
+        if !moved || !all {
+            break;
         }
     }
+    if !stepped {
+        return;
+    }
+    state.clear_transients();
+    update_status_bar(state);
+    unsafe {
+        refresh_rule_menus(hwnd, &state.game);
+    }
+    check_for_victory(hwnd, state);
+    request_redraw(hwnd);
+}
 
-    HitTarget::None
+/// The positions Go Back may return to, newest first: `current`, then the undo `snapshots`
+/// back to this deal's opening. Anything older belongs to a game already over and scored.
+fn rewind_candidates<'a>(
+    current: &'a GameState,
+    snapshots: &'a VecDeque<GameState>,
+) -> Vec<&'a GameState> {
+    let deal = DealKey::of(current);
+    let mut candidates = vec![current];
+    if current.moves == 0 {
+        return candidates;
+    }
+    for game in snapshots.iter().rev() {
+        if DealKey::of(game) != deal {
+            break;
+        }
+        candidates.push(game);
+        if game.moves == 0 {
+            break;
+        }
+    }
+    candidates
 }
 
-fn tableau_card_top(
-    state: &WindowState,
-    metrics: &CardMetrics,
-    column: usize,
-    index: usize,
-) -> i32 {
-    if let Some(slot) = state.tableau_slots[column].get(index) {
-        slot.top
-    } else {
-        let mut y = metrics.tableau_y();
-        if let Some(cards) = state.game.tableau_column(column) {
-            for (i, card) in cards.iter().enumerate() {
-                if i == index {
-                    return y;
-                }
-                y += if card.face_up {
-                    metrics.face_up_offset
-                } else {
-                    metrics.face_down_offset
-                };
-            }
+/// Number of undos back to the first of `candidates` that `winnable` accepts, or `Some(None)`
+/// when it takes none of them. `winnable` can call the whole search off by giving `None`.
+fn last_winnable_rewind<T>(
+    candidates: &[T],
+    mut winnable: impl FnMut(&T) -> Option<bool>,
+) -> Option<Option<usize>> {
+    for (steps, candidate) in candidates.iter().enumerate() {
+        if winnable(candidate)? {
+            return Some(Some(steps));
         }
-        y
     }
+    Some(None)
 }
 
-fn inset_rect(rect: RECT, inset: i32) -> RECT {
-    RECT {
-        left: rect.left + inset,
-        top: rect.top + inset,
-        right: rect.right - inset,
-        bottom: rect.bottom - inset,
+/// Walks this deal's undo history back on the Go Back worker, asking the solver about each
+/// position in turn, and reports the nearest winnable one as `WM_REWIND_FOUND`.
+fn start_rewind_search(hwnd: HWND, state: &mut WindowState) {
+    if !state.settings.rewind_assist || state.rewind_from.is_some() || state.replay.is_some() {
+        return;
+    }
+    state.rewind_from = Some(state.game.clone());
+    state.status_note = Some("Looking for the last winnable position...".to_string());
+    update_status_bar(state);
+
+    let positions = rewind_candidates(&state.game, &state.history.undo)
+        .into_iter()
+        .map(GameState::to_solver_position)
+        .collect();
+    state.rewind_generation = state.rewind_generation.wrapping_add(1);
+    state
+        .rewind_worker
+        .get_or_insert_with(|| {
+            SolverWorker::spawn(hwnd, WM_REWIND_FOUND, |positions, ticket| {
+                let budget = Duration::from_millis(REWIND_CHECK_BUDGET_MS);
+                let steps = last_winnable_rewind(&positions, |position| {
+                    let mut solver = solver::Solver::from_position(position);
+                    let result = solve_while_current(&mut solver, ticket, budget)?;
+                    Some(result == SolveResult::Winnable)
+                })?;
+                Some(steps.map_or(-1, |steps| steps as isize))
+            })
+        })
+        .request(state.rewind_generation, Some(positions));
+}
+
+/// Calls off a Go Back search that is still running, once play has moved on from where it
+/// started.
+fn cancel_rewind_search(state: &mut WindowState) {
+    if state.rewind_from.take().is_none() {
+        return;
+    }
+    state.status_note = None;
+    state.rewind_generation = state.rewind_generation.wrapping_add(1);
+    if let Some(worker) = &state.rewind_worker {
+        worker.request(state.rewind_generation, None);
     }
 }
 
-fn draw_round_rect_fill(dc: HDC, rect: RECT, radius: i32, fill: COLORREF, border: COLORREF) {
-    unsafe {
-        let brush = CreateSolidBrush(fill);
-        if brush.0 == 0 {
+/// Offers to undo back to the position the Go Back search found, as long as the board is
+/// still where the search started.
+fn finish_rewind_search(hwnd: HWND, state: &mut WindowState, steps: Option<usize>) {
+    let Some(from) = state.rewind_from.take() else {
+        return;
+    };
+    state.status_note = None;
+    // A result for a position played on since, or hidden behind a pause, is dropped.
+    if from != state.game || state.paused {
+        update_status_bar(state);
+        return;
+    }
+    let steps = match steps {
+        Some(0) => {
+            state.status_note = Some("This position can still be won".to_string());
+            update_status_bar(state);
             return;
         }
-        let pen = CreatePen(PS_SOLID, 1, border);
-        if pen.0 == 0 {
-            let _ = DeleteObject(HGDIOBJ(brush.0));
+        Some(steps) => steps,
+        None => {
+            state.status_note = Some("No winnable position found in the undo history".to_string());
+            update_status_bar(state);
             return;
         }
-        let old_brush = SelectObject(dc, HGDIOBJ(brush.0));
-        let old_pen = SelectObject(dc, HGDIOBJ(pen.0));
-        let radius = radius.max(0);
-        let _ = RoundRect(
-            dc,
-            rect.left,
-            rect.top,
-            rect.right,
-            rect.bottom,
-            radius,
-            radius,
-        );
-        if old_brush.0 != 0 {
-            let _ = SelectObject(dc, old_brush);
-        }
-        if old_pen.0 != 0 {
-            let _ = SelectObject(dc, old_pen);
-        }
-        let _ = DeleteObject(HGDIOBJ(brush.0));
-        let _ = DeleteObject(HGDIOBJ(pen.0));
+    };
+    let plural = if steps == 1 { "" } else { "s" };
+    let prompt = to_wide(&format!(
+        "The game was last winnable {steps} move{plural} ago. Go back there?"
+    ));
+    let answer = unsafe {
+        MessageBoxW(
+            hwnd,
+            PCWSTR(prompt.as_ptr()),
+            w!("Go Back"),
+            MB_YESNO | MB_ICONQUESTION,
+        )
+    };
+    if answer != IDYES {
+        update_status_bar(state);
+        return;
+    }
+    stop_victory_animation(hwnd, state);
+    for _ in 0..steps {
+        state.history.undo(&mut state.game);
+    }
+    state.clear_transients();
+    update_status_bar(state);
+    unsafe {
+        refresh_rule_menus(hwnd, &state.game);
     }
+    request_redraw(hwnd);
 }
 
-fn draw_round_outline(dc: HDC, rect: RECT, radius: i32, color: COLORREF, thickness: i32) {
-    unsafe {
-        let pen = CreatePen(PS_SOLID, thickness.max(1), color);
-        if pen.0 == 0 {
-            return;
+/// Searches for a winnable deal on a worker thread; the result comes back as
+/// `WM_SOLVABLE_DEAL_READY` so the message loop keeps running meanwhile.
+fn start_solvable_deal(hwnd: HWND, state: &mut WindowState) {
+    if state.solvable_pending {
+        return;
+    }
+    state.solvable_pending = true;
+    state.status_note = Some("Looking for a solvable deal...".to_string());
+    update_status_bar(state);
+
+    let mut game = state.game.clone();
+    let hwnd_raw = hwnd.0;
+    std::thread::spawn(move || {
+        let draw_mode = game.draw_mode;
+        let result = match game.deal_new_solvable(draw_mode, SOLVABLE_DEAL_ATTEMPTS) {
+            Ok(_) => Some(game),
+            Err(err) => {
+                debug_log(&format!("deal_new_solvable failed: {err:?}"));
+                None
+            }
+        };
+        let payload = Box::into_raw(Box::new(result));
+        unsafe {
+            if PostMessageW(
+                HWND(hwnd_raw),
+                WM_SOLVABLE_DEAL_READY,
+                WPARAM(0),
+                LPARAM(payload as isize),
+            )
+            .is_err()
+            {
+                drop(Box::from_raw(payload));
+            }
         }
-        let hollow = GetStockObject(HOLLOW_BRUSH);
-        let old_pen = SelectObject(dc, HGDIOBJ(pen.0));
-        let old_brush = SelectObject(dc, hollow);
+    });
+}
+
+fn finish_solvable_deal(hwnd: HWND, state: &mut WindowState, result: Option<GameState>) {
+    state.solvable_pending = false;
+    stop_auto_finish(hwnd, state);
+    let Some(game) = result else {
+        state.status_note = Some("No solvable deal found in time".to_string());
+        update_status_bar(state);
+        return;
+    };
+    stop_victory_animation(hwnd, state);
+    if let Some(drag) = state.drag.take() {
+        state.game = drag.snapshot;
+        unsafe {
+            let _ = ReleaseCapture();
+        }
+    }
+    let snapshot = std::mem::replace(&mut state.game, game);
+    state.settle_previous_game(&snapshot);
+    state.solvable_deals = true;
+    state.push_undo(snapshot);
+    state.clock.reset();
+    state.clear_transients();
+    state.unpause();
+    start_deal_animation(hwnd, state);
+    unsafe {
+        refresh_rule_menus(hwnd, &state.game);
+    }
+    update_status_bar(state);
+    request_redraw(hwnd);
+}
+
+/// Plays the current game out with real moves, one step per timer tick, when the solver
+/// can find a winning line.
+fn start_auto_finish(hwnd: HWND, state: &mut WindowState) {
+    if state.win_anim.is_some() || state.drag.is_some() || state.game.is_won() {
+        return;
+    }
+    let line = state
+        .game
+        .solve_line(Duration::from_millis(AUTO_FINISH_BUDGET_MS));
+    match line {
+        Some(line) if !line.is_empty() => unsafe {
+            if SetTimer(hwnd, AUTO_FINISH_TIMER_ID, AUTO_FINISH_STEP_MS, None) != 0 {
+                state.auto_finish = Some(line.into());
+                state.pending_selection = None;
+                state.hint = None;
+            }
+        },
+        _ => {
+            state.status_note = Some("No winning line found".to_string());
+            update_status_bar(state);
+            unsafe {
+                let _ = MessageBeep(MB_OK);
+            }
+        }
+    }
+}
+
+fn step_auto_finish(hwnd: HWND, state: &mut WindowState) {
+    let Some(step) = state
+        .auto_finish
+        .as_mut()
+        .and_then(|steps| steps.pop_front())
+    else {
+        stop_auto_finish(hwnd, state);
+        return;
+    };
+    let snapshot = state.game.clone();
+    if state.game.play_hint(step) {
+        state.play_sound(match step.to {
+            PileRef::Foundation(_) => Sound::Foundation,
+            PileRef::Tableau(_) => Sound::Tableau,
+            PileRef::Stock | PileRef::Waste => Sound::Stock,
+        });
+        animate_moves(hwnd, state, &snapshot);
+        state.push_undo(snapshot);
+        state.hint = Some(step);
+        update_status_bar(state);
+    } else {
+        debug_log(&format!("auto finish step rejected: {step:?}"));
+        if let Some(steps) = state.auto_finish.as_mut() {
+            steps.clear();
+        }
+    }
+    if state.auto_finish.as_ref().is_none_or(VecDeque::is_empty) {
+        stop_auto_finish(hwnd, state);
+        check_for_victory(hwnd, state);
+    }
+    request_redraw(hwnd);
+}
+
+fn stop_auto_finish(hwnd: HWND, state: &mut WindowState) {
+    if state.auto_finish.take().is_some() {
+        unsafe {
+            let _ = KillTimer(hwnd, AUTO_FINISH_TIMER_ID);
+        }
+        state.hint = None;
+        request_redraw(hwnd);
+    }
+}
+
+/// Plays a batch of foundation moves, such as Autoplay or Collect, as one undo step, with
+/// `note` saying on the status bar how it went. Beeps when `play` finds nothing to move.
+fn run_batch_foundation_move(
+    hwnd: HWND,
+    state: &mut WindowState,
+    play: impl FnOnce(&mut GameState) -> usize,
+    note: impl FnOnce(usize) -> Option<String>,
+) {
+    if state.win_anim.is_some() || state.drag.is_some() {
+        return;
+    }
+    let snapshot = state.game.clone();
+    let moved = play(&mut state.game);
+    if moved == 0 {
+        unsafe {
+            let _ = MessageBeep(MB_OK);
+        }
+        return;
+    }
+    state.play_sound(Sound::Foundation);
+    animate_moves(hwnd, state, &snapshot);
+    state.push_undo(snapshot);
+    state.pending_selection = None;
+    if let Some(text) = note(moved) {
+        state.status_note = Some(text);
+    }
+    update_status_bar(state);
+    check_for_victory(hwnd, state);
+    request_redraw(hwnd);
+}
+
+/// Replaces the game in play with the one `deal` lays out, the way New Game, Deal Again,
+/// Restart and Deal by Seed all do: the old game is settled and kept for undo, and the new
+/// one starts with a fresh clock and board. `what` names the deal in the debug log if it fails.
+fn start_new_deal(
+    hwnd: HWND,
+    state: &mut WindowState,
+    what: &str,
+    deal: impl FnOnce(&mut GameState) -> anyhow::Result<()>,
+) {
+    stop_victory_animation(hwnd, state);
+    let snapshot = state.game.clone();
+    match deal(&mut state.game) {
+        Ok(()) => {
+            state.settle_previous_game(&snapshot);
+            state.push_undo(snapshot);
+            state.clock.reset();
+            state.clear_transients();
+            state.unpause();
+            start_deal_animation(hwnd, state);
+            state.layout_metrics = None;
+            update_status_bar(state);
+        }
+        Err(err) => debug_log(&format!("{what} failed: {err:?}")),
+    }
+    request_redraw(hwnd);
+}
+
+/// Commands that replace the game in play. Closing is not among them: the autosave brings
+/// the game back on the next launch.
+const DISCARDING_COMMANDS: [u16; 7] = [
+    constants::IDM_FILE_NEW,
+    constants::IDM_FILE_DEALAGAIN,
+    constants::IDM_GAME_RESTART,
+    constants::IDM_GAME_NEW_SOLVABLE,
+    constants::IDM_GAME_DEAL_BY_SEED,
+    constants::IDM_FILE_LOAD,
+    constants::IDM_FILE_REPLAY,
+];
+
+/// Commands that play on, or peek at, the board the pause is hiding. They do nothing until
+/// the game is resumed; those that replace the game resume it instead.
+const PAUSE_BLOCKED_COMMANDS: [u16; 11] = [
+    constants::IDM_GAME_AUTOPLAY,
+    constants::IDM_GAME_COLLECT_LOW,
+    constants::IDM_GAME_HINT,
+    constants::IDM_GAME_AUTOCOMPLETE,
+    constants::IDM_GAME_REWIND_WINNABLE,
+    constants::IDM_GAME_PEEK,
+    constants::IDM_GAME_VICTORY,
+    constants::IDM_EDIT_UNDO,
+    constants::IDM_EDIT_REDO,
+    constants::IDM_EDIT_UNDO_ALL,
+    constants::IDM_EDIT_REDO_ALL,
+];
+
+/// Whether replacing `game` would throw away play worth asking about: at least one move made,
+/// not yet won, and the result not already settled (as it is for a replayed game).
+fn discard_needs_confirmation(game: &GameState, settings: &Settings, settled: bool) -> bool {
+    settings.confirm_discard && !settled && game.moves > 0 && !game.is_won()
+}
+
+/// Offers to save an unfinished game before `command` replaces it; `false` if the player
+/// cancels. Load only offers to discard, since saving would overwrite the very game it is
+/// about to read.
+fn confirm_discard(hwnd: HWND, state: &mut WindowState, command: u16) -> bool {
+    if !discard_needs_confirmation(&state.game, &state.settings, state.result_recorded) {
+        return true;
+    }
+    let progress = foundation_progress(state.best_foundation);
+    if command == constants::IDM_FILE_LOAD {
+        let text = to_wide(&format!(
+            "This game is still in progress ({progress}). Discard it and load the saved game?"
+        ));
+        let answer = unsafe {
+            MessageBoxW(
+                hwnd,
+                PCWSTR(text.as_ptr()),
+                w!("Solitaire"),
+                MB_OKCANCEL | MB_ICONQUESTION,
+            )
+        };
+        return answer == IDOK;
+    }
+    let text = to_wide(&format!(
+        "This game is still in progress ({progress}). Save it before leaving it?"
+    ));
+    let answer = unsafe {
+        MessageBoxW(
+            hwnd,
+            PCWSTR(text.as_ptr()),
+            w!("Solitaire"),
+            MB_YESNOCANCEL | MB_ICONQUESTION,
+        )
+    };
+    if answer == IDCANCEL {
+        return false;
+    }
+    if answer == IDYES {
+        save_game(state);
+    }
+    true
+}
+
+fn save_game(state: &WindowState) {
+    let Some(path) = app_data_dir().map(|dir| dir.join(SAVED_GAME_FILE)) else {
+        debug_log("save_game: no data directory");
+        return;
+    };
+    if let Err(err) = state.game.save_to_path(&path) {
+        debug_log(&format!("save_game failed: {err:?}"));
+        unsafe {
+            let _ = MessageBeep(MB_OK);
+        }
+    }
+}
+
+/// Deals the game recorded by Export Moves and hands the arrow keys and Space over to stepping
+/// through it. Escape or any menu command ends the replay and leaves the board as it stands.
+fn start_replay(hwnd: HWND, state: &mut WindowState) {
+    let log = app_data_dir()
+        .map(|dir| dir.join(MOVE_LOG_FILE))
+        .ok_or_else(|| anyhow::anyhow!("no data directory"))
+        .and_then(|path| Ok(std::fs::read_to_string(path)?))
+        .and_then(|text| MoveLog::parse(&text));
+    let dealt = log.and_then(|log| Ok((log.deal()?, log.moves)));
+    let (mut game, moves) = match dealt {
+        Ok(dealt) => dealt,
+        Err(err) => {
+            debug_log(&format!("start_replay failed: {err:?}"));
+            unsafe {
+                let _ = MessageBeep(MB_OK);
+            }
+            return;
+        }
+    };
+    // Whether flips are manual is a preference, not a rule; the log records every flip.
+    game.manual_flips = state.game.manual_flips;
+    stop_victory_animation(hwnd, state);
+    let previous = std::mem::replace(&mut state.game, game);
+    state.settle_previous_game(&previous);
+    // Nothing that happens on a replayed board counts towards the statistics.
+    state.result_recorded = true;
+    state.history.clear();
+    state.clock.reset();
+    state.clear_transients();
+    state.unpause();
+    state.replay = Some(Replay { moves, position: 0 });
+    unsafe {
+        refresh_rule_menus(hwnd, &state.game);
+    }
+    update_status_bar(state);
+    request_redraw(hwnd);
+}
+
+/// Plays the next recorded move, or takes back the last one, stopping at either end.
+fn step_replay(hwnd: HWND, state: &mut WindowState, forward: bool) {
+    let Some(replay) = state.replay.as_mut() else {
+        return;
+    };
+    let snapshot = state.game.clone();
+    let stepped = if forward {
+        replay
+            .moves
+            .get(replay.position)
+            .is_some_and(|&mv| state.game.apply(mv).is_ok())
+    } else {
+        replay
+            .position
+            .checked_sub(1)
+            .is_some_and(|last| state.game.undo(replay.moves[last]).is_ok())
+    };
+    if !stepped {
+        return;
+    }
+    if forward {
+        replay.position += 1;
+    } else {
+        replay.position -= 1;
+    }
+    state.clear_transients();
+    animate_moves(hwnd, state, &snapshot);
+    update_status_bar(state);
+    request_redraw(hwnd);
+}
+
+/// Writes the seed and move log next to the saved game and says where in the status bar.
+fn export_moves(state: &mut WindowState) {
+    let Some(path) = app_data_dir().map(|dir| dir.join(MOVE_LOG_FILE)) else {
+        debug_log("export_moves: no data directory");
+        return;
+    };
+    match std::fs::write(&path, state.game.export_move_log()) {
+        Ok(()) => state.status_note = Some(format!("Moves exported to {}", path.display())),
+        Err(err) => {
+            debug_log(&format!("export_moves failed: {err:?}"));
+            unsafe {
+                let _ = MessageBeep(MB_OK);
+            }
+        }
+    }
+}
+
+/// Persists the game in play so the next launch can pick it up. Finished games are
+/// discarded so the next launch deals fresh.
+fn write_autosave(state: &WindowState) {
+    let Some(path) = app_data_dir().map(|dir| dir.join(AUTOSAVE_FILE)) else {
+        return;
+    };
+    // A drag in flight has lifted cards out of the game; save the pre-drag snapshot.
+    let game = state
+        .drag
+        .as_ref()
+        .map_or(&state.game, |drag| &drag.snapshot);
+    if game.is_won() {
+        let _ = std::fs::remove_file(&path);
+        return;
+    }
+    if let Err(err) = game.save_to_path(&path) {
+        debug_log(&format!("write_autosave failed: {err:?}"));
+    }
+}
+
+fn restore_autosave() -> Option<GameState> {
+    let path = app_data_dir()?.join(AUTOSAVE_FILE);
+    if !path.exists() {
+        return None;
+    }
+    match GameState::load_from_path(&path) {
+        Ok(game) if !game.is_won() => Some(game),
+        Ok(_) => None,
+        Err(err) => {
+            debug_log(&format!("restore_autosave failed: {err:?}"));
+            None
+        }
+    }
+}
+
+fn load_game(hwnd: HWND, state: &mut WindowState) {
+    let loaded = app_data_dir()
+        .map(|dir| dir.join(SAVED_GAME_FILE))
+        .ok_or_else(|| anyhow::anyhow!("no data directory"))
+        .and_then(|path| GameState::load_from_path(&path));
+    match loaded {
+        Ok(game) => {
+            stop_victory_animation(hwnd, state);
+            state.game = game;
+            // A save of an already-won game should not count as another win.
+            state.result_recorded = state.game.is_won();
+            state.history.clear();
+            state.clock.reset();
+            state.clear_transients();
+            state.unpause();
+            state.peek = false;
+            unsafe {
+                refresh_rule_menus(hwnd, &state.game);
+            }
+            update_status_bar(state);
+            check_for_victory(hwnd, state);
+            request_redraw(hwnd);
+        }
+        Err(err) => {
+            debug_log(&format!("load_game failed: {err:?}"));
+            unsafe {
+                let _ = MessageBeep(MB_OK);
+            }
+        }
+    }
+}
+
+fn check_for_victory(hwnd: HWND, state: &mut WindowState) {
+    if state.win_anim.is_some() {
+        return;
+    }
+    if state.game.is_won() {
+        state.clock.stop();
+        if !state.result_recorded {
+            state.result_recorded = true;
+            state.win_summary_pending = true;
+            state.game.award_time_bonus(state.clock.elapsed());
+            state.stats.record_win(state.clock.elapsed());
+            stats::save_stats(&state.stats);
+        }
+        update_status_bar(state);
+        if start_victory_animation(hwnd, state) {
+            state.play_sound(Sound::Victory);
+        } else {
+            post_win_summary(hwnd, state);
+        }
+    }
+}
+
+fn post_win_summary(hwnd: HWND, state: &mut WindowState) {
+    if !std::mem::take(&mut state.win_summary_pending) {
+        return;
+    }
+    if state.settings.auto_deal {
+        // The win is already in the statistics; only the next deal waits.
+        state.auto_deal_pending = true;
+        state.status_note = Some("Next deal in a moment: press a key or click to stay".to_string());
+        update_status_bar(state);
+        unsafe {
+            let _ = SetTimer(hwnd, AUTO_DEAL_TIMER_ID, AUTO_DEAL_DELAY_MS, None);
+        }
+        return;
+    }
+    unsafe {
+        let _ = PostMessageW(hwnd, WM_SHOW_WIN_SUMMARY, WPARAM(0), LPARAM(0));
+    }
+}
+
+/// Calls off a pending automatic deal; `true` if there was one. With `show_summary` the
+/// player stays on the won table and gets the win summary they skipped.
+fn cancel_auto_deal(hwnd: HWND, state: &mut WindowState, show_summary: bool) -> bool {
+    if !std::mem::take(&mut state.auto_deal_pending) {
+        return false;
+    }
+    unsafe {
+        let _ = KillTimer(hwnd, AUTO_DEAL_TIMER_ID);
+    }
+    state.status_note = None;
+    update_status_bar(state);
+    if show_summary {
+        unsafe {
+            let _ = PostMessageW(hwnd, WM_SHOW_WIN_SUMMARY, WPARAM(0), LPARAM(0));
+        }
+    }
+    true
+}
+
+/// Deals the same kind of game the player last asked for, once the pause after a win is up.
+fn deal_after_win(hwnd: HWND, state: &mut WindowState) {
+    unsafe {
+        let _ = KillTimer(hwnd, AUTO_DEAL_TIMER_ID);
+    }
+    if !std::mem::take(&mut state.auto_deal_pending) || !state.game.is_won() {
+        return;
+    }
+    let command = if state.solvable_deals {
+        constants::IDM_GAME_NEW_SOLVABLE
+    } else {
+        constants::IDM_FILE_NEW
+    };
+    unsafe {
+        let _ = PostMessageW(hwnd, WM_COMMAND, WPARAM(command as usize), LPARAM(0));
+    }
+}
+fn hit_test(state: &WindowState, x: i32, y: i32) -> HitTarget {
+    let metrics = state.layout_metrics.unwrap_or_else(|| {
+        let (w, h) = state.client_size;
+        CardMetrics::compute(state, w.max(1), h.max(1))
+    });
+
+    let card_w = metrics.card_w;
+    let card_h = metrics.card_h;
+    let top_y = metrics.top_y();
+
+    let stock_x = metrics.stock_x();
+    let stock_height = card_h;
+    if point_in_rect(x, y, stock_x, top_y, card_w, stock_height) {
+        return HitTarget::Stock;
+    }
+
+    let waste_x = waste_top_x(&state.game, &metrics);
+    if point_in_rect(x, y, waste_x, top_y, card_w, card_h) && state.game.waste_count() > 0 {
+        return HitTarget::Waste;
+    }
+
+    for foundation in 0..FOUNDATION_COLUMNS {
+        let fx = metrics.foundation_x(foundation);
+        if point_in_rect(x, y, fx, top_y, card_w, card_h) {
+            return HitTarget::Foundation(foundation);
+        }
+    }
+
+    let tableau_top = metrics.tableau_y();
+    for column in 0..TABLEAU_COLUMNS {
+        let col_x = metrics.column_x(column);
+        if x < col_x || x >= col_x + card_w {
+            continue;
+        }
+
+        let cards = match state.game.tableau_column(column) {
+            Some(cards) => cards,
+            None => continue,
+        };
+        let slots = &state.tableau_slots[column];
+
+        if cards.is_empty() {
+            let slot = slots.first().copied().unwrap_or(CardSlot {
+                top: tableau_top,
+                height: card_h,
+            });
+            if point_in_rect(x, y, col_x, slot.top, card_w, slot.height.max(card_h)) {
+                return HitTarget::Tableau {
+                    column,
+                    card_index: None,
+                };
+            }
+            continue;
+        }
+
+        if slots.len() == cards.len() {
+            for (idx, slot) in slots.iter().enumerate().rev() {
+                let height = if idx + 1 == cards.len() {
+                    card_h
+                } else {
+                    slot.height
+                };
+                if point_in_rect(x, y, col_x, slot.top, card_w, height.max(1)) {
+                    return HitTarget::Tableau {
+                        column,
+                        card_index: Some(idx),
+                    };
+                }
+            }
+            if let Some(last) = slots.last() {
+                let bottom = last.top + card_h;
+                if y >= last.top && y < bottom {
+                    return HitTarget::Tableau {
+                        column,
+                        card_index: Some(cards.len() - 1),
+                    };
+                }
+            }
+            continue;
+        }
+
+        if let Some(idx) = tableau_index_at(&metrics.tableau_tops(cards), card_h, y) {
+            return HitTarget::Tableau {
+                column,
+                card_index: Some(idx),
+            };
+        }
+    }
+
+    HitTarget::None
+}
+
+fn tableau_card_top(
+    state: &WindowState,
+    metrics: &CardMetrics,
+    column: usize,
+    index: usize,
+) -> i32 {
+    if let Some(slot) = state.tableau_slots[column].get(index) {
+        slot.top
+    } else {
+        let cards = state.game.tableau_column(column).unwrap_or_default();
+        let tops = metrics.tableau_tops(cards);
+        tops.get(index)
+            .or(tops.last())
+            .copied()
+            .unwrap_or_else(|| metrics.tableau_y())
+    }
+}
+
+fn inset_rect(rect: RECT, inset: i32) -> RECT {
+    RECT {
+        left: rect.left + inset,
+        top: rect.top + inset,
+        right: rect.right - inset,
+        bottom: rect.bottom - inset,
+    }
+}
+
+fn draw_round_rect_fill(
+    dc: HDC,
+    gdi: &GdiCache,
+    rect: RECT,
+    radius: i32,
+    fill: COLORREF,
+    border: COLORREF,
+) {
+    unsafe {
+        let brush = gdi.brush(fill);
+        let pen = gdi.pen(border);
+        if brush.0 == 0 || pen.0 == 0 {
+            return;
+        }
+        let old_brush = SelectObject(dc, HGDIOBJ(brush.0));
+        let old_pen = SelectObject(dc, HGDIOBJ(pen.0));
         let radius = radius.max(0);
         let _ = RoundRect(
             dc,
             rect.left,
             rect.top,
-            rect.right,
-            rect.bottom,
-            radius,
-            radius,
+            rect.right,
+            rect.bottom,
+            radius,
+            radius,
+        );
+        if old_brush.0 != 0 {
+            let _ = SelectObject(dc, old_brush);
+        }
+        if old_pen.0 != 0 {
+            let _ = SelectObject(dc, old_pen);
+        }
+    }
+}
+
+fn draw_round_outline(dc: HDC, rect: RECT, radius: i32, color: COLORREF, thickness: i32) {
+    unsafe {
+        let pen = CreatePen(PS_SOLID, thickness.max(1), color);
+        if pen.0 == 0 {
+            return;
+        }
+        let hollow = GetStockObject(HOLLOW_BRUSH);
+        let old_pen = SelectObject(dc, HGDIOBJ(pen.0));
+        let old_brush = SelectObject(dc, hollow);
+        let radius = radius.max(0);
+        let _ = RoundRect(
+            dc,
+            rect.left,
+            rect.top,
+            rect.right,
+            rect.bottom,
+            radius,
+            radius,
+        );
+        if old_pen.0 != 0 {
+            let _ = SelectObject(dc, old_pen);
+        }
+        if old_brush.0 != 0 {
+            let _ = SelectObject(dc, old_brush);
+        }
+        let _ = DeleteObject(HGDIOBJ(pen.0));
+    }
+}
+
+fn highlight_rect(dc: HDC, metrics: &CardMetrics, rect: RECT, color: COLORREF, thickness: i32) {
+    let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
+    let pad = thickness / 2 + 1;
+    draw_round_outline(dc, inset_rect(rect, -pad), radius + pad, color, thickness);
+}
+
+/// Screen rectangle covering the cards of `pile` that a hint refers to. For tableau sources
+/// this spans the moving run; for destinations it is the card (or slot) being built on.
+fn hint_pile_rect(
+    state: &WindowState,
+    metrics: &CardMetrics,
+    pile: PileRef,
+    cards: usize,
+    is_source: bool,
+) -> RECT {
+    let top_y = metrics.top_y();
+    match pile {
+        PileRef::Stock => make_rect(metrics.stock_x(), top_y, metrics.card_w, metrics.card_h),
+        PileRef::Waste => make_rect(
+            waste_top_x(&state.game, metrics),
+            top_y,
+            metrics.card_w,
+            metrics.card_h,
+        ),
+        PileRef::Foundation(index) => make_rect(
+            metrics.foundation_x(index),
+            top_y,
+            metrics.card_w,
+            metrics.card_h,
+        ),
+        PileRef::Tableau(column) => {
+            let x = metrics.column_x(column);
+            let len = state.game.tableau_len(column);
+            if len == 0 {
+                return make_rect(x, metrics.tableau_y(), metrics.card_w, metrics.card_h);
+            }
+            let first = if is_source {
+                len.saturating_sub(cards.max(1))
+            } else {
+                len - 1
+            };
+            let top = tableau_card_top(state, metrics, column, first);
+            let bottom = tableau_card_top(state, metrics, column, len - 1) + metrics.card_h;
+            RECT {
+                left: x,
+                top,
+                right: x + metrics.card_w,
+                bottom,
+            }
+        }
+    }
+}
+
+/// The pile and cards a click-selection refers to, or `None` if it no longer matches the game.
+fn selected_cards(game: &GameState, selection: Selection) -> Option<(PileRef, &[Card])> {
+    match selection {
+        Selection::Waste => {
+            let len = game.waste.cards.len();
+            let cards = game.waste.cards.get(len.checked_sub(1)?..)?;
+            Some((PileRef::Waste, cards))
+        }
+        Selection::Tableau { column, index } => {
+            let cards = game.tableau_column(column)?.get(index..)?;
+            (!cards.is_empty()).then_some((PileRef::Tableau(column), cards))
+        }
+        Selection::Foundation(index) => {
+            let cards = &game.foundations.get(index)?.cards;
+            let cards = cards.get(cards.len().checked_sub(1)?..)?;
+            Some((PileRef::Foundation(index), cards))
+        }
+    }
+}
+
+/// Every pile the current selection could legally be moved to.
+fn legal_destinations(game: &GameState, selection: Selection) -> Vec<PileRef> {
+    let Some((source, cards)) = selected_cards(game, selection) else {
+        return Vec::new();
+    };
+    let mut targets = Vec::new();
+    let from_foundation = matches!(source, PileRef::Foundation(_));
+    if let ([card], false) = (cards, from_foundation) {
+        targets.extend(
+            (0..FOUNDATION_COLUMNS)
+                .filter(|&index| game.can_accept_foundation(index, *card))
+                .map(PileRef::Foundation),
+        );
+    }
+    targets.extend(
+        (0..TABLEAU_COLUMNS)
+            .filter(|&column| {
+                source != PileRef::Tableau(column) && game.can_accept_tableau_stack(column, cards)
+            })
+            .map(PileRef::Tableau),
+    );
+    targets
+}
+
+/// Outlines the click-selected cards and, if enabled, each pile they could move to.
+fn draw_selection_outline(dc: HDC, state: &WindowState, metrics: &CardMetrics) {
+    let Some(selection) = state.pending_selection else {
+        return;
+    };
+    let Some((source, cards)) = selected_cards(&state.game, selection) else {
+        return;
+    };
+    let count = cards.len();
+    let outlines = &state.settings.outlines;
+    let rect = hint_pile_rect(state, metrics, source, count, true);
+    highlight_rect(
+        dc,
+        metrics,
+        rect,
+        outline_color(outlines.selection),
+        outlines.thickness,
+    );
+    if state.settings.highlight_moves {
+        for target in legal_destinations(&state.game, selection) {
+            let rect = hint_pile_rect(state, metrics, target, count, false);
+            highlight_rect(dc, metrics, rect, rgb(190, 232, 170), 2);
+        }
+    }
+}
+
+fn draw_hint_outline(dc: HDC, state: &WindowState, metrics: &CardMetrics, hint: Hint) {
+    let color = rgb(64, 200, 255);
+    let source = hint_pile_rect(state, metrics, hint.from, hint.cards, true);
+    highlight_rect(dc, metrics, source, color, 3);
+    if hint.to != hint.from {
+        let target = hint_pile_rect(state, metrics, hint.to, hint.cards, false);
+        highlight_rect(dc, metrics, target, color, 3);
+    }
+}
+
+/// Whether dropping `drag` on its hover target would be accepted; `None` when the cursor
+/// is not over a pile the cards could go to.
+fn drag_drop_legality(state: &WindowState, drag: &DragContext) -> Option<bool> {
+    match drag.hover {
+        HitTarget::Tableau { column, .. } => {
+            if matches!(drag.source, DragSource::Tableau { column: from } if from == column) {
+                return None;
+            }
+            Some(state.game.can_accept_tableau_stack(column, &drag.cards))
+        }
+        HitTarget::Foundation(index) => match (drag.source, drag.cards.as_slice()) {
+            (DragSource::Foundation(from), _) if from == index => None,
+            (DragSource::Foundation(_), _) => Some(false),
+            (_, [card]) => Some(state.game.can_accept_foundation(index, *card)),
+            _ => Some(false),
+        },
+        _ => None,
+    }
+}
+
+/// Top-left corner where a stack dropped on `target` would come to rest.
+fn drop_origin(state: &WindowState, metrics: &CardMetrics, target: HitTarget) -> (i32, i32) {
+    match target {
+        HitTarget::Foundation(index) => (metrics.foundation_x(index), metrics.top_y()),
+        HitTarget::Tableau { column, .. } => {
+            let x = metrics.column_x(column);
+            let Some(last) = state.game.tableau_len(column).checked_sub(1) else {
+                return (x, metrics.tableau_y());
+            };
+            let face_up = state
+                .game
+                .tableau_column(column)
+                .and_then(|cards| cards.last())
+                .is_some_and(|card| card.face_up);
+            let step = if face_up {
+                metrics.face_up_offset
+            } else {
+                metrics.face_down_offset
+            };
+            (x, tableau_card_top(state, metrics, column, last) + step)
+        }
+        _ => (0, 0),
+    }
+}
+
+/// Faint copies of the cards the next stock click would turn up, fanned over the waste the
+/// way they would land. Nothing moves; the stock stays face down.
+fn draw_stock_preview(back: &BackBuffer, state: &WindowState, metrics: &CardMetrics) {
+    let stock = &state.game.stock.cards;
+    let drawn = usize::from(state.game.draw_mode.count()).min(stock.len());
+    let shown = drawn.min(WASTE_FAN_CARDS);
+    if shown == 0 {
+        return;
+    }
+    // Drawing pops from the end of the stock, so the last card popped lands on top.
+    let cards: Vec<Card> = stock
+        .iter()
+        .rev()
+        .take(drawn)
+        .skip(drawn - shown)
+        .map(|&card| Card {
+            face_up: true,
+            ..card
+        })
+        .collect();
+    let x = metrics.waste_x(shown - 1);
+    let y = metrics.top_y();
+    let width = metrics.card_w + (shown as i32 - 1) * metrics.waste_fan_offset;
+    let height = metrics.card_h;
+    unsafe {
+        let Ok(mut ghost) = BackBuffer::new(width, height) else {
+            return;
+        };
+        let _ = BitBlt(ghost.dc, 0, 0, width, height, back.dc, x, y, SRCCOPY);
+        for (offset, card) in cards.iter().enumerate() {
+            draw_card_face_up_to_dc(
+                state.card_art(),
+                metrics,
+                ghost.dc,
+                &state.gdi,
+                card,
+                offset as i32 * metrics.waste_fan_offset,
+                0,
+            );
+        }
+        let blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER as u8,
+            BlendFlags: 0,
+            SourceConstantAlpha: STOCK_PREVIEW_ALPHA,
+            AlphaFormat: 0,
+        };
+        AlphaBlend(
+            back.dc, x, y, width, height, ghost.dc, 0, 0, width, height, blend,
+        );
+        ghost.destroy();
+    }
+}
+
+/// Outlines the pile under a drag in green or red, and for legal drops shows a faint copy of
+/// the stack where it would land.
+fn draw_drag_feedback(back: &BackBuffer, state: &WindowState, metrics: &CardMetrics) {
+    let Some(drag) = state.drag.as_ref() else {
+        return;
+    };
+    let Some(legal) = drag_drop_legality(state, drag) else {
+        return;
+    };
+    let pile = match drag.hover {
+        HitTarget::Foundation(index) => PileRef::Foundation(index),
+        HitTarget::Tableau { column, .. } => PileRef::Tableau(column),
+        _ => return,
+    };
+
+    if legal {
+        let (x, y) = drop_origin(state, metrics, drag.hover);
+        let height = metrics.card_h + metrics.face_up_offset * (drag.cards.len() as i32 - 1);
+        let width = metrics.card_w;
+        unsafe {
+            if let Ok(mut ghost) = BackBuffer::new(width, height) {
+                let _ = BitBlt(ghost.dc, 0, 0, width, height, back.dc, x, y, SRCCOPY);
+                let mut offset = 0;
+                for card in &drag.cards {
+                    if card.face_up {
+                        draw_card_face_up_to_dc(
+                            state.card_art(),
+                            metrics,
+                            ghost.dc,
+                            &state.gdi,
+                            card,
+                            0,
+                            offset,
+                        );
+                    } else {
+                        draw_face_down_card(
+                            state.card_art(),
+                            ghost.dc,
+                            &state.gdi,
+                            make_rect(0, offset, width, metrics.card_h),
+                            state.settings.card_back,
+                        );
+                    }
+                    offset += metrics.face_up_offset;
+                }
+                let blend = BLENDFUNCTION {
+                    BlendOp: AC_SRC_OVER as u8,
+                    BlendFlags: 0,
+                    SourceConstantAlpha: DRAG_GHOST_ALPHA,
+                    AlphaFormat: 0,
+                };
+                AlphaBlend(
+                    back.dc, x, y, width, height, ghost.dc, 0, 0, width, height, blend,
+                );
+                ghost.destroy();
+            }
+        }
+    }
+
+    let outlines = &state.settings.outlines;
+    let color = if legal {
+        outline_color(outlines.drag_legal)
+    } else {
+        outline_color(outlines.drag_illegal)
+    };
+    let target = hint_pile_rect(state, metrics, pile, drag.cards.len(), false);
+    highlight_rect(back.dc, metrics, target, color, outlines.thickness);
+}
+
+fn suit_symbol(suit: Suit) -> &'static str {
+    match suit {
+        Suit::Spades => "\u{2660}",
+        Suit::Hearts => "\u{2665}",
+        Suit::Diamonds => "\u{2666}",
+        Suit::Clubs => "\u{2663}",
+    }
+}
+
+/// What a foundation slot shows while no card is visible on it: the suit it is already
+/// building (its cards may be in flight or part of the victory animation), or an "A" while it
+/// still waits for an Ace.
+fn foundation_watermark(pile: &Pile) -> &'static str {
+    pile.cards.last().map_or("A", |card| suit_symbol(card.suit))
+}
+
+/// Draws `label` large and faint inside the slot at (`x`, `y`). Paint only; hit testing is
+/// unaffected.
+fn draw_foundation_watermark(
+    dc: HDC,
+    gdi: &GdiCache,
+    metrics: &CardMetrics,
+    x: i32,
+    y: i32,
+    label: &str,
+) {
+    let fill = metrics.slot_fill.0;
+    let lift = |shift: u32| (((fill >> shift) & 0xFF) + 40).min(255) as u8;
+    let color = rgb(lift(0), lift(8), lift(16));
+    unsafe {
+        let font = gdi.font(UiFont::Symbol, metrics.card_h / 2);
+        let old_font = (font.0 != 0).then(|| SelectObject(dc, font));
+        let _ = SetBkMode(dc, TRANSPARENT);
+        let _ = SetTextColor(dc, color);
+        let mut text = to_wide(label);
+        let mut rect = make_rect(x, y, metrics.card_w, metrics.card_h);
+        let _ = DrawTextW(
+            dc,
+            text.as_mut_slice(),
+            &mut rect,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+        );
+        if let Some(old) = old_font {
+            SelectObject(dc, old);
+        }
+    }
+}
+
+/// Cards per drawn layer of the stock, so a full stock looks three cards thick.
+const STOCK_CARDS_PER_LAYER: usize = 8;
+const MAX_STOCK_LAYERS: usize = 3;
+
+/// How many card backs to stack up for a stock of `count` cards.
+fn stock_layers(count: usize) -> usize {
+    (count / STOCK_CARDS_PER_LAYER).clamp(1, MAX_STOCK_LAYERS)
+}
+
+fn stock_layer_step(metrics: &CardMetrics) -> i32 {
+    (metrics.card_w / 40).max(2)
+}
+
+/// Dark tab in the bottom-right corner of the stock's top card showing how many cards are
+/// left in it.
+fn draw_stock_badge(dc: HDC, gdi: &GdiCache, metrics: &CardMetrics, x: i32, y: i32, count: usize) {
+    let height = (metrics.card_h / 7).max(12);
+    let width = (height * 2).min(metrics.card_w);
+    let pad = (metrics.card_w / 16).max(3);
+    let badge = make_rect(
+        x + metrics.card_w - pad - width,
+        y + metrics.card_h - pad - height,
+        width,
+        height,
+    );
+    let fill = rgb(24, 28, 32);
+    draw_round_rect_fill(dc, gdi, badge, height / 2, fill, rgb(240, 240, 240));
+    unsafe {
+        let font = gdi.font(UiFont::Text, height * 3 / 4);
+        let old_font = (font.0 != 0).then(|| SelectObject(dc, font));
+        let _ = SetBkMode(dc, TRANSPARENT);
+        let _ = SetTextColor(dc, rgb(240, 240, 240));
+        let mut text = to_wide(&count.to_string());
+        let mut rect = badge;
+        let _ = DrawTextW(
+            dc,
+            text.as_mut_slice(),
+            &mut rect,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+        );
+        if let Some(old) = old_font {
+            SelectObject(dc, old);
+        }
+    }
+}
+
+fn draw_card_placeholder_dc(dc: HDC, gdi: &GdiCache, metrics: &CardMetrics, x: i32, y: i32) {
+    let rect = make_rect(x, y, metrics.card_w, metrics.card_h);
+    let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
+    draw_round_rect_fill(dc, gdi, rect, radius, metrics.slot_fill, rgb(0, 0, 0));
+    let inner = inset_rect(rect, 3);
+    draw_round_outline(dc, inner, (radius - 2).max(4), rgb(0, 0, 0), 1);
+}
+
+/// Washes a face-down card's face over its back, faint enough to read as still hidden.
+fn draw_peeked_face(
+    art: CardArt,
+    metrics: &CardMetrics,
+    target_dc: HDC,
+    gdi: &GdiCache,
+    card: &Card,
+    x: i32,
+    y: i32,
+) {
+    let (width, height) = (metrics.card_w, metrics.card_h);
+    unsafe {
+        if let Ok(mut layer) = BackBuffer::new(width, height) {
+            let _ = BitBlt(layer.dc, 0, 0, width, height, target_dc, x, y, SRCCOPY);
+            let face = Card {
+                face_up: true,
+                ..*card
+            };
+            draw_card_face_up_to_dc(art, metrics, layer.dc, gdi, &face, 0, 0);
+            let blend = BLENDFUNCTION {
+                BlendOp: AC_SRC_OVER as u8,
+                BlendFlags: 0,
+                SourceConstantAlpha: PEEK_FACE_ALPHA,
+                AlphaFormat: 0,
+            };
+            AlphaBlend(
+                target_dc, x, y, width, height, layer.dc, 0, 0, width, height, blend,
+            );
+            layer.destroy();
+        }
+    }
+}
+
+/// Paints the shadow a card at (`x`, `y`) casts down and to the right. It goes down before the
+/// card itself and never changes the card's hit rectangle.
+fn draw_card_shadow(dc: HDC, gdi: &GdiCache, metrics: &CardMetrics, x: i32, y: i32) {
+    let Some(color) = metrics.shadow else {
+        return;
+    };
+    let offset = (metrics.card_w / 24).max(2);
+    let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
+    let rect = make_rect(x + offset, y + offset, metrics.card_w, metrics.card_h);
+    draw_round_rect_fill(dc, gdi, rect, radius, color, color);
+}
+
+fn draw_card_face_up_to_dc(
+    art: CardArt,
+    metrics: &CardMetrics,
+    target_dc: HDC,
+    gdi: &GdiCache,
+    card: &Card,
+    x: i32,
+    y: i32,
+) {
+    let rect = make_rect(x, y, metrics.card_w, metrics.card_h);
+    unsafe {
+        if let Some(image) = art.sheet() {
+            let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
+            draw_round_rect_fill(
+                target_dc,
+                gdi,
+                rect,
+                radius,
+                rgb(252, 252, 252),
+                rgb(204, 204, 204),
+            );
+            let sprite = card.sprite_index as i32;
+            let src_x = (sprite % CARD_SPRITE_COLS) * image.cell_w;
+            let src_y = (sprite / CARD_SPRITE_COLS) * image.cell_h;
+            let trim_x = 1;
+            let trim_y = 1;
+            let src_w = (image.cell_w - trim_x * 2).max(1);
+            let src_h = (image.cell_h - trim_y * 2).max(1);
+            let blend = BLENDFUNCTION {
+                BlendOp: AC_SRC_OVER as u8,
+                BlendFlags: 0,
+                SourceConstantAlpha: 255,
+                AlphaFormat: AC_SRC_ALPHA as u8,
+            };
+            let max_inset_w = ((rect.right - rect.left) / 2).saturating_sub(1);
+            let max_inset_h = ((rect.bottom - rect.top) / 2).saturating_sub(1);
+            let face_gap = (metrics.card_w.min(metrics.card_h) / 32).max(2);
+            let inset = metrics
+                .face_inset
+                .saturating_add(face_gap)
+                .min(max_inset_w)
+                .min(max_inset_h)
+                .max(0);
+            let inner = if inset > 0 {
+                inset_rect(rect, inset)
+            } else {
+                rect
+            };
+            let dest_w = (inner.right - inner.left).max(0);
+            let dest_h = (inner.bottom - inner.top).max(0);
+            if dest_w > 0 && dest_h > 0 {
+                AlphaBlend(
+                    target_dc,
+                    inner.left,
+                    inner.top,
+                    dest_w,
+                    dest_h,
+                    art.dc,
+                    src_x + trim_x,
+                    src_y + trim_y,
+                    src_w,
+                    src_h,
+                    blend,
+                );
+            }
+        } else if metrics.corner_indices {
+            // No sprite sheet: a blank face is enough once the index says what the card is.
+            let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
+            draw_round_rect_fill(
+                target_dc,
+                gdi,
+                rect,
+                radius,
+                rgb(252, 252, 252),
+                rgb(204, 204, 204),
+            );
+        } else {
+            draw_card_placeholder_dc(target_dc, gdi, metrics, x, y);
+        }
+        if metrics.corner_indices {
+            draw_corner_index(target_dc, gdi, metrics, card, rect);
+        }
+    }
+}
+
+fn rank_label(rank: Rank) -> &'static str {
+    match rank {
+        Rank::Ace => "A",
+        Rank::Two => "2",
+        Rank::Three => "3",
+        Rank::Four => "4",
+        Rank::Five => "5",
+        Rank::Six => "6",
+        Rank::Seven => "7",
+        Rank::Eight => "8",
+        Rank::Nine => "9",
+        Rank::Ten => "10",
+        Rank::Jack => "J",
+        Rank::Queen => "Q",
+        Rank::King => "K",
+    }
+}
+
+fn corner_index(card: &Card) -> String {
+    format!("{}{}", rank_label(card.rank), suit_symbol(card.suit))
+}
+
+/// Rank and suit on a white tab in the top-left corner, coloured by suit so red and black
+/// never depend on the artwork alone.
+unsafe fn draw_corner_index(
+    dc: HDC,
+    gdi: &GdiCache,
+    metrics: &CardMetrics,
+    card: &Card,
+    rect: RECT,
+) {
+    let height = (metrics.card_h / 6).max(10);
+    let pad = (metrics.card_w / 24).max(2);
+    let tab = RECT {
+        left: rect.left + pad,
+        top: rect.top + pad,
+        right: rect.left + pad + (metrics.card_w / 2).max(height * 2),
+        bottom: rect.top + pad + height,
+    };
+    draw_round_rect_fill(dc, gdi, tab, 4, rgb(252, 252, 252), rgb(252, 252, 252));
+    let font = gdi.font(UiFont::Symbol, height);
+    let old_font = (font.0 != 0).then(|| SelectObject(dc, font));
+    let color = match card.suit.color() {
+        CardColor::Red => rgb(198, 32, 40),
+        CardColor::Black => rgb(20, 20, 24),
+    };
+    let _ = SetBkMode(dc, TRANSPARENT);
+    let _ = SetTextColor(dc, color);
+    let mut text = to_wide(&corner_index(card));
+    let mut text_rect = tab;
+    let _ = DrawTextW(
+        dc,
+        text.as_mut_slice(),
+        &mut text_rect,
+        DT_LEFT | DT_VCENTER | DT_SINGLELINE,
+    );
+    if let Some(old) = old_font {
+        SelectObject(dc, old);
+    }
+}
+
+/// A face-down card. The classic design comes from the sprite sheet's back cell when the sheet
+/// has one, so an image-based deck looks the same on both sides; every other design, and any
+/// sheet without a back, is drawn by `draw_card_back`.
+fn draw_face_down_card(art: CardArt, dc: HDC, gdi: &GdiCache, rect: RECT, style: CardBack) {
+    let sheet_back = art.sheet().filter(|image| image.has_back);
+    let Some(image) = sheet_back.filter(|_| style == CardBack::ClassicBlue) else {
+        draw_card_back(dc, gdi, rect, style);
+        return;
+    };
+    let blend = BLENDFUNCTION {
+        BlendOp: AC_SRC_OVER as u8,
+        BlendFlags: 0,
+        SourceConstantAlpha: 255,
+        AlphaFormat: AC_SRC_ALPHA as u8,
+    };
+    unsafe {
+        AlphaBlend(
+            dc,
+            rect.left,
+            rect.top,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            art.dc,
+            0,
+            CARD_SPRITE_ROWS * image.cell_h,
+            image.cell_w,
+            image.cell_h,
+            blend,
+        );
+    }
+}
+
+fn draw_card_back(dc: HDC, gdi: &GdiCache, rect: RECT, style: CardBack) {
+    let radius = ((rect.right - rect.left).min(rect.bottom - rect.top) / 6).max(8);
+    let border = rgb(240, 240, 240);
+    let inner = inset_rect(rect, 4);
+    let inner_radius = (radius - 4).max(4);
+    let pattern = inset_rect(inner, 6);
+    let pattern_w = pattern.right - pattern.left;
+    let pattern_h = pattern.bottom - pattern.top;
+
+    match style {
+        CardBack::ClassicBlue => {
+            draw_round_rect_fill(dc, gdi, rect, radius, rgb(30, 60, 150), border);
+            draw_round_rect_fill(
+                dc,
+                gdi,
+                inner,
+                inner_radius,
+                rgb(12, 32, 104),
+                rgb(12, 32, 104),
+            );
+
+            let stripe_width = ((inner.right - inner.left) / 6).max(8);
+            let stripe_radius = (inner_radius - 4).max(3);
+            let mut left_stripe = pattern;
+            left_stripe.right = left_stripe.left + stripe_width;
+            let mut right_stripe = pattern;
+            right_stripe.left = right_stripe.right - stripe_width;
+            for stripe in [left_stripe, right_stripe] {
+                draw_round_rect_fill(
+                    dc,
+                    gdi,
+                    stripe,
+                    stripe_radius,
+                    rgb(200, 48, 64),
+                    rgb(200, 48, 64),
+                );
+            }
+        }
+        CardBack::RedDiamond => {
+            draw_round_rect_fill(dc, gdi, rect, radius, rgb(170, 30, 40), border);
+            draw_round_rect_fill(
+                dc,
+                gdi,
+                inner,
+                inner_radius,
+                rgb(110, 12, 24),
+                rgb(110, 12, 24),
+            );
+
+            // Offset rows of small rounded tiles read as a diamond weave.
+            let cell = (pattern_w / 4).max(6);
+            let tile = (cell * 2 / 3).max(3);
+            let mut row = 0;
+            let mut y = pattern.top;
+            while y + tile <= pattern.bottom {
+                let mut x = pattern.left + if row % 2 == 1 { cell / 2 } else { 0 };
+                while x + tile <= pattern.right {
+                    draw_round_rect_fill(
+                        dc,
+                        gdi,
+                        make_rect(x, y, tile, tile),
+                        tile / 2,
+                        rgb(220, 72, 84),
+                        rgb(220, 72, 84),
+                    );
+                    x += cell;
+                }
+                y += cell / 2 + tile / 2;
+                row += 1;
+            }
+        }
+        CardBack::GreenLattice => {
+            draw_round_rect_fill(dc, gdi, rect, radius, rgb(24, 110, 56), border);
+            draw_round_rect_fill(
+                dc,
+                gdi,
+                inner,
+                inner_radius,
+                rgb(10, 70, 32),
+                rgb(10, 70, 32),
+            );
+
+            let bar = (pattern_w / 20).max(2);
+            let lattice = rgb(120, 190, 120);
+            for i in 0..=3 {
+                let x = pattern.left + (pattern_w - bar) * i / 3;
+                draw_round_rect_fill(
+                    dc,
+                    gdi,
+                    make_rect(x, pattern.top, bar, pattern_h),
+                    2,
+                    lattice,
+                    lattice,
+                );
+            }
+            for i in 0..=4 {
+                let y = pattern.top + (pattern_h - bar) * i / 4;
+                draw_round_rect_fill(
+                    dc,
+                    gdi,
+                    make_rect(pattern.left, y, pattern_w, bar),
+                    2,
+                    lattice,
+                    lattice,
+                );
+            }
+        }
+        CardBack::Plain => {
+            draw_round_rect_fill(dc, gdi, rect, radius, rgb(30, 60, 150), border);
+        }
+    }
+}
+
+const CARD_SIZE_MENU: [(u16, CardSize); 3] = [
+    (constants::IDM_GAME_SIZE_SMALL, CardSize::Small),
+    (constants::IDM_GAME_SIZE_MEDIUM, CardSize::Medium),
+    (constants::IDM_GAME_SIZE_LARGE, CardSize::Large),
+];
+
+unsafe fn update_card_size_menu(hwnd: HWND, size: CardSize) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        for (id, preset) in CARD_SIZE_MENU {
+            check_menu_item(menu, id, preset == size);
+        }
+    }
+}
+
+const VICTORY_PHYSICS_MENU: [(u16, VictoryPhysics); 3] = [
+    (constants::IDM_GAME_PHYSICS_CALM, VictoryPhysics::Calm),
+    (constants::IDM_GAME_PHYSICS_CLASSIC, VictoryPhysics::Classic),
+    (constants::IDM_GAME_PHYSICS_CHAOTIC, VictoryPhysics::Chaotic),
+];
+
+unsafe fn update_victory_physics_menu(hwnd: HWND, physics: VictoryPhysics) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        for (id, preset) in VICTORY_PHYSICS_MENU {
+            check_menu_item(menu, id, preset == physics);
+        }
+    }
+}
+
+const CARD_BACK_MENU: [(u16, CardBack); 4] = [
+    (constants::IDM_GAME_BACK_CLASSIC, CardBack::ClassicBlue),
+    (constants::IDM_GAME_BACK_DIAMOND, CardBack::RedDiamond),
+    (constants::IDM_GAME_BACK_LATTICE, CardBack::GreenLattice),
+    (constants::IDM_GAME_BACK_PLAIN, CardBack::Plain),
+];
+
+unsafe fn update_card_back_menu(hwnd: HWND, style: CardBack) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        for (id, back) in CARD_BACK_MENU {
+            check_menu_item(menu, id, back == style);
+        }
+    }
+}
+
+const OUTLINE_MENU: [(u16, OutlineStyle); 2] = [
+    (
+        constants::IDM_GAME_OUTLINES_STANDARD,
+        OutlineStyle::STANDARD,
+    ),
+    (
+        constants::IDM_GAME_OUTLINES_HIGH_CONTRAST,
+        OutlineStyle::HIGH_CONTRAST,
+    ),
+];
+
+/// Checks the preset matching `style`; hand-edited colours leave both unchecked.
+unsafe fn update_outline_menu(hwnd: HWND, style: &OutlineStyle) {
+    let menu = GetMenu(hwnd);
+    if menu.0 != 0 {
+        for (id, preset) in OUTLINE_MENU {
+            check_menu_item(menu, id, preset == *style);
+        }
+    }
+}
+
+fn outline_color([r, g, b]: [u8; 3]) -> COLORREF {
+    rgb(r, g, b)
+}
+
+fn set_focus(state: &mut WindowState, focus: HitTarget) {
+    let focus = normalize_focus(state, focus);
+    if state.keyboard_active && state.focus != Some(focus) {
+        if let Some(text) = describe_focus(&state.game, focus) {
+            state.announce(&text);
+        }
+    }
+    state.focus = Some(focus);
+}
+
+/// Keeps the window above all others, or drops it back into the normal z-order.
+fn apply_always_on_top(hwnd: HWND, on: bool) {
+    let insert_after = if on { HWND_TOPMOST } else { HWND_NOTOPMOST };
+    unsafe {
+        let _ = SetWindowPos(
+            hwnd,
+            insert_after,
+            0,
+            0,
+            0,
+            0,
+            SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
         );
-        if old_pen.0 != 0 {
-            let _ = SelectObject(dc, old_pen);
+    }
+}
+
+/// Drops everything measured in pixels for the old client size, ready for the next paint to
+/// lay the table out again. The focus and any click selection are kept as they are: they
+/// name piles and cards rather than positions, so they reappear on the same cards.
+fn forget_layout(hwnd: HWND, state: &mut WindowState) {
+    state.layout_metrics = None;
+    for slots in &mut state.tableau_slots {
+        slots.clear();
+    }
+    // A card in flight would land where its pile used to be, so let it arrive now.
+    state.tweens.clear();
+    // The cursor is no longer over the same card.
+    update_hover(hwnd, state, HitTarget::None);
+}
+
+fn ensure_focus_valid(state: &mut WindowState) {
+    let current = state.focus.unwrap_or(HitTarget::Stock);
+    state.focus = Some(normalize_focus(state, current));
+}
+
+fn normalize_focus(state: &WindowState, focus: HitTarget) -> HitTarget {
+    match focus {
+        HitTarget::Tableau { column, card_index } => {
+            if TABLEAU_COLUMNS == 0 {
+                return HitTarget::Stock;
+            }
+            if column >= TABLEAU_COLUMNS {
+                return focus_tableau_top(state, TABLEAU_COLUMNS - 1);
+            }
+            let len = state.game.tableau_len(column);
+            if len == 0 {
+                HitTarget::Tableau {
+                    column,
+                    card_index: None,
+                }
+            } else {
+                let mut idx = card_index.unwrap_or(len - 1);
+                if idx >= len {
+                    idx = len - 1;
+                }
+                HitTarget::Tableau {
+                    column,
+                    card_index: Some(idx),
+                }
+            }
         }
-        if old_brush.0 != 0 {
-            let _ = SelectObject(dc, old_brush);
+        HitTarget::Foundation(idx) => {
+            HitTarget::Foundation(idx.min(FOUNDATION_COLUMNS.saturating_sub(1)))
         }
-        let _ = DeleteObject(HGDIOBJ(pen.0));
+        HitTarget::None => HitTarget::Stock,
+        other => other,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FocusMove {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// The empty top-row column between the waste and the foundations.
+fn top_row_gap(left_handed: bool) -> usize {
+    if left_handed {
+        FOUNDATION_COLUMNS
+    } else {
+        2
+    }
+}
+
+/// Top-row piles in screen order, skipping the gap column.
+fn top_row_index(target: HitTarget, left_handed: bool) -> Option<usize> {
+    let pile = match target {
+        HitTarget::Stock => PileRef::Stock,
+        HitTarget::Waste => PileRef::Waste,
+        HitTarget::Foundation(index) => PileRef::Foundation(index),
+        _ => return None,
+    };
+    let column = top_row_column(pile, left_handed);
+    Some(if column > top_row_gap(left_handed) {
+        column - 1
+    } else {
+        column
+    })
+}
+
+fn top_row_target(index: usize, left_handed: bool) -> HitTarget {
+    top_row_above(index_column(index, left_handed), left_handed)
+}
+
+/// Board column under the top-row pile at screen position `index`.
+fn index_column(index: usize, left_handed: bool) -> usize {
+    if index >= top_row_gap(left_handed) {
+        index + 1
+    } else {
+        index
+    }
+}
+
+/// The top-row pile above a tableau column; the gap column belongs to the waste beside it.
+fn top_row_above(column: usize, left_handed: bool) -> HitTarget {
+    match (left_handed, column) {
+        (false, 0) => HitTarget::Stock,
+        (false, 1 | 2) => HitTarget::Waste,
+        (false, other) => HitTarget::Foundation((other - 3).min(FOUNDATION_COLUMNS - 1)),
+        (true, other) if other < FOUNDATION_COLUMNS => HitTarget::Foundation(other),
+        (true, other) if other + 1 < TABLEAU_COLUMNS => HitTarget::Waste,
+        (true, _) => HitTarget::Stock,
+    }
+}
+
+/// Where Tab (or Shift+Tab when `forward` is false) lands from `focus`, cycling stock, waste,
+/// foundations, then each tableau column and round again. Columns are entered at their top.
+fn cycle_focus(focus: HitTarget, forward: bool) -> HitTarget {
+    let stops = 2 + FOUNDATION_COLUMNS + TABLEAU_COLUMNS;
+    let index = match focus {
+        HitTarget::Stock | HitTarget::None => 0,
+        HitTarget::Waste => 1,
+        HitTarget::Foundation(index) => 2 + index,
+        HitTarget::Tableau { column, .. } => 2 + FOUNDATION_COLUMNS + column,
+    };
+    let next = if forward {
+        (index + 1) % stops
+    } else {
+        (index + stops - 1) % stops
+    };
+    match next {
+        0 => HitTarget::Stock,
+        1 => HitTarget::Waste,
+        n if n < 2 + FOUNDATION_COLUMNS => HitTarget::Foundation(n - 2),
+        n => HitTarget::Tableau {
+            column: n - 2 - FOUNDATION_COLUMNS,
+            card_index: None,
+        },
+    }
+}
+
+/// Where arrow-key navigation lands from `focus`. Up and down step through the face-up run of
+/// a column before crossing between the tableau and the top row.
+fn next_focus(
+    game: &GameState,
+    focus: HitTarget,
+    direction: FocusMove,
+    left_handed: bool,
+) -> HitTarget {
+    let column_top = |column: usize| {
+        let len = game.tableau_len(column);
+        HitTarget::Tableau {
+            column,
+            card_index: len.checked_sub(1),
+        }
+    };
+    if let Some(index) = top_row_index(focus, left_handed) {
+        let last = 1 + FOUNDATION_COLUMNS;
+        return match direction {
+            FocusMove::Left => top_row_target(index.saturating_sub(1), left_handed),
+            FocusMove::Right => top_row_target((index + 1).min(last), left_handed),
+            FocusMove::Up => focus,
+            FocusMove::Down => column_top(index_column(index, left_handed)),
+        };
+    }
+    let HitTarget::Tableau { column, card_index } = focus else {
+        return HitTarget::Stock;
+    };
+    let first_face_up = game
+        .tableau_column(column)
+        .and_then(|cards| cards.iter().position(|card| card.face_up));
+    match direction {
+        FocusMove::Left => column_top(column.saturating_sub(1)),
+        FocusMove::Right => column_top((column + 1).min(TABLEAU_COLUMNS - 1)),
+        FocusMove::Up => match (card_index, first_face_up) {
+            (Some(index), Some(first)) if index > first => HitTarget::Tableau {
+                column,
+                card_index: Some(index - 1),
+            },
+            _ => top_row_above(column, left_handed),
+        },
+        FocusMove::Down => match card_index {
+            Some(index) if index + 1 < game.tableau_len(column) => HitTarget::Tableau {
+                column,
+                card_index: Some(index + 1),
+            },
+            _ => focus,
+        },
+    }
+}
+
+/// Whole downward notches accumulated from `delta`; scrolling up discards any partial notch.
+fn wheel_notches(accum: &mut i32, delta: i16) -> u32 {
+    let delta = delta as i32;
+    if delta > 0 {
+        *accum = 0;
+        return 0;
+    }
+    *accum -= delta;
+    let notches = *accum / WHEEL_DELTA as i32;
+    *accum %= WHEEL_DELTA as i32;
+    notches as u32
+}
+
+/// Plays the waste or tableau top card under `target` to whichever foundation takes it. Leaves
+/// the game and the undo history untouched when none does.
+fn send_to_any_foundation(hwnd: HWND, state: &mut WindowState, target: HitTarget) -> bool {
+    let snapshot = state.game.clone();
+    let moved = match target {
+        HitTarget::Waste => state.game.move_waste_to_any_foundation(),
+        HitTarget::Tableau {
+            column,
+            card_index: Some(idx),
+        } if idx + 1 == state.game.tableau_len(column) => {
+            state.game.move_tableau_top_to_any_foundation(column)
+        }
+        _ => false,
+    };
+    if moved {
+        state.play_sound(Sound::Foundation);
+        animate_moves(hwnd, state, &snapshot);
+        state.push_undo(snapshot);
+        update_status_bar(state);
+        check_for_victory(hwnd, state);
+    }
+    moved
+}
+
+/// Double-click fallback when no foundation takes the card: plays the waste top, or the
+/// tableau card under `target` with the cards above it, to the column the engine ranks best.
+fn send_to_best_tableau(hwnd: HWND, state: &mut WindowState, target: HitTarget) -> bool {
+    let from = match target {
+        HitTarget::Waste => None,
+        HitTarget::Tableau {
+            column,
+            card_index: Some(index),
+        } => Some((column, index)),
+        _ => return false,
+    };
+    let snapshot = state.game.clone();
+    let moved = match state.game.best_tableau_move(from) {
+        Some(Move::WasteToTableau { column }) => state.game.move_waste_to_tableau(column),
+        Some(Move::TableauToTableau { from, count, to }) => {
+            let start = state.game.tableau_len(from) - count;
+            state.game.move_tableau_stack(from, start, to)
+        }
+        _ => false,
+    };
+    if moved {
+        state.play_sound(Sound::Tableau);
+        animate_moves(hwnd, state, &snapshot);
+        state.push_undo(snapshot);
+        update_status_bar(state);
+        check_for_victory(hwnd, state);
+    }
+    moved
+}
+
+/// The single card a number key should send home: the pending selection when there is one,
+/// otherwise the focused waste or tableau top. Only a pile's top card can go to a foundation.
+fn foundation_key_source(
+    game: &GameState,
+    selection: Option<Selection>,
+    focus: Option<HitTarget>,
+) -> Option<Selection> {
+    let source = match (selection, focus) {
+        (Some(selection), _) => selection,
+        (None, Some(HitTarget::Waste)) => Selection::Waste,
+        (None, Some(HitTarget::Tableau { column, .. })) => Selection::Tableau {
+            column,
+            index: game.tableau_len(column).checked_sub(1)?,
+        },
+        _ => return None,
+    };
+    match source {
+        Selection::Waste if game.waste_count() > 0 => Some(source),
+        Selection::Tableau { column, index }
+            if index + 1 == game.tableau_len(column)
+                && game
+                    .tableau_card(column, index)
+                    .is_some_and(|card| card.face_up) =>
+        {
+            Some(source)
+        }
+        _ => None,
+    }
+}
+
+/// Moves the selected or focused card to one foundation; an illegal target changes nothing.
+fn send_to_foundation(hwnd: HWND, state: &mut WindowState, foundation: usize) {
+    let Some(source) = foundation_key_source(&state.game, state.pending_selection, state.focus)
+    else {
+        return;
+    };
+    let snapshot = state.game.clone();
+    let moved = match source {
+        Selection::Waste => state.game.move_waste_to_foundation(foundation),
+        Selection::Tableau { column, .. } => {
+            state.game.move_tableau_to_foundation(column, foundation)
+        }
+        Selection::Foundation(_) => false,
+    };
+    if !moved {
+        return;
+    }
+    state.pending_selection = None;
+    state.play_sound(Sound::Foundation);
+    animate_moves(hwnd, state, &snapshot);
+    state.push_undo(snapshot);
+    update_status_bar(state);
+    check_for_victory(hwnd, state);
+    ensure_focus_valid(state);
+    request_redraw(hwnd);
+}
+
+/// Keys for tableau columns 1 to 7, the row under the foundation number keys.
+const COLUMN_KEYS: [u8; TABLEAU_COLUMNS] = *b"QWERTYU";
+
+/// What a column key should move: the pending selection when there is one, otherwise the
+/// focused waste card, foundation top, or face-up tableau card with everything on it.
+fn column_key_source(
+    game: &GameState,
+    selection: Option<Selection>,
+    focus: Option<HitTarget>,
+) -> Option<Selection> {
+    if selection.is_some() {
+        return selection;
+    }
+    match focus? {
+        HitTarget::Waste if game.waste_count() > 0 => Some(Selection::Waste),
+        HitTarget::Foundation(index) if !game.foundations[index].cards.is_empty() => {
+            Some(Selection::Foundation(index))
+        }
+        HitTarget::Tableau {
+            column,
+            card_index: Some(index),
+        } if game
+            .tableau_card(column, index)
+            .is_some_and(|card| card.face_up) =>
+        {
+            Some(Selection::Tableau { column, index })
+        }
+        _ => None,
+    }
+}
+
+/// Moves the selected or focused cards straight onto a column; an illegal target changes
+/// nothing.
+fn send_to_column(hwnd: HWND, state: &mut WindowState, column: usize) {
+    let Some(source) = column_key_source(&state.game, state.pending_selection, state.focus) else {
+        return;
+    };
+    let snapshot = state.game.clone();
+    let moved = match source {
+        Selection::Waste => state.game.move_waste_to_tableau(column),
+        Selection::Tableau {
+            column: from,
+            index,
+        } => from != column && state.game.move_tableau_stack(from, index, column),
+        Selection::Foundation(from) => state.game.move_foundation_to_tableau(from, column),
+    };
+    if !moved {
+        return;
+    }
+    state.pending_selection = None;
+    state.play_sound(Sound::Tableau);
+    animate_moves(hwnd, state, &snapshot);
+    state.push_undo(snapshot);
+    update_status_bar(state);
+    check_for_victory(hwnd, state);
+    ensure_focus_valid(state);
+    request_redraw(hwnd);
+}
+
+/// Arrow keys move the focus, Space acts on it like a click (so a first press picks a card
+/// up and a second one places it), and Escape drops the pickup. 1 to 4 send a card to a
+/// foundation and Q to U send cards to a column without walking the focus there.
+fn handle_key_down(hwnd: HWND, state: &mut WindowState, key: u16) -> bool {
+    // A key press is the player taking over, like a click.
+    stop_auto_finish(hwnd, state);
+    if state.deal_anim.is_some() || state.win_anim.is_some() || state.paused {
+        return false;
+    }
+    if state.replay.is_some() {
+        match key {
+            k if k == VK_RIGHT.0 || k == VK_SPACE.0 => step_replay(hwnd, state, true),
+            k if k == VK_LEFT.0 => step_replay(hwnd, state, false),
+            k if k == VK_ESCAPE.0 => {
+                state.replay = None;
+                update_status_bar(state);
+            }
+            _ => return false,
+        }
+        return true;
+    }
+    if key == VK_ESCAPE.0 {
+        let cancelled = cancel_drag(state) || state.pending_selection.take().is_some();
+        if cancelled {
+            state.mouse_down = None;
+            request_redraw(hwnd);
+        }
+        return cancelled;
+    }
+    if state.drag.is_some() {
+        return false;
+    }
+    let direction = match key {
+        k if k == VK_LEFT.0 => Some(FocusMove::Left),
+        k if k == VK_RIGHT.0 => Some(FocusMove::Right),
+        k if k == VK_UP.0 => Some(FocusMove::Up),
+        k if k == VK_DOWN.0 => Some(FocusMove::Down),
+        _ => None,
+    };
+    if let Some(direction) = direction {
+        state.keyboard_active = true;
+        let current = state.focus.unwrap_or(HitTarget::Stock);
+        let next = next_focus(&state.game, current, direction, state.settings.left_handed);
+        set_focus(state, next);
+        request_redraw(hwnd);
+        return true;
+    }
+    if key == VK_TAB.0 {
+        state.keyboard_active = true;
+        let backward = unsafe { GetKeyState(VK_SHIFT.0 as i32) } < 0;
+        let current = state.focus.unwrap_or(HitTarget::Stock);
+        set_focus(state, cycle_focus(current, !backward));
+        request_redraw(hwnd);
+        return true;
+    }
+    if key == VK_SPACE.0 {
+        state.keyboard_active = true;
+        let target = state.focus.unwrap_or(HitTarget::Stock);
+        handle_click(hwnd, state, target);
+        request_redraw(hwnd);
+        return true;
+    }
+    // The keyboard's double-click: the focused card goes home if it can, otherwise to the
+    // best tableau spot, and stays put when neither takes it.
+    if key == VK_RETURN.0 {
+        state.keyboard_active = true;
+        let target = state.focus.unwrap_or(HitTarget::Stock);
+        if send_to_any_foundation(hwnd, state, target) || send_to_best_tableau(hwnd, state, target)
+        {
+            state.pending_selection = None;
+        }
+        request_redraw(hwnd);
+        return true;
+    }
+    if (VK_1.0..=VK_4.0).contains(&key) {
+        state.keyboard_active = true;
+        send_to_foundation(hwnd, state, (key - VK_1.0) as usize);
+        return true;
+    }
+    if let Some(column) = COLUMN_KEYS.iter().position(|&k| u16::from(k) == key) {
+        state.keyboard_active = true;
+        send_to_column(hwnd, state, column);
+        return true;
+    }
+    false
+}
+
+fn return_cards(game: &mut GameState, source: DragSource, cards: Vec<Card>) {
+    match source {
+        DragSource::Waste => game.waste.cards.extend(cards),
+        DragSource::Tableau { column } => game.cancel_tableau_stack(column, cards),
+        DragSource::Foundation(index) => game.foundations[index].cards.extend(cards),
+    }
+}
+
+/// Puts any cards being dragged back where they came from and lets go of the mouse.
+fn cancel_drag(state: &mut WindowState) -> bool {
+    let Some(drag) = state.drag.take() else {
+        return false;
+    };
+    return_cards(&mut state.game, drag.source, drag.cards);
+    unsafe {
+        let _ = ReleaseCapture();
+    }
+    true
+}
+
+/// Gold outline around the keyboard focus, shown only once the keyboard is in use.
+fn draw_focus_outline(dc: HDC, state: &WindowState, metrics: &CardMetrics) {
+    if !state.keyboard_active {
+        return;
+    }
+    if let Some(rect) = state
+        .focus
+        .and_then(|focus| target_rect(state, metrics, focus))
+    {
+        let outlines = &state.settings.outlines;
+        highlight_rect(
+            dc,
+            metrics,
+            rect,
+            outline_color(outlines.focus),
+            outlines.thickness,
+        );
     }
 }
 
-fn draw_card_placeholder_dc(dc: HDC, metrics: &CardMetrics, x: i32, y: i32) {
-    let rect = make_rect(x, y, metrics.card_w, metrics.card_h);
-    let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
-    draw_round_rect_fill(dc, rect, radius, rgb(8, 96, 24), rgb(0, 0, 0));
-    let inner = inset_rect(rect, 3);
-    draw_round_outline(dc, inner, (radius - 2).max(4), rgb(0, 0, 0), 1);
+/// Outline around a hit target; a tableau card includes everything stacked on it.
+fn target_rect(state: &WindowState, metrics: &CardMetrics, target: HitTarget) -> Option<RECT> {
+    Some(match target {
+        HitTarget::Stock => hint_pile_rect(state, metrics, PileRef::Stock, 1, false),
+        HitTarget::Waste => hint_pile_rect(state, metrics, PileRef::Waste, 1, false),
+        HitTarget::Foundation(index) => {
+            hint_pile_rect(state, metrics, PileRef::Foundation(index), 1, false)
+        }
+        HitTarget::Tableau { column, card_index } => {
+            let len = state.game.tableau_len(column);
+            let count = card_index.map_or(1, |index| len.saturating_sub(index));
+            hint_pile_rect(state, metrics, PileRef::Tableau(column), count, true)
+        }
+        HitTarget::None => return None,
+    })
 }
 
-fn draw_card_face_up_to_dc(
-    card_image: Option<&CardImage>,
-    card_dc: HDC,
-    metrics: &CardMetrics,
-    target_dc: HDC,
-    card: &Card,
-    x: i32,
-    y: i32,
-) {
-    let rect = make_rect(x, y, metrics.card_w, metrics.card_h);
-    unsafe {
-        if let (Some(image), true) = (card_image, card_dc.0 != 0) {
-            let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
-            draw_round_rect_fill(
-                target_dc,
-                rect,
-                radius,
-                rgb(252, 252, 252),
-                rgb(204, 204, 204),
-            );
-            let sprite = card.sprite_index as i32;
-            let src_x = (sprite % CARD_SPRITE_COLS) * image.cell_w;
-            let src_y = (sprite / CARD_SPRITE_COLS) * image.cell_h;
-            let trim_x = 1;
-            let trim_y = 1;
-            let src_w = (image.cell_w - trim_x * 2).max(1);
-            let src_h = (image.cell_h - trim_y * 2).max(1);
-            let blend = BLENDFUNCTION {
-                BlendOp: AC_SRC_OVER as u8,
-                BlendFlags: 0,
-                SourceConstantAlpha: 255,
-                AlphaFormat: AC_SRC_ALPHA as u8,
-            };
-            let max_inset_w = ((rect.right - rect.left) / 2).saturating_sub(1);
-            let max_inset_h = ((rect.bottom - rect.top) / 2).saturating_sub(1);
-            let face_gap = (metrics.card_w.min(metrics.card_h) / 32).max(2);
-            let inset = metrics
-                .face_inset
-                .saturating_add(face_gap)
-                .min(max_inset_w)
-                .min(max_inset_h)
-                .max(0);
-            let inner = if inset > 0 {
-                inset_rect(rect, inset)
-            } else {
-                rect
-            };
-            let dest_w = (inner.right - inner.left).max(0);
-            let dest_h = (inner.bottom - inner.top).max(0);
-            if dest_w > 0 && dest_h > 0 {
-                AlphaBlend(
-                    target_dc,
-                    inner.left,
-                    inner.top,
-                    dest_w,
-                    dest_h,
-                    card_dc,
-                    src_x + trim_x,
-                    src_y + trim_y,
-                    src_w,
-                    src_h,
-                    blend,
-                );
-            }
-        } else {
-            draw_card_placeholder_dc(target_dc, metrics, x, y);
-        }
+/// The face-up card under a hit target, if there is one to name.
+fn hover_card(game: &GameState, target: HitTarget) -> Option<Card> {
+    match target {
+        HitTarget::Waste => game.waste.cards.last().copied(),
+        HitTarget::Foundation(index) => game.foundations.get(index)?.cards.last().copied(),
+        HitTarget::Tableau {
+            column,
+            card_index: Some(index),
+        } => game
+            .tableau_card(column, index)
+            .copied()
+            .filter(|card| card.face_up),
+        _ => None,
     }
 }
 
-fn draw_card_back(dc: HDC, rect: RECT) {
-    let radius = ((rect.right - rect.left).min(rect.bottom - rect.top) / 6).max(8);
-    let border = rgb(240, 240, 240);
-    draw_round_rect_fill(dc, rect, radius, rgb(30, 60, 150), border);
+fn card_name(card: &Card) -> String {
+    let rank = match card.rank {
+        Rank::Ace => "Ace",
+        Rank::Two => "Two",
+        Rank::Three => "Three",
+        Rank::Four => "Four",
+        Rank::Five => "Five",
+        Rank::Six => "Six",
+        Rank::Seven => "Seven",
+        Rank::Eight => "Eight",
+        Rank::Nine => "Nine",
+        Rank::Ten => "Ten",
+        Rank::Jack => "Jack",
+        Rank::Queen => "Queen",
+        Rank::King => "King",
+    };
+    let suit = match card.suit {
+        Suit::Spades => "Spades",
+        Suit::Hearts => "Hearts",
+        Suit::Diamonds => "Diamonds",
+        Suit::Clubs => "Clubs",
+    };
+    format!("{rank} of {suit}")
+}
 
-    let inner = inset_rect(rect, 4);
-    let inner_radius = (radius - 4).max(4);
-    draw_round_rect_fill(dc, inner, inner_radius, rgb(12, 32, 104), rgb(12, 32, 104));
+fn draw_hover_outline(dc: HDC, state: &WindowState, metrics: &CardMetrics) {
+    if state.drag.is_some() || state.win_anim.is_some() {
+        return;
+    }
+    if hover_card(&state.game, state.hover).is_none() {
+        return;
+    }
+    if let Some(rect) = target_rect(state, metrics, state.hover) {
+        highlight_rect(dc, metrics, rect, rgb(236, 242, 230), 1);
+    }
+}
 
-    let stripe_width = ((inner.right - inner.left) / 6).max(8);
-    let mut left_stripe = inset_rect(inner, 6);
-    left_stripe.right = left_stripe.left + stripe_width;
-    let stripe_radius = (inner_radius - 4).max(3);
-    draw_round_rect_fill(
-        dc,
-        left_stripe,
-        stripe_radius,
-        rgb(200, 48, 64),
-        rgb(200, 48, 64),
+unsafe fn create_card_tooltip(hwnd: HWND) -> HWND {
+    let hinst = GetModuleHandleW(None).unwrap_or_default();
+    let tooltip = CreateWindowExW(
+        WS_EX_TOPMOST,
+        TOOLTIPS_CLASSW,
+        None,
+        WINDOW_STYLE(WS_POPUP.0 | TTS_NOPREFIX | TTS_ALWAYSTIP),
+        CW_USEDEFAULT,
+        CW_USEDEFAULT,
+        CW_USEDEFAULT,
+        CW_USEDEFAULT,
+        hwnd,
+        None,
+        hinst,
+        None,
     );
+    if tooltip.0 != 0 {
+        let mut empty = [0u16];
+        let info = card_tool_info(hwnd, &mut empty);
+        SendMessageW(
+            tooltip,
+            TTM_ADDTOOLW,
+            WPARAM(0),
+            LPARAM(&info as *const TTTOOLINFOW as isize),
+        );
+    }
+    tooltip
+}
 
-    let mut right_stripe = inset_rect(inner, 6);
-    right_stripe.left = right_stripe.right - stripe_width;
-    draw_round_rect_fill(
-        dc,
-        right_stripe,
-        stripe_radius,
-        rgb(200, 48, 64),
-        rgb(200, 48, 64),
-    );
+/// The one tracking tool the board registers; `text` must outlive any message it is sent with.
+fn card_tool_info(hwnd: HWND, text: &mut [u16]) -> TTTOOLINFOW {
+    TTTOOLINFOW {
+        cbSize: size_of::<TTTOOLINFOW>() as u32,
+        uFlags: TTF_TRACK | TTF_ABSOLUTE,
+        hwnd,
+        uId: 1,
+        lpszText: PWSTR(text.as_mut_ptr()),
+        ..Default::default()
+    }
 }
 
-fn set_focus(state: &mut WindowState, focus: HitTarget) {
-    state.focus = Some(normalize_focus(state, focus));
+/// Tracks the pile under the pointer. A change hides any tooltip and restarts the dwell timer;
+/// drags and the victory animation suppress hovering altogether.
+fn update_hover(hwnd: HWND, state: &mut WindowState, target: HitTarget) {
+    let target = if state.drag.is_some()
+        || state.win_anim.is_some()
+        || state.mouse_down.is_some()
+        || state.paused
+    {
+        HitTarget::None
+    } else {
+        target
+    };
+    if target == state.hover {
+        return;
+    }
+    let had_card = hover_card(&state.game, state.hover).is_some();
+    state.hover = target;
+    refresh_stock_preview(hwnd, state);
+    hide_card_tooltip(hwnd, state);
+    let has_card = hover_card(&state.game, target).is_some();
+    if has_card {
+        unsafe {
+            SetTimer(hwnd, HOVER_TIMER_ID, HOVER_DWELL_MS, None);
+        }
+    }
+    if had_card || has_card {
+        request_redraw(hwnd);
+    }
 }
 
-fn ensure_focus_valid(state: &mut WindowState) {
-    let current = state.focus.unwrap_or(HitTarget::Stock);
-    state.focus = Some(normalize_focus(state, current));
+/// Shows the stock preview while Shift is held over the stock, if the player has opted in.
+fn refresh_stock_preview(hwnd: HWND, state: &mut WindowState) {
+    let shift = unsafe { GetKeyState(VK_SHIFT.0 as i32) } < 0;
+    let show = state.settings.stock_preview && shift && state.hover == HitTarget::Stock;
+    if show != state.stock_preview {
+        state.stock_preview = show;
+        request_redraw(hwnd);
+    }
 }
 
-fn normalize_focus(state: &WindowState, focus: HitTarget) -> HitTarget {
-    match focus {
-        HitTarget::Tableau { column, card_index } => {
-            if TABLEAU_COLUMNS == 0 {
-                return HitTarget::Stock;
-            }
-            if column >= TABLEAU_COLUMNS {
-                return focus_tableau_top(state, TABLEAU_COLUMNS - 1);
-            }
-            let len = state.game.tableau_len(column);
-            if len == 0 {
-                HitTarget::Tableau {
-                    column,
-                    card_index: None,
-                }
-            } else {
-                let mut idx = card_index.unwrap_or(len - 1);
-                if idx >= len {
-                    idx = len - 1;
-                }
-                HitTarget::Tableau {
-                    column,
-                    card_index: Some(idx),
-                }
-            }
-        }
-        HitTarget::Foundation(idx) => {
-            HitTarget::Foundation(idx.min(FOUNDATION_COLUMNS.saturating_sub(1)))
+fn hide_card_tooltip(hwnd: HWND, state: &mut WindowState) {
+    unsafe {
+        let _ = KillTimer(hwnd, HOVER_TIMER_ID);
+        if state.tooltip_visible {
+            let mut empty = [0u16];
+            let info = card_tool_info(hwnd, &mut empty);
+            SendMessageW(
+                state.tooltip,
+                TTM_TRACKACTIVATE,
+                WPARAM(0),
+                LPARAM(&info as *const TTTOOLINFOW as isize),
+            );
         }
-        HitTarget::None => HitTarget::Stock,
-        other => other,
     }
+    state.tooltip_visible = false;
+}
+
+fn show_card_tooltip(hwnd: HWND, state: &mut WindowState) {
+    if state.tooltip.0 == 0 || state.drag.is_some() || state.win_anim.is_some() {
+        return;
+    }
+    let Some(card) = hover_card(&state.game, state.hover) else {
+        return;
+    };
+    let mut text = to_wide(&card_name(&card));
+    let info = card_tool_info(hwnd, &mut text);
+    let mut point = POINT {
+        x: state.pointer_pos.0 + 16,
+        y: state.pointer_pos.1 + 20,
+    };
+    unsafe {
+        let _ = ClientToScreen(hwnd, &mut point);
+        let info_param = LPARAM(&info as *const TTTOOLINFOW as isize);
+        SendMessageW(state.tooltip, TTM_UPDATETIPTEXTW, WPARAM(0), info_param);
+        let position = ((point.y as u16 as u32) << 16) | point.x as u16 as u32;
+        SendMessageW(
+            state.tooltip,
+            TTM_TRACKPOSITION,
+            WPARAM(0),
+            LPARAM(position as isize),
+        );
+        SendMessageW(state.tooltip, TTM_TRACKACTIVATE, WPARAM(1), info_param);
+    }
+    state.tooltip_visible = true;
 }
 
 fn focus_tableau_top(state: &WindowState, column: usize) -> HitTarget {
@@ -2426,17 +6819,38 @@ fn begin_drag(hwnd: HWND, state: &mut WindowState, target: HitTarget, cursor: (i
             }
         }
         HitTarget::Waste => {
-            if state.game.waste.cards.is_empty() {
+            let waste_x = waste_top_x(&state.game, &metrics);
+            let Some((card, snapshot)) = lift_top_card(&mut state.game, DragSource::Waste) else {
                 return false;
-            }
-            let snapshot = state.game.clone();
-            let card = state.game.waste.cards.pop().unwrap();
+            };
             let top = metrics.top_y();
             state.drag = Some(DragContext {
                 source: DragSource::Waste,
                 cards: vec![card],
-                hotspot: (cursor.0 - metrics.column_x(1), cursor.1 - top),
-                position: (metrics.column_x(1), top),
+                hotspot: (cursor.0 - waste_x, cursor.1 - top),
+                position: (waste_x, top),
+                hover: HitTarget::None,
+                snapshot,
+            });
+            state.pending_selection = None;
+            state.layout_metrics = Some(metrics);
+            unsafe {
+                SetCapture(hwnd);
+            }
+            true
+        }
+        HitTarget::Foundation(index) => {
+            let source = DragSource::Foundation(index);
+            let Some((card, snapshot)) = lift_top_card(&mut state.game, source) else {
+                return false;
+            };
+            let x = metrics.foundation_x(index);
+            let top = metrics.top_y();
+            state.drag = Some(DragContext {
+                source: DragSource::Foundation(index),
+                cards: vec![card],
+                hotspot: (cursor.0 - x, cursor.1 - top),
+                position: (x, top),
                 hover: HitTarget::None,
                 snapshot,
             });
@@ -2451,102 +6865,183 @@ fn begin_drag(hwnd: HWND, state: &mut WindowState, target: HitTarget, cursor: (i
     }
 }
 
+/// Takes the top card off the waste or a foundation for dragging, along with the game as it
+/// stood before, which is what undo goes back to once the card is dropped somewhere.
+fn lift_top_card(game: &mut GameState, source: DragSource) -> Option<(Card, GameState)> {
+    let snapshot = game.clone();
+    let card = match source {
+        DragSource::Waste => game.waste.cards.pop(),
+        DragSource::Foundation(index) => game.foundations.get_mut(index)?.cards.pop(),
+        DragSource::Tableau { .. } => None,
+    }?;
+    Some((card, snapshot))
+}
+
+/// A drop that landed on some other pile but was refused, as opposed to one let go over the
+/// felt or back on its own pile.
+fn is_rejected_drop(source: DragSource, drop_target: HitTarget) -> bool {
+    match drop_target {
+        HitTarget::Foundation(index) => {
+            !matches!(source, DragSource::Foundation(from) if from == index)
+        }
+        HitTarget::Tableau { column, .. } => {
+            !matches!(source, DragSource::Tableau { column: from } if from == column)
+        }
+        _ => false,
+    }
+}
+
 fn finalize_drag(state: &mut WindowState, drag: DragContext, drop_target: HitTarget) -> bool {
     let DragContext { source, cards, .. } = drag;
+    // Put the lifted cards back and replay the drop as an ordinary move, so it is checked and
+    // recorded like any other.
+    let count = cards.len();
+    return_cards(&mut state.game, source, cards);
     match source {
-        DragSource::Tableau { column: from } => match drop_target {
-            HitTarget::Tableau { column: to, .. } if from != to => {
-                if state.game.can_accept_tableau_stack(to, &cards) {
-                    state.game.place_tableau_stack(to, cards);
-                    state.game.reveal_tableau_top(from);
+        DragSource::Tableau { column: from } => {
+            let start = state.game.tableau_len(from) - count;
+            match drop_target {
+                HitTarget::Tableau { column: to, .. }
+                    if from != to && state.game.move_tableau_stack(from, start, to) =>
+                {
                     state.pending_selection = None;
                     state.layout_metrics = None;
                     let focus_target = focus_tableau_top(state, to);
                     set_focus(state, focus_target);
                     true
-                } else {
-                    state.game.cancel_tableau_stack(from, cards);
-                    false
                 }
-            }
-            HitTarget::Foundation(index) if cards.len() == 1 => {
-                let card = cards.into_iter().next().unwrap();
-                if state.game.place_on_foundation(index, card) {
-                    state.game.reveal_tableau_top(from);
+                HitTarget::Foundation(index)
+                    if count == 1 && state.game.move_tableau_to_foundation(from, index) =>
+                {
                     state.pending_selection = None;
                     state.layout_metrics = None;
                     set_focus(state, HitTarget::Foundation(index));
                     true
-                } else {
-                    state.game.cancel_tableau_stack(from, vec![card]);
-                    false
                 }
-            }
-            _ => {
-                state.game.cancel_tableau_stack(from, cards);
-                let len = state.game.tableau_len(from);
-                if len == 0 {
-                    state.pending_selection = None;
-                    set_focus(
-                        state,
-                        HitTarget::Tableau {
-                            column: from,
-                            card_index: None,
-                        },
-                    );
-                } else {
-                    let top = len - 1;
-                    state.pending_selection = Some(Selection::Tableau {
-                        column: from,
-                        index: top,
-                    });
-                    set_focus(
-                        state,
-                        HitTarget::Tableau {
+                HitTarget::Tableau { column: to, .. } if from != to => false,
+                HitTarget::Foundation(_) if count == 1 => false,
+                _ => {
+                    let len = state.game.tableau_len(from);
+                    if len == 0 {
+                        state.pending_selection = None;
+                        set_focus(
+                            state,
+                            HitTarget::Tableau {
+                                column: from,
+                                card_index: None,
+                            },
+                        );
+                    } else {
+                        let top = len - 1;
+                        state.pending_selection = Some(Selection::Tableau {
                             column: from,
-                            card_index: Some(top),
-                        },
-                    );
+                            index: top,
+                        });
+                        set_focus(
+                            state,
+                            HitTarget::Tableau {
+                                column: from,
+                                card_index: Some(top),
+                            },
+                        );
+                    }
+                    state.layout_metrics = None;
+                    false
                 }
-                state.layout_metrics = None;
-                false
             }
-        },
+        }
         DragSource::Waste => match drop_target {
             HitTarget::Tableau { column: to, .. } => {
-                if state.game.can_accept_tableau_stack(to, &cards) {
-                    state.game.place_tableau_stack(to, cards);
+                if state.game.move_waste_to_tableau(to) {
                     state.pending_selection = None;
                     state.layout_metrics = None;
                     let focus_target = focus_tableau_top(state, to);
                     set_focus(state, focus_target);
                     true
                 } else {
-                    state.game.waste.cards.extend(cards);
                     false
                 }
             }
-            HitTarget::Foundation(index) if cards.len() == 1 => {
-                let card = cards.into_iter().next().unwrap();
-                if state.game.place_on_foundation(index, card) {
+            HitTarget::Foundation(index) if count == 1 => {
+                if state.game.move_waste_to_foundation(index) {
                     state.pending_selection = None;
                     state.layout_metrics = None;
                     set_focus(state, HitTarget::Foundation(index));
                     true
                 } else {
-                    state.game.waste.cards.push(card);
                     false
                 }
             }
             _ => {
-                state.game.waste.cards.extend(cards);
                 state.pending_selection = Some(Selection::Waste);
                 set_focus(state, HitTarget::Waste);
                 state.layout_metrics = None;
                 false
             }
         },
+        DragSource::Foundation(from) => match drop_target {
+            HitTarget::Tableau { column: to, .. }
+                if state.game.move_foundation_to_tableau(from, to) =>
+            {
+                state.pending_selection = None;
+                state.layout_metrics = None;
+                let focus_target = focus_tableau_top(state, to);
+                set_focus(state, focus_target);
+                true
+            }
+            _ => {
+                state.layout_metrics = None;
+                false
+            }
+        },
+    }
+}
+
+/// Forgiving variant's stock click when play is stuck: draws on to the next card that can be
+/// played and points at where it goes, or, once the stock has nothing left to offer, asks
+/// to reshuffle it. Returns whether the click was used up.
+fn stuck_stock_click(hwnd: HWND, state: &mut WindowState) -> bool {
+    if let Some(draws) = state.game.draws_until_playable() {
+        let snapshot = state.game.clone();
+        for _ in 0..draws {
+            state.game.stock_click();
+        }
+        state.play_sound(Sound::Stock);
+        state.push_undo(snapshot);
+        state.hint = legal_destinations(&state.game, Selection::Waste)
+            .first()
+            .map(|&to| Hint {
+                from: PileRef::Waste,
+                to,
+                cards: 1,
+            });
+        update_status_bar(state);
+        request_redraw(hwnd);
+        return true;
+    }
+    if state.game.has_any_legal_move() || !state.game.can_reshuffle() {
+        return false;
+    }
+    let answer = unsafe {
+        MessageBoxW(
+            hwnd,
+            w!("Nothing left can be played. Shuffle the stock and waste into a new order?"),
+            w!("Reshuffle"),
+            MB_YESNO | MB_ICONQUESTION,
+        )
+    };
+    if answer != IDYES {
+        return true;
     }
+    let snapshot = state.game.clone();
+    let seed = state.game.reshuffle_seed();
+    if state.game.reshuffle_stock_waste(seed) {
+        state.play_sound(Sound::Stock);
+        state.push_undo(snapshot);
+        update_status_bar(state);
+        request_redraw(hwnd);
+    }
+    true
 }
 
 fn handle_click(hwnd: HWND, state: &mut WindowState, target: HitTarget) {
@@ -2554,9 +7049,21 @@ fn handle_click(hwnd: HWND, state: &mut WindowState, target: HitTarget) {
     match target {
         HitTarget::Stock => {
             state.pending_selection = None;
+            // Face-down cards in flight are a recycle still sweeping onto the stock; drawing
+            // from under it would fly the new card out of the middle of the sweep.
+            if state.tweens.iter().any(|tween| !tween.card.face_up) {
+                return;
+            }
+            if state.settings.reshuffle_assist && stuck_stock_click(hwnd, state) {
+                return;
+            }
             let snapshot = state.game.clone();
             match state.game.stock_click() {
-                StockAction::Drawn(_) | StockAction::Recycled(_) => {
+                action @ (StockAction::Drawn(_) | StockAction::Recycled(_)) => {
+                    state.play_sound(Sound::Stock);
+                    if matches!(action, StockAction::Recycled(_)) {
+                        animate_recycle(hwnd, state, &snapshot);
+                    }
                     state.push_undo(snapshot);
                     update_status_bar(state);
                     request_redraw(hwnd);
@@ -2589,21 +7096,37 @@ fn handle_click(hwnd: HWND, state: &mut WindowState, target: HitTarget) {
                             false
                         }
                     }
+                    Selection::Foundation(_) => false,
                 }
             } else {
                 state.game.move_waste_to_foundation(index)
             };
             if moved {
                 state.pending_selection = None;
+                state.play_sound(Sound::Foundation);
+                animate_moves(hwnd, state, &snapshot);
                 state.push_undo(snapshot);
                 update_status_bar(state);
                 check_for_victory(hwnd, state);
                 request_redraw(hwnd);
+            } else if let Some(Selection::Foundation(_)) = state.pending_selection {
+                // A second click on any foundation drops the pickup rather than trying to
+                // move the card sideways.
+                state.pending_selection = None;
+                request_redraw(hwnd);
+            } else if state.pending_selection.is_some() {
+                state.play_sound(Sound::Invalid);
+            } else if !state.game.foundations[index].cards.is_empty() {
+                state.pending_selection = Some(Selection::Foundation(index));
+                request_redraw(hwnd);
             }
         }
         HitTarget::Tableau { column, card_index } => {
             let mut snapshot: Option<GameState> = None;
             let mut moved = false;
+            let attempted = state.pending_selection.is_some_and(|selection| {
+                !matches!(selection, Selection::Tableau { column: from, .. } if from == column)
+            });
             if let Some(selection) = state.pending_selection {
                 match selection {
                     Selection::Waste => {
@@ -2616,22 +7139,23 @@ fn handle_click(hwnd: HWND, state: &mut WindowState, target: HitTarget) {
                     } => {
                         if from != column {
                             snapshot.get_or_insert_with(|| state.game.clone());
-                            if let Some(stack) = state.game.extract_tableau_stack(from, start) {
-                                if state.game.can_accept_tableau_stack(column, &stack) {
-                                    state.game.place_tableau_stack(column, stack);
-                                    state.game.reveal_tableau_top(from);
-                                    moved = true;
-                                } else {
-                                    state.game.cancel_tableau_stack(from, stack);
-                                }
-                            }
+                            moved = state.game.move_tableau_stack(from, start, column);
                         }
                     }
+                    Selection::Foundation(from) => {
+                        snapshot.get_or_insert_with(|| state.game.clone());
+                        moved = state.game.move_foundation_to_tableau(from, column);
+                    }
                 }
             }
+            if !moved && attempted {
+                state.play_sound(Sound::Invalid);
+            }
             if moved {
                 state.pending_selection = None;
+                state.play_sound(Sound::Tableau);
                 if let Some(snap) = snapshot {
+                    animate_moves(hwnd, state, &snap);
                     state.push_undo(snap);
                 }
                 update_status_bar(state);
@@ -2653,6 +7177,7 @@ fn handle_click(hwnd: HWND, state: &mut WindowState, target: HitTarget) {
                         let snapshot = state.game.clone();
                         if state.game.flip_tableau_top(column) {
                             state.pending_selection = None;
+                            start_flip_animations(hwnd, state, &snapshot);
                             state.push_undo(snapshot);
                             update_status_bar(state);
                             request_redraw(hwnd);
@@ -2688,7 +7213,24 @@ fn handle_click(hwnd: HWND, state: &mut WindowState, target: HitTarget) {
     ensure_focus_valid(state);
 }
 
-unsafe fn load_card_bitmap_from_resource(res_id: u16) -> anyhow::Result<Option<CardImage>> {
+/// The parts of the `cards.json` map written by `xtask gen-cards` that the loader needs.
+#[derive(serde::Deserialize)]
+struct SheetMap {
+    /// Row holding the card back, present only when the sheet was packed with `--back`.
+    #[serde(default)]
+    back_row: Option<u32>,
+}
+
+/// Whether the sheet described by `map` carries a card back in the row below the faces.
+/// A missing or unreadable map means a faces-only sheet.
+fn sheet_has_back(map: Option<&[u8]>) -> bool {
+    map.and_then(|bytes| serde_json::from_slice::<SheetMap>(bytes).ok())
+        .and_then(|map| map.back_row)
+        == Some(CARD_SPRITE_ROWS as u32)
+}
+
+/// The bytes of an `RCDATA` resource, which live as long as the module does.
+unsafe fn rcdata_resource(res_id: u16) -> anyhow::Result<Option<&'static [u8]>> {
     let hinst = HINSTANCE(GetModuleHandleW(None)?.0);
     let hresinfo = FindResourceW(hinst, make_int_resource(res_id), make_int_resource(10));
     if hresinfo.0 == 0 {
@@ -2700,7 +7242,13 @@ unsafe fn load_card_bitmap_from_resource(res_id: u16) -> anyhow::Result<Option<C
     if locked.is_null() {
         return Ok(None);
     }
-    let bytes = std::slice::from_raw_parts(locked, size as usize);
+    Ok(Some(std::slice::from_raw_parts(locked, size as usize)))
+}
+
+unsafe fn load_card_bitmap_from_resource(res_id: u16) -> anyhow::Result<Option<CardImage>> {
+    let Some(bytes) = rcdata_resource(res_id)? else {
+        return Ok(None);
+    };
 
     let factory: IWICImagingFactory =
         CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?;
@@ -2752,13 +7300,16 @@ unsafe fn load_card_bitmap_from_resource(res_id: u16) -> anyhow::Result<Option<C
     let slice = std::slice::from_raw_parts_mut(bits as *mut u8, buf_size);
     converter.CopyPixels(std::ptr::null(), stride, slice)?;
 
+    let has_back = sheet_has_back(rcdata_resource(constants::IDB_CARDS_MAP)?);
+    let rows = CARD_SPRITE_ROWS + i32::from(has_back);
     let cell_w = (w / CARD_SPRITE_COLS).max(1);
-    let cell_h = (h / CARD_SPRITE_ROWS).max(1);
+    let cell_h = (h / rows).max(1);
 
     Ok(Some(CardImage {
         hbm,
         cell_w,
         cell_h,
+        has_back,
     }))
 }
 
@@ -2785,57 +7336,137 @@ unsafe fn paint_window(hwnd: HWND, hdc: HDC, state: &mut WindowState) {
 
         if let Some(back) = state.back.as_ref() {
             FillRect(back.dc, &draw_rect, state.bg_brush);
+            if state.settings.score_overlay {
+                draw_score_overlay(back.dc, state, &metrics, draw_rect);
+            }
 
-            let card_image = state.card.as_ref();
-            let card_dc = state.card_dc;
+            // Borrowing the two fields, not the whole state, leaves the tableau slots free.
+            let art = CardArt {
+                image: state.card.as_ref(),
+                dc: state.card_dc,
+            };
+            let gdi = &state.gdi;
 
             let draw_placeholder = |dc: HDC, x: i32, y: i32| {
-                draw_card_placeholder_dc(dc, &metrics, x, y);
+                draw_card_placeholder_dc(dc, gdi, &metrics, x, y);
             };
 
             let draw_face_up = |card: &Card, x: i32, y: i32| {
-                draw_card_face_up_to_dc(card_image, card_dc, &metrics, back.dc, card, x, y);
+                draw_card_shadow(back.dc, gdi, &metrics, x, y);
+                draw_card_face_up_to_dc(art, &metrics, back.dc, gdi, card, x, y);
             };
 
+            let card_back = state.settings.card_back;
             let draw_face_down = |x: i32, y: i32| {
+                draw_card_shadow(back.dc, gdi, &metrics, x, y);
                 let rect = make_rect(x, y, metrics.card_w, metrics.card_h);
-                draw_card_back(back.dc, rect);
+                draw_face_down_card(art, back.dc, gdi, rect, card_back);
             };
 
             let draw_empty = |x: i32, y: i32| {
                 draw_placeholder(back.dc, x, y);
             };
 
+            let deal_anim = &state.deal_anim;
+            let deal_landed = |column: usize, index: usize| {
+                deal_anim
+                    .as_ref()
+                    .is_none_or(|anim| anim.landed(column, index))
+            };
+
+            let now = Instant::now();
+            let flips = &state.flips;
+            let draw_flipping = |card: &Card, x: i32, y: i32, flip: &FlipAnimation| {
+                let (scale, face) = flip.phase(now);
+                let width = ((metrics.card_w as f32 * scale).round() as i32).max(1);
+                let left = x + (metrics.card_w - width) / 2;
+                if face {
+                    let narrow = CardMetrics {
+                        card_w: width,
+                        ..metrics
+                    };
+                    draw_card_face_up_to_dc(art, &narrow, back.dc, gdi, card, left, y);
+                } else {
+                    draw_face_down_card(
+                        art,
+                        back.dc,
+                        gdi,
+                        make_rect(left, y, width, metrics.card_h),
+                        card_back,
+                    );
+                }
+            };
+
+            let tweens = &state.tweens;
+            let in_flight = |card: &Card| {
+                tweens
+                    .iter()
+                    .any(|tween| tween.card.sprite_index == card.sprite_index)
+            };
+
             let top_y = metrics.top_y();
-            let stock_x = metrics.column_x(0);
-            if !state.game.stock.cards.is_empty() {
-                draw_face_down(stock_x, top_y);
+            let stock_x = metrics.stock_x();
+            let stock_count = state.game.stock.cards.len();
+            if stock_count > 0 {
+                // Lower layers peek out below and to the right; the top card keeps the
+                // footprint that hit testing uses.
+                let step = stock_layer_step(&metrics);
+                for layer in (0..stock_layers(stock_count) as i32).rev() {
+                    draw_face_down(stock_x + layer * step, top_y + layer * step);
+                }
+                if state.settings.stock_count_badge {
+                    draw_stock_badge(back.dc, gdi, &metrics, stock_x, top_y, stock_count);
+                }
             } else {
                 draw_empty(stock_x, top_y);
             }
 
-            let waste_x = metrics.column_x(1);
-            if let Some(card) = state.game.waste.cards.last() {
-                draw_face_up(card, waste_x, top_y);
+            let fan_len = waste_fan_len(&state.game);
+            let waste_x = metrics.waste_x(fan_len.saturating_sub(1));
+            if fan_len > 0 {
+                let waste = &state.game.waste.cards;
+                for (offset, card) in waste[waste.len() - fan_len..].iter().enumerate() {
+                    draw_face_up(
+                        card,
+                        waste_x + offset as i32 * metrics.waste_fan_offset,
+                        top_y,
+                    );
+                }
             } else {
                 draw_empty(waste_x, top_y);
             }
 
-            let foundation_start = 3usize;
             for (index, pile) in state.game.foundations.iter().enumerate() {
-                let x = metrics.column_x(foundation_start + index);
+                let x = metrics.foundation_x(index);
                 let emitted = state
                     .win_anim
                     .as_ref()
                     .map(|anim| anim.emitted_from(index))
                     .unwrap_or(0);
                 let visible = pile.cards.len().saturating_sub(emitted);
-                if visible > 0 {
-                    let mut card = pile.cards[visible - 1];
-                    card.face_up = true;
-                    draw_face_up(&card, x, top_y);
-                } else {
-                    draw_empty(x, top_y);
+                match pile.cards[..visible]
+                    .iter()
+                    .rev()
+                    .find(|card| !in_flight(card))
+                {
+                    Some(&card) => {
+                        let card = Card {
+                            face_up: true,
+                            ..card
+                        };
+                        draw_face_up(&card, x, top_y);
+                    }
+                    None => {
+                        draw_empty(x, top_y);
+                        draw_foundation_watermark(
+                            back.dc,
+                            gdi,
+                            &metrics,
+                            x,
+                            top_y,
+                            foundation_watermark(pile),
+                        );
+                    }
                 }
             }
 
@@ -2855,30 +7486,72 @@ unsafe fn paint_window(hwnd: HWND, hdc: HDC, state: &mut WindowState) {
                     continue;
                 }
 
-                let mut y = tableau_top;
+                if !deal_landed(column, 0) {
+                    draw_empty(x, tableau_top);
+                }
+                let tops = metrics.tableau_tops(&pile.cards);
                 for (idx, card) in pile.cards.iter().enumerate() {
+                    if !deal_landed(column, idx) {
+                        break;
+                    }
                     let is_last = idx + 1 == pile.cards.len();
-                    let height = if is_last {
-                        metrics.card_h
-                    } else if card.face_up {
-                        metrics.face_up_offset
-                    } else {
-                        metrics.face_down_offset
-                    };
+                    let y = tops[idx];
+                    let height = tops.get(idx + 1).map_or(metrics.card_h, |next| next - y);
                     slots.push(CardSlot {
                         top: y,
                         height: height.max(1),
                     });
-                    if card.face_up {
+                    let flip = flips[column]
+                        .as_ref()
+                        .filter(|flip| is_last && flip.sprite_index == card.sprite_index);
+                    if in_flight(card) {
+                        continue;
+                    } else if let Some(flip) = flip {
+                        draw_flipping(card, x, y, flip);
+                    } else if card.face_up {
                         draw_face_up(card, x, y);
-                        y += metrics.face_up_offset;
                     } else {
                         draw_face_down(x, y);
-                        y += metrics.face_down_offset;
+                        if state.peek {
+                            draw_peeked_face(art, &metrics, back.dc, gdi, card, x, y);
+                        }
                     }
                 }
             }
 
+            if let Some(deal) = deal_anim {
+                let stock = (stock_x, top_y);
+                for card in deal
+                    .cards
+                    .iter()
+                    .filter(|card| card.emitted && card.progress < 1.0)
+                {
+                    let to = (
+                        metrics.column_x(card.column),
+                        tableau_top + card.index as i32 * metrics.face_down_offset,
+                    );
+                    let lerp = |a: i32, b: i32| a + ((b - a) as f32 * card.progress).round() as i32;
+                    draw_face_down(lerp(stock.0, to.0), lerp(stock.1, to.1));
+                }
+            }
+
+            for tween in tweens {
+                let (x, y) = tween.position(now);
+                if tween.card.face_up {
+                    draw_face_up(&tween.card, x, y);
+                } else {
+                    draw_face_down(x, y);
+                }
+            }
+
+            draw_selection_outline(back.dc, state, &metrics);
+            draw_focus_outline(back.dc, state, &metrics);
+            draw_hover_outline(back.dc, state, &metrics);
+
+            if let Some(hint) = state.hint {
+                draw_hint_outline(back.dc, state, &metrics, hint);
+            }
+
             if let Some(anim) = &state.win_anim {
                 match anim {
                     VictoryAnimation::Modern(modern) => {
@@ -2921,6 +7594,15 @@ unsafe fn paint_window(hwnd: HWND, hdc: HDC, state: &mut WindowState) {
                 }
             }
 
+            draw_drag_feedback(back, state, &metrics);
+            if state.stock_preview {
+                draw_stock_preview(back, state, &metrics);
+            }
+
+            if state.paused {
+                draw_pause_panel(back.dc, state, &metrics, draw_rect);
+            }
+
             if let Some(drag) = &state.drag {
                 let mut y = drag.position.1;
                 let x = drag.position.0;
@@ -2947,16 +7629,692 @@ unsafe fn paint_window(hwnd: HWND, hdc: HDC, state: &mut WindowState) {
     }
 }
 
-fn show_about_dialog(hwnd: HWND) {
-    unsafe {
+/// The score overlay's line: the status bar's essentials, without the pile counts.
+fn score_overlay_text(game: &GameState, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!(
+        "Draw {}   Score: {}   Moves: {}   Time: {:02}:{:02}",
+        game.draw_mode.count(),
+        format_score(game),
+        game.moves,
+        secs / 60,
+        secs % 60
+    )
+}
+
+/// Paints the score overlay into the bottom margin, under the cards, which only reach it once
+/// a long column has closed up as far as it can.
+fn draw_score_overlay(dc: HDC, state: &WindowState, metrics: &CardMetrics, area: RECT) {
+    let font_h = scale_for_dpi(14, BASE_DPI, state.dpi).max(metrics.margin * 2 / 3);
+    let mut rect = RECT {
+        left: area.left + metrics.margin,
+        top: (area.bottom - metrics.margin).min(area.bottom - font_h),
+        right: area.right - metrics.margin,
+        bottom: area.bottom,
+    };
+    let mut text = to_wide(&score_overlay_text(&state.game, state.clock.elapsed()));
+    unsafe {
+        let font = state.gdi.font(UiFont::Text, font_h);
+        let old_font = (font.0 != 0).then(|| SelectObject(dc, font));
+        let _ = SetBkMode(dc, TRANSPARENT);
+        let _ = SetTextColor(dc, rgb(236, 242, 230));
+        let _ = DrawTextW(
+            dc,
+            text.as_mut_slice(),
+            &mut rect,
+            DT_LEFT | DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS,
+        );
+        if let Some(old) = old_font {
+            SelectObject(dc, old);
+        }
+    }
+}
+
+/// Covers the whole table with felt and a "Paused" panel, so nothing of the deal shows.
+fn draw_pause_panel(dc: HDC, state: &WindowState, metrics: &CardMetrics, area: RECT) {
+    unsafe {
+        FillRect(dc, &area, state.bg_brush);
+    }
+    let panel_w = (metrics.card_w * 4).min(area.right - area.left);
+    let panel_h = metrics.card_h;
+    let panel = make_rect(
+        (area.left + area.right - panel_w) / 2,
+        (area.top + area.bottom - panel_h) / 2,
+        panel_w,
+        panel_h,
+    );
+    let radius = (metrics.card_w.min(metrics.card_h) / 6).max(6);
+    draw_round_rect_fill(
+        dc,
+        &state.gdi,
+        panel,
+        radius,
+        metrics.slot_fill,
+        rgb(0, 0, 0),
+    );
+    unsafe {
+        let font = state.gdi.font(UiFont::Text, panel_h / 4);
+        let old_font = (font.0 != 0).then(|| SelectObject(dc, font));
+        let _ = SetBkMode(dc, TRANSPARENT);
+        let _ = SetTextColor(dc, rgb(236, 242, 230));
+        let mut title = to_wide("Paused");
+        let mut rect = panel;
+        rect.bottom = panel.top + panel_h * 2 / 3;
+        let _ = DrawTextW(
+            dc,
+            title.as_mut_slice(),
+            &mut rect,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+        );
+        if let Some(old) = old_font {
+            SelectObject(dc, old);
+        }
+        let mut hint = to_wide("Press P to resume");
+        let mut rect = panel;
+        rect.top = panel.top + panel_h / 2;
+        let _ = DrawTextW(
+            dc,
+            hint.as_mut_slice(),
+            &mut rect,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+        );
+    }
+}
+
+/// Hides the board and holds the clock, or brings both back.
+fn toggle_pause(hwnd: HWND, state: &mut WindowState) {
+    if state.win_anim.is_some() || state.deal_anim.is_some() {
+        return;
+    }
+    let paused = !state.paused;
+    if paused {
+        cancel_drag(state);
+        state.mouse_down = None;
+        update_hover(hwnd, state, HitTarget::None);
+    }
+    state.paused = paused;
+    state.clock.set_held(paused);
+    update_status_bar(state);
+    force_redraw(hwnd);
+}
+
+fn show_about_dialog(hwnd: HWND) {
+    unsafe {
+        let hinst = GetModuleHandleW(None).unwrap_or_default();
+        let _ = DialogBoxParamW(
+            hinst,
+            make_int_resource(constants::IDD_ABOUT),
+            hwnd,
+            Some(about_dialog_proc),
+            LPARAM(0),
+        );
+    }
+}
+
+fn format_stats(stats: &Stats) -> String {
+    let best_time = if stats.best_time_secs == 0 {
+        "-".to_string()
+    } else {
+        format!(
+            "{:02}:{:02}",
+            stats.best_time_secs / 60,
+            stats.best_time_secs % 60
+        )
+    };
+    format!(
+        "Games played:\t{}\nGames won:\t{} ({}%)\nCurrent streak:\t{}\nBest streak:\t{}\nBest time:\t{}\nBest lost game:\t{} cards home",
+        stats.played,
+        stats.won,
+        stats.win_percentage(),
+        stats.current_streak,
+        stats.best_streak,
+        best_time,
+        stats.best_lost_foundation
+    )
+}
+
+fn show_stats_dialog(hwnd: HWND, stats: &Stats) {
+    let text = to_wide(&format_stats(stats));
+    unsafe {
+        let hinst = GetModuleHandleW(None).unwrap_or_default();
+        let _ = DialogBoxParamW(
+            hinst,
+            make_int_resource(constants::IDD_STATS),
+            hwnd,
+            Some(stats_dialog_proc),
+            LPARAM(text.as_ptr() as isize),
+        );
+    }
+}
+
+unsafe extern "system" fn stats_dialog_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> isize {
+    match msg {
+        WM_INITDIALOG => {
+            let _ = SetDlgItemTextW(
+                hwnd,
+                constants::IDC_STATS_TEXT as i32,
+                PCWSTR(lparam.0 as *const u16),
+            );
+            1
+        }
+        WM_COMMAND => {
+            let id = loword(wparam);
+            if id == IDOK.0 as u16 || id == IDCANCEL.0 as u16 {
+                let _ = EndDialog(hwnd, 0);
+            }
+            1
+        }
+        _ => 0,
+    }
+}
+
+const DRAW_CHOICES: [(&str, DrawMode); 3] = [
+    ("Draw one", DrawMode::DrawOne),
+    ("Draw two", DrawMode::DrawTwo),
+    ("Draw three", DrawMode::DrawThree),
+];
+
+const SCORING_CHOICES: [(&str, ScoringMode, bool); 3] = [
+    ("Standard", ScoringMode::Standard, false),
+    ("Vegas", ScoringMode::Vegas, false),
+    ("Vegas (cumulative)", ScoringMode::Vegas, true),
+];
+
+const PASS_CHOICES: [(&str, Option<u32>); 3] =
+    [("Unlimited", None), ("One", Some(1)), ("Three", Some(3))];
+
+const FELT_CHOICES: [(&str, FeltColor); 4] = [
+    ("Green", FeltColor::Green),
+    ("Blue", FeltColor::Blue),
+    ("Burgundy", FeltColor::Burgundy),
+    ("Charcoal", FeltColor::Charcoal),
+];
+
+const BACK_CHOICES: [(&str, CardBack); 4] = [
+    ("Classic blue", CardBack::ClassicBlue),
+    ("Red diamond", CardBack::RedDiamond),
+    ("Green lattice", CardBack::GreenLattice),
+    ("Plain", CardBack::Plain),
+];
+
+const OPTION_CHECKBOXES: [u16; 6] = [
+    constants::IDC_OPT_SOUND,
+    constants::IDC_OPT_ANIMATE_DEAL,
+    constants::IDC_OPT_ANIMATE_FLIPS,
+    constants::IDC_OPT_HIGHLIGHT,
+    constants::IDC_OPT_SHOW_SEED,
+    constants::IDC_OPT_CONFIRM_DISCARD,
+];
+
+fn option_flags(settings: &mut Settings) -> [&mut bool; 6] {
+    [
+        &mut settings.sound,
+        &mut settings.animate_deal,
+        &mut settings.animate_flips,
+        &mut settings.highlight_moves,
+        &mut settings.show_seed,
+        &mut settings.confirm_discard,
+    ]
+}
+
+/// Shows the Options dialog seeded with `current`; `None` if the player cancels.
+fn show_options_dialog(hwnd: HWND, current: Settings) -> Option<Settings> {
+    let mut options = current;
+    let result = unsafe {
+        let hinst = GetModuleHandleW(None).unwrap_or_default();
+        DialogBoxParamW(
+            hinst,
+            make_int_resource(constants::IDD_OPTIONS),
+            hwnd,
+            Some(options_dialog_proc),
+            LPARAM(&mut options as *mut Settings as isize),
+        )
+    };
+    (result == IDOK.0 as isize).then_some(options)
+}
+
+unsafe fn fill_combo(dialog: HWND, id: u16, labels: &[&str], selected: Option<usize>) {
+    for label in labels {
+        let wide = to_wide(label);
+        SendDlgItemMessageW(
+            dialog,
+            id as i32,
+            CB_ADDSTRING,
+            WPARAM(0),
+            LPARAM(wide.as_ptr() as isize),
+        );
+    }
+    let index = selected.map_or(-1, |index| index as isize);
+    SendDlgItemMessageW(
+        dialog,
+        id as i32,
+        CB_SETCURSEL,
+        WPARAM(index as usize),
+        LPARAM(0),
+    );
+}
+
+unsafe fn combo_selection(dialog: HWND, id: u16) -> Option<usize> {
+    let index = SendDlgItemMessageW(dialog, id as i32, CB_GETCURSEL, WPARAM(0), LPARAM(0)).0;
+    (index != CB_ERR as isize).then_some(index as usize)
+}
+
+unsafe fn init_options_dialog(dialog: HWND, settings: &mut Settings) {
+    fill_combo(
+        dialog,
+        constants::IDC_OPT_DRAW,
+        &DRAW_CHOICES.map(|choice| choice.0),
+        DRAW_CHOICES
+            .iter()
+            .position(|&(_, mode)| mode == settings.draw_mode),
+    );
+    fill_combo(
+        dialog,
+        constants::IDC_OPT_SCORING,
+        &SCORING_CHOICES.map(|choice| choice.0),
+        SCORING_CHOICES.iter().position(|&(_, mode, cumulative)| {
+            mode == settings.scoring_mode
+                && (mode == ScoringMode::Standard || cumulative == settings.vegas_cumulative)
+        }),
+    );
+    fill_combo(
+        dialog,
+        constants::IDC_OPT_PASSES,
+        &PASS_CHOICES.map(|choice| choice.0),
+        PASS_CHOICES
+            .iter()
+            .position(|&(_, limit)| limit == settings.pass_limit),
+    );
+    fill_combo(
+        dialog,
+        constants::IDC_OPT_FELT,
+        &FELT_CHOICES.map(|choice| choice.0),
+        FELT_CHOICES
+            .iter()
+            .position(|&(_, felt)| felt == settings.felt),
+    );
+    fill_combo(
+        dialog,
+        constants::IDC_OPT_BACK,
+        &BACK_CHOICES.map(|choice| choice.0),
+        BACK_CHOICES
+            .iter()
+            .position(|&(_, back)| back == settings.card_back),
+    );
+    for (id, flag) in OPTION_CHECKBOXES.into_iter().zip(option_flags(settings)) {
+        let check = if *flag { BST_CHECKED } else { BST_UNCHECKED };
+        SendDlgItemMessageW(
+            dialog,
+            id as i32,
+            BM_SETCHECK,
+            WPARAM(check.0 as usize),
+            LPARAM(0),
+        );
+    }
+}
+
+/// Reads every control back into `settings`. Leaves `settings` untouched and returns false if
+/// any list has nothing selected, so a half-read dialog is never applied.
+unsafe fn read_options_dialog(dialog: HWND, settings: &mut Settings) -> bool {
+    let (Some(draw), Some(scoring), Some(passes), Some(felt), Some(back)) = (
+        combo_selection(dialog, constants::IDC_OPT_DRAW),
+        combo_selection(dialog, constants::IDC_OPT_SCORING),
+        combo_selection(dialog, constants::IDC_OPT_PASSES),
+        combo_selection(dialog, constants::IDC_OPT_FELT),
+        combo_selection(dialog, constants::IDC_OPT_BACK),
+    ) else {
+        return false;
+    };
+    let (
+        Some(&(_, draw_mode)),
+        Some(&(_, scoring_mode, cumulative)),
+        Some(&(_, pass_limit)),
+        Some(&(_, felt)),
+        Some(&(_, card_back)),
+    ) = (
+        DRAW_CHOICES.get(draw),
+        SCORING_CHOICES.get(scoring),
+        PASS_CHOICES.get(passes),
+        FELT_CHOICES.get(felt),
+        BACK_CHOICES.get(back),
+    )
+    else {
+        return false;
+    };
+    let mut read = Settings {
+        draw_mode,
+        felt,
+        card_back,
+        scoring_mode,
+        vegas_cumulative: cumulative,
+        pass_limit,
+        ..settings.clone()
+    };
+    for (id, flag) in OPTION_CHECKBOXES.into_iter().zip(option_flags(&mut read)) {
+        let state = SendDlgItemMessageW(dialog, id as i32, BM_GETCHECK, WPARAM(0), LPARAM(0));
+        *flag = state.0 == BST_CHECKED.0 as isize;
+    }
+    *settings = read;
+    true
+}
+
+unsafe extern "system" fn options_dialog_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> isize {
+    match msg {
+        WM_INITDIALOG => {
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, lparam.0);
+            let settings = lparam.0 as *mut Settings;
+            if !settings.is_null() {
+                init_options_dialog(hwnd, &mut *settings);
+            }
+            1
+        }
+        WM_COMMAND => {
+            let id = loword(wparam);
+            if id == IDOK.0 as u16 {
+                let settings = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut Settings;
+                if !settings.is_null() && read_options_dialog(hwnd, &mut *settings) {
+                    let _ = EndDialog(hwnd, IDOK.0 as isize);
+                } else {
+                    let _ = MessageBeep(MB_OK);
+                }
+            } else if id == IDCANCEL.0 as u16 {
+                let _ = EndDialog(hwnd, IDCANCEL.0 as isize);
+            }
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// Applies a confirmed Options dialog. Everything takes effect at once except a scoring change,
+/// which needs a fresh deal so the score never mixes two rule sets. The settings reach the
+/// registry as a single value, so an interrupted apply leaves either the old or the new set.
+fn apply_options(hwnd: HWND, state: &mut WindowState, settings: Settings) {
+    let felt = settings.felt;
+    // Keep the old felt for a moment so `change_felt` notices the switch and swaps the brush.
+    state.settings = Settings {
+        felt: state.settings.felt,
+        ..settings.clone()
+    };
+    change_felt(hwnd, state, felt);
+    state.settings = settings;
+
+    if state.game.draw_mode != state.settings.draw_mode {
+        state.game.draw_mode = state.settings.draw_mode;
+        state.pending_selection = None;
+    }
+    state.game.pass_limit = state.settings.pass_limit;
+    state.game.manual_flips = !state.settings.auto_flip;
+    state.game.allow_any_to_empty = state.settings.allow_any_to_empty;
+    let (scoring_mode, cumulative) = (state.settings.scoring_mode, state.settings.vegas_cumulative);
+    change_scoring_mode(hwnd, state, scoring_mode, cumulative);
+    settings::save_settings(&state.settings);
+
+    unsafe {
+        update_draw_menu(hwnd, state.game.draw_mode);
+        update_pass_limit_menu(hwnd, &state.game);
+        update_rules_menu(hwnd, &state.game);
+        update_preferences_menu(hwnd, &state.settings);
+        update_card_back_menu(hwnd, state.settings.card_back);
+    }
+    layout_status_parts(state);
+    update_status_bar(state);
+    force_redraw(hwnd);
+}
+
+fn format_seed(seed: u64) -> String {
+    format!("0x{seed:X}")
+}
+
+/// Puts the deal's seed on the clipboard in decimal, ready to paste into Deal By Seed.
+fn copy_seed(hwnd: HWND, state: &WindowState) {
+    if let Err(err) = copy_to_clipboard(hwnd, &state.game.rng_seed.to_string()) {
+        debug_log(&format!("copy_seed failed: {err:?}"));
+        unsafe {
+            let _ = MessageBeep(MB_OK);
+        }
+    }
+}
+
+/// Replaces the clipboard contents with `text`. The clipboard takes ownership of the memory
+/// once `SetClipboardData` succeeds; until then it is ours to free.
+fn copy_to_clipboard(hwnd: HWND, text: &str) -> anyhow::Result<()> {
+    let wide = to_wide(text);
+    unsafe {
+        OpenClipboard(hwnd)?;
+        let result = (|| -> anyhow::Result<()> {
+            EmptyClipboard()?;
+            let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * size_of::<u16>())?;
+            let locked = GlobalLock(memory) as *mut u16;
+            if locked.is_null() {
+                let _ = GlobalFree(memory);
+                return Err(anyhow::anyhow!("GlobalLock failed"));
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), locked, wide.len());
+            // Reports an "error" once the lock count reaches zero, which is the expected case.
+            let _ = GlobalUnlock(memory);
+            if let Err(err) =
+                SetClipboardData(u32::from(CF_UNICODETEXT.0), HANDLE(memory.0 as isize))
+            {
+                let _ = GlobalFree(memory);
+                return Err(err.into());
+            }
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+/// Prompts for a seed, pre-filled with `current`. Returns `None` if the user cancels.
+fn show_seed_dialog(hwnd: HWND, current: u64) -> Option<u64> {
+    let mut seed = current;
+    let result = unsafe {
+        let hinst = GetModuleHandleW(None).unwrap_or_default();
+        DialogBoxParamW(
+            hinst,
+            make_int_resource(constants::IDD_DEAL_SEED),
+            hwnd,
+            Some(seed_dialog_proc),
+            LPARAM(&mut seed as *mut u64 as isize),
+        )
+    };
+    (result == IDOK.0 as isize).then_some(seed)
+}
+
+unsafe extern "system" fn seed_dialog_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> isize {
+    match msg {
+        WM_INITDIALOG => {
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, lparam.0);
+            let seed = *(lparam.0 as *const u64);
+            let text = to_wide(&format_seed(seed));
+            let _ = SetDlgItemTextW(hwnd, constants::IDC_SEED_EDIT as i32, PCWSTR(text.as_ptr()));
+            1
+        }
+        WM_COMMAND => {
+            let id = loword(wparam);
+            if id == IDOK.0 as u16 {
+                let mut buffer = [0u16; 64];
+                let len = GetDlgItemTextW(hwnd, constants::IDC_SEED_EDIT as i32, &mut buffer);
+                let text = String::from_utf16_lossy(&buffer[..len as usize]);
+                let out = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut u64;
+                match parse_seed(&text) {
+                    Some(seed) if !out.is_null() => {
+                        *out = seed;
+                        let _ = EndDialog(hwnd, IDOK.0 as isize);
+                    }
+                    _ => {
+                        let _ = MessageBeep(MB_OK);
+                        SendDlgItemMessageW(
+                            hwnd,
+                            constants::IDC_SEED_EDIT as i32,
+                            EM_SETSEL,
+                            WPARAM(0),
+                            LPARAM(-1),
+                        );
+                    }
+                }
+            } else if id == IDCANCEL.0 as u16 {
+                let _ = EndDialog(hwnd, IDCANCEL.0 as isize);
+            }
+            1
+        }
+        _ => 0,
+    }
+}
+
+fn format_win_summary(game: &GameState, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let bonus = if game.time_bonus > 0 {
+        format!(" (time bonus {})", game.time_bonus)
+    } else {
+        String::new()
+    };
+    format!(
+        "Time: {:02}:{:02}\nMoves: {}\nScore: {}{}\nSeed: {}",
+        secs / 60,
+        secs % 60,
+        game.moves,
+        format_score(game),
+        bonus,
+        format_seed(game.rng_seed)
+    )
+}
+
+/// Shows the end-of-game summary and returns the id of the button that closed it.
+fn show_win_dialog(hwnd: HWND, summary: &str) -> u16 {
+    let text = to_wide(summary);
+    let result = unsafe {
         let hinst = GetModuleHandleW(None).unwrap_or_default();
-        let _ = DialogBoxParamW(
+        DialogBoxParamW(
             hinst,
-            make_int_resource(constants::IDD_ABOUT),
+            make_int_resource(constants::IDD_WIN),
             hwnd,
-            Some(about_dialog_proc),
-            LPARAM(0),
-        );
+            Some(win_dialog_proc),
+            LPARAM(text.as_ptr() as isize),
+        )
+    };
+    result as u16
+}
+
+struct WinDialogState {
+    bg_brush: HBRUSH,
+    summary: Vec<u16>,
+}
+
+unsafe fn free_win_state(hwnd: HWND) {
+    let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WinDialogState;
+    if ptr.is_null() {
+        return;
+    }
+    let state = Box::from_raw(ptr);
+    if state.bg_brush.0 != 0 {
+        let _ = DeleteObject(state.bg_brush);
+    }
+    SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+}
+
+/// Painted on the felt like the About box; `lparam` is the NUL-terminated summary text.
+unsafe extern "system" fn win_dialog_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> isize {
+    match msg {
+        WM_INITDIALOG => {
+            let bg_brush = CreateSolidBrush(rgb(12, 90, 24));
+            let summary = PCWSTR(lparam.0 as *const u16).as_wide().to_vec();
+            let state = Box::new(WinDialogState { bg_brush, summary });
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as isize);
+            1
+        }
+        WM_CTLCOLORDLG => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const WinDialogState;
+            if ptr.is_null() {
+                0
+            } else {
+                (*ptr).bg_brush.0
+            }
+        }
+        WM_CTLCOLORBTN | WM_CTLCOLORSTATIC => {
+            let hdc = HDC(wparam.0 as isize);
+            let _ = SetBkMode(hdc, TRANSPARENT);
+            0
+        }
+        WM_PAINT => {
+            let mut ps = PAINTSTRUCT::default();
+            let hdc = BeginPaint(hwnd, &mut ps);
+            let mut client = RECT::default();
+            let _ = GetClientRect(hwnd, &mut client);
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WinDialogState;
+            if let Some(state) = ptr.as_mut() {
+                if state.bg_brush.0 != 0 {
+                    FillRect(hdc, &client, state.bg_brush);
+                }
+                let _ = SetBkMode(hdc, TRANSPARENT);
+                let _ = SetTextColor(hdc, rgb(236, 242, 230));
+                let mut title = to_wide("Congratulations, you won!");
+                let mut title_rect = RECT {
+                    left: client.left + 16,
+                    top: client.top + 16,
+                    right: client.right - 16,
+                    bottom: client.top + 40,
+                };
+                let _ = DrawTextW(
+                    hdc,
+                    title.as_mut_slice(),
+                    &mut title_rect,
+                    DT_CENTER | DT_SINGLELINE | DT_TOP,
+                );
+                let _ = SetTextColor(hdc, rgb(200, 212, 198));
+                let mut body_rect = RECT {
+                    left: client.left + 16,
+                    top: client.top + 52,
+                    right: client.right - 16,
+                    bottom: client.bottom - 48,
+                };
+                let _ = DrawTextW(
+                    hdc,
+                    state.summary.as_mut_slice(),
+                    &mut body_rect,
+                    DT_CENTER | DT_TOP,
+                );
+            }
+            EndPaint(hwnd, &ps);
+            1
+        }
+        WM_COMMAND => {
+            let id = loword(wparam);
+            if id == constants::IDC_WIN_NEW
+                || id == constants::IDC_WIN_REPLAY
+                || id == IDCANCEL.0 as u16
+            {
+                free_win_state(hwnd);
+                let _ = EndDialog(hwnd, id as isize);
+            }
+            1
+        }
+        WM_DESTROY => {
+            free_win_state(hwnd);
+            0
+        }
+        _ => 0,
     }
 }
 
@@ -2993,6 +8351,18 @@ unsafe fn free_about_state(hwnd: HWND) {
     SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
 }
 
+/// Product name and build version for the About box, with the commit when it is known.
+fn about_title() -> String {
+    match constants::GIT_HASH {
+        Some(hash) => format!(
+            "{} v{} ({hash})",
+            constants::PRODUCT_NAME,
+            constants::VERSION
+        ),
+        None => format!("{} v{}", constants::PRODUCT_NAME, constants::VERSION),
+    }
+}
+
 unsafe extern "system" fn about_dialog_proc(
     hwnd: HWND,
     msg: u32,
@@ -3088,18 +8458,19 @@ unsafe extern "system" fn about_dialog_proc(
                 let _ = SelectObject(hdc, old_brush);
                 let _ = SetTextColor(hdc, rgb(236, 242, 230));
                 let _ = SetBkMode(hdc, TRANSPARENT);
-                let mut title = to_wide(&format!("{} V1.0.0", constants::PRODUCT_NAME));
+                let mut title = to_wide(&about_title());
                 let mut title_rect = RECT {
                     left: client.left + 20,
                     top: base_y + card_height + 16,
                     right: client.right - 20,
                     bottom: client.bottom - 56,
                 };
+                // A long git hash must not spill past the dialog's edge.
                 let _ = DrawTextW(
                     hdc,
                     title.as_mut_slice(),
                     &mut title_rect,
-                    DT_CENTER | DT_SINGLELINE | DT_TOP,
+                    DT_CENTER | DT_SINGLELINE | DT_TOP | DT_END_ELLIPSIS,
                 );
                 let _ = SetTextColor(hdc, rgb(200, 212, 198));
                 let brand = constants::COMPANY_NAME
@@ -3147,3 +8518,877 @@ unsafe extern "system" fn about_dialog_proc(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(moves: u32) -> GameState {
+        GameState {
+            moves,
+            ..GameState::default()
+        }
+    }
+
+    #[test]
+    fn test_player_pause_and_focus_loss_hold_the_clock_independently() {
+        let mut clock = GameClock::default();
+        clock.start();
+        assert!(clock.is_running());
+        clock.set_held(true);
+        clock.set_paused(true);
+        clock.set_paused(false);
+        assert!(!clock.is_running());
+        clock.set_held(false);
+        assert!(clock.is_running());
+        clock.set_held(true);
+        clock.reset();
+        clock.start();
+        assert!(clock.is_running());
+    }
+
+    #[test]
+    fn test_undo_history_is_capped() {
+        let mut history = UndoHistory::default();
+        let pushed = UNDO_LIMIT as u32 * 3;
+        for moves in 0..pushed {
+            history.push(snapshot(moves));
+        }
+        assert_eq!(history.undo.len(), UNDO_LIMIT);
+        assert_eq!(
+            history.undo.front().unwrap().moves,
+            pushed - UNDO_LIMIT as u32
+        );
+
+        let mut game = snapshot(pushed);
+        assert!(history.undo(&mut game));
+        assert_eq!(game.moves, pushed - 1);
+        assert!(history.undo(&mut game));
+        assert_eq!(game.moves, pushed - 2);
+        assert!(history.redo(&mut game));
+        assert!(history.redo(&mut game));
+        assert_eq!(game.moves, pushed);
+        assert!(!history.redo(&mut game));
+        assert_eq!(history.undo.len(), UNDO_LIMIT);
+
+        while history.undo(&mut game) {}
+        assert_eq!(history.redo.len(), UNDO_LIMIT);
+        assert_eq!(game.moves, pushed - UNDO_LIMIT as u32);
+
+        history.push(snapshot(0));
+        assert!(history.redo.is_empty());
+    }
+
+    #[test]
+    fn test_undo_and_redo_keep_the_net_move_count() {
+        let mut history = UndoHistory::default();
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        for _ in 0..3 {
+            history.push(game.clone());
+            assert_ne!(game.stock_click(), StockAction::NoOp);
+        }
+        assert_eq!(game.moves, 3);
+
+        assert!(history.undo(&mut game));
+        assert!(history.undo(&mut game));
+        assert_eq!(game.moves, 1);
+        assert!(history.redo(&mut game));
+        assert_eq!(game.moves, 2);
+
+        // A fresh move after an undo replaces the redone branch rather than adding to it.
+        history.push(game.clone());
+        game.stock_click();
+        assert_eq!(game.moves, 3);
+        assert!(!history.redo(&mut game));
+        while history.undo(&mut game) {}
+        assert_eq!(game.moves, 0);
+    }
+
+    #[test]
+    fn test_legal_destinations_for_selection() {
+        use engine::Suit;
+        let face_up = |suit, rank| Card {
+            face_up: true,
+            ..Card::new(suit, rank)
+        };
+        let mut game = GameState::new();
+        game.waste.cards.push(face_up(Suit::Hearts, Rank::Ace));
+        game.tableaus[0]
+            .cards
+            .push(face_up(Suit::Spades, Rank::Queen));
+        game.tableaus[1]
+            .cards
+            .push(face_up(Suit::Hearts, Rank::Jack));
+        game.tableaus[2]
+            .cards
+            .push(face_up(Suit::Diamonds, Rank::King));
+        game.tableaus[5].cards.push(face_up(Suit::Clubs, Rank::Two));
+
+        // An ace fits every empty foundation as well as the black two.
+        let waste = legal_destinations(&game, Selection::Waste);
+        assert_eq!(waste.len(), FOUNDATION_COLUMNS + 1);
+        assert_eq!(waste.last(), Some(&PileRef::Tableau(5)));
+
+        // The jack can go on the queen; the empty columns only take kings.
+        let jack = legal_destinations(
+            &game,
+            Selection::Tableau {
+                column: 1,
+                index: 0,
+            },
+        );
+        assert_eq!(jack, vec![PileRef::Tableau(0)]);
+        let king = legal_destinations(
+            &game,
+            Selection::Tableau {
+                column: 2,
+                index: 0,
+            },
+        );
+        assert_eq!(
+            king,
+            vec![
+                PileRef::Tableau(3),
+                PileRef::Tableau(4),
+                PileRef::Tableau(6)
+            ]
+        );
+        assert!(legal_destinations(
+            &game,
+            Selection::Tableau {
+                column: 1,
+                index: 4
+            }
+        )
+        .is_empty());
+
+        // A foundation's top card can come back down onto the tableau, but never sideways.
+        game.foundations[1]
+            .cards
+            .push(face_up(Suit::Diamonds, Rank::Jack));
+        assert_eq!(
+            legal_destinations(&game, Selection::Foundation(1)),
+            vec![PileRef::Tableau(0)]
+        );
+        assert!(legal_destinations(&game, Selection::Foundation(0)).is_empty());
+    }
+
+    #[test]
+    fn test_arrow_navigation_walks_the_board() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        let top = |column: usize| HitTarget::Tableau {
+            column,
+            card_index: Some(column),
+        };
+
+        assert_eq!(
+            next_focus(&game, HitTarget::Stock, FocusMove::Right, false),
+            HitTarget::Waste
+        );
+        assert_eq!(
+            next_focus(&game, HitTarget::Waste, FocusMove::Right, false),
+            HitTarget::Foundation(0)
+        );
+        assert_eq!(
+            next_focus(&game, HitTarget::Foundation(3), FocusMove::Right, false),
+            HitTarget::Foundation(3)
+        );
+        assert_eq!(
+            next_focus(&game, HitTarget::Foundation(1), FocusMove::Down, false),
+            top(4)
+        );
+        // Only the top card of a fresh deal is face up, so Up leaves the column at once.
+        assert_eq!(
+            next_focus(&game, top(4), FocusMove::Up, false),
+            HitTarget::Foundation(1)
+        );
+        assert_eq!(
+            next_focus(&game, top(2), FocusMove::Up, false),
+            HitTarget::Waste
+        );
+        assert_eq!(next_focus(&game, top(6), FocusMove::Right, false), top(6));
+        assert_eq!(next_focus(&game, top(3), FocusMove::Left, false), top(2));
+        assert_eq!(next_focus(&game, top(3), FocusMove::Down, false), top(3));
+    }
+
+    #[test]
+    fn test_tab_cycles_every_pile_and_wraps() {
+        let column = |column| HitTarget::Tableau {
+            column,
+            card_index: None,
+        };
+        let mut focus = HitTarget::Stock;
+        let mut visited = vec![focus];
+        for _ in 0..(2 + FOUNDATION_COLUMNS + TABLEAU_COLUMNS) {
+            focus = cycle_focus(focus, true);
+            visited.push(focus);
+        }
+        assert_eq!(visited[1], HitTarget::Waste);
+        assert_eq!(visited[2], HitTarget::Foundation(0));
+        assert_eq!(visited[6], column(0));
+        assert_eq!(visited[12], column(6));
+        assert_eq!(focus, HitTarget::Stock);
+
+        assert_eq!(cycle_focus(HitTarget::Stock, false), column(6));
+        let deep = HitTarget::Tableau {
+            column: 2,
+            card_index: Some(0),
+        };
+        assert_eq!(cycle_focus(deep, true), column(3));
+        assert_eq!(cycle_focus(column(0), false), HitTarget::Foundation(3));
+    }
+
+    #[test]
+    fn test_long_columns_close_up_and_still_hit_the_right_card() {
+        let metrics = CardMetrics {
+            card_w: 70,
+            card_h: 100,
+            column_gap: 10,
+            row_gap: 16,
+            face_down_offset: 12,
+            face_up_offset: 20,
+            face_inset: 4,
+            waste_fan_offset: 15,
+            margin: 20,
+            tableau_bottom: 600,
+            slot_fill: COLORREF(0),
+            corner_indices: false,
+            left_handed: false,
+            shadow: None,
+        };
+        let cards: Vec<Card> = (0..24)
+            .map(|index| Card {
+                face_up: index >= 6,
+                ..Card::new(Suit::Spades, Rank::Ace)
+            })
+            .collect();
+
+        // A short column keeps its usual steps.
+        let short = metrics.tableau_tops(&cards[..8]);
+        assert_eq!(short[6] - short[5], 12);
+        assert_eq!(short[7] - short[6], 20);
+
+        let tops = metrics.tableau_tops(&cards);
+        assert_eq!(tops[6] - tops[5], 12);
+        let step = tops[7] - tops[6];
+        assert!(step > 0 && step < 20);
+        assert!(tops
+            .windows(2)
+            .skip(6)
+            .all(|pair| pair[1] - pair[0] == step));
+        assert!(tops[23] + metrics.card_h <= metrics.tableau_bottom);
+
+        for (index, pair) in tops.windows(2).enumerate() {
+            assert_eq!(
+                tableau_index_at(&tops, metrics.card_h, pair[0]),
+                Some(index)
+            );
+            assert_eq!(
+                tableau_index_at(&tops, metrics.card_h, pair[1] - 1),
+                Some(index)
+            );
+        }
+        let bottom = tops[23] + metrics.card_h;
+        assert_eq!(
+            tableau_index_at(&tops, metrics.card_h, bottom - 1),
+            Some(23)
+        );
+        assert_eq!(tableau_index_at(&tops, metrics.card_h, bottom), None);
+        assert_eq!(tableau_index_at(&tops, metrics.card_h, tops[0] - 1), None);
+    }
+
+    #[test]
+    fn test_left_handed_layout_mirrors_the_top_row() {
+        let metrics = CardMetrics {
+            card_w: 70,
+            card_h: 100,
+            column_gap: 10,
+            row_gap: 16,
+            face_down_offset: 12,
+            face_up_offset: 20,
+            face_inset: 4,
+            waste_fan_offset: 15,
+            margin: 20,
+            tableau_bottom: 600,
+            slot_fill: COLORREF(0),
+            corner_indices: false,
+            left_handed: true,
+            shadow: None,
+        };
+        let right = CardMetrics {
+            left_handed: false,
+            ..metrics
+        };
+        assert_eq!(right.stock_x(), metrics.column_x(0));
+        assert_eq!(metrics.stock_x(), metrics.column_x(6));
+        assert_eq!(right.foundation_x(0), metrics.column_x(3));
+        assert_eq!(metrics.foundation_x(0), metrics.column_x(0));
+        assert_eq!(metrics.foundation_x(3), metrics.column_x(3));
+
+        // The playable waste card sits squarely in its column and the fan stays clear of the
+        // foundations.
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 5)
+            .unwrap();
+        game.stock_click();
+        assert_eq!(waste_top_x(&game, &right), right.column_x(1) + 30);
+        assert_eq!(waste_top_x(&game, &metrics), metrics.column_x(5));
+        assert!(metrics.waste_x(2) > metrics.foundation_x(3) + metrics.card_w);
+
+        let top = |column: usize| HitTarget::Tableau {
+            column,
+            card_index: Some(column),
+        };
+        assert_eq!(
+            next_focus(&game, HitTarget::Stock, FocusMove::Left, true),
+            HitTarget::Waste
+        );
+        assert_eq!(
+            next_focus(&game, HitTarget::Waste, FocusMove::Left, true),
+            HitTarget::Foundation(3)
+        );
+        assert_eq!(
+            next_focus(&game, HitTarget::Stock, FocusMove::Right, true),
+            HitTarget::Stock
+        );
+        assert_eq!(
+            next_focus(&game, HitTarget::Stock, FocusMove::Down, true),
+            top(6)
+        );
+        assert_eq!(
+            next_focus(&game, top(4), FocusMove::Up, true),
+            HitTarget::Waste
+        );
+        assert_eq!(
+            next_focus(&game, top(0), FocusMove::Up, true),
+            HitTarget::Foundation(0)
+        );
+    }
+
+    #[test]
+    fn test_number_keys_only_send_top_cards() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        let focus_column = |column: usize| {
+            Some(HitTarget::Tableau {
+                column,
+                card_index: Some(0),
+            })
+        };
+
+        assert_eq!(
+            foundation_key_source(&game, None, focus_column(3)),
+            Some(Selection::Tableau {
+                column: 3,
+                index: 3
+            })
+        );
+        // The waste is empty until the first draw.
+        assert_eq!(
+            foundation_key_source(&game, None, Some(HitTarget::Waste)),
+            None
+        );
+        assert_eq!(
+            foundation_key_source(&game, None, Some(HitTarget::Foundation(0))),
+            None
+        );
+        // A selection wins over focus, but a buried card never qualifies.
+        let buried = Some(Selection::Tableau {
+            column: 6,
+            index: 2,
+        });
+        assert_eq!(foundation_key_source(&game, buried, focus_column(3)), None);
+    }
+
+    #[test]
+    fn test_column_keys_take_the_focused_run() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        let focus = |column: usize, index: usize| {
+            Some(HitTarget::Tableau {
+                column,
+                card_index: Some(index),
+            })
+        };
+
+        assert_eq!(
+            column_key_source(&game, None, focus(3, 3)),
+            Some(Selection::Tableau {
+                column: 3,
+                index: 3
+            })
+        );
+        // Face-down cards, an empty waste and an empty foundation give nothing to move.
+        assert_eq!(column_key_source(&game, None, focus(3, 0)), None);
+        assert_eq!(column_key_source(&game, None, Some(HitTarget::Waste)), None);
+        assert_eq!(
+            column_key_source(&game, None, Some(HitTarget::Foundation(0))),
+            None
+        );
+        // A pickup wins over the focus.
+        assert_eq!(
+            column_key_source(&game, Some(Selection::Waste), focus(3, 3)),
+            Some(Selection::Waste)
+        );
+    }
+
+    #[test]
+    fn test_wheel_draws_once_per_notch() {
+        let mut accum = 0;
+        assert_eq!(wheel_notches(&mut accum, -120), 1);
+        assert_eq!(wheel_notches(&mut accum, -240), 2);
+        assert_eq!(wheel_notches(&mut accum, -40), 0);
+        assert_eq!(wheel_notches(&mut accum, -40), 0);
+        assert_eq!(wheel_notches(&mut accum, -40), 1);
+        assert_eq!(wheel_notches(&mut accum, -60), 0);
+        assert_eq!(wheel_notches(&mut accum, 120), 0);
+        assert_eq!(wheel_notches(&mut accum, -60), 0);
+    }
+
+    #[test]
+    fn test_win_summary_reports_time_moves_score_and_seed() {
+        let mut game = GameState::new();
+        game.set_scoring_mode(ScoringMode::Vegas, false);
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 0x2A)
+            .unwrap();
+        game.moves = 97;
+        game.score = -12;
+        assert_eq!(
+            format_win_summary(&game, Duration::from_secs(125)),
+            "Time: 02:05\nMoves: 97\nScore: -$12\nSeed: 0x2A"
+        );
+    }
+
+    #[test]
+    fn test_score_overlay_shows_draw_score_moves_and_time() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 9)
+            .unwrap();
+        game.moves = 14;
+        game.score = 35;
+        assert_eq!(
+            score_overlay_text(&game, Duration::from_secs(61)),
+            "Draw 3   Score: 35   Moves: 14   Time: 01:01"
+        );
+        let settings = Settings {
+            score_overlay: true,
+            ..Settings::default()
+        };
+        assert!(!status_bar_shown(&settings));
+        assert!(status_bar_shown(&Settings::default()));
+    }
+
+    #[test]
+    fn test_foundation_watermark_follows_the_top_suit() {
+        let mut pile = Pile::default();
+        assert_eq!(foundation_watermark(&pile), "A");
+        pile.cards.push(Card::new(Suit::Hearts, Rank::Ace));
+        assert_eq!(foundation_watermark(&pile), "\u{2665}");
+    }
+
+    #[test]
+    fn test_corner_index_labels() {
+        assert_eq!(
+            corner_index(&Card::new(Suit::Hearts, Rank::Ace)),
+            "A\u{2665}"
+        );
+        assert_eq!(
+            corner_index(&Card::new(Suit::Clubs, Rank::Ten)),
+            "10\u{2663}"
+        );
+        assert_eq!(
+            corner_index(&Card::new(Suit::Spades, Rank::King)),
+            "K\u{2660}"
+        );
+    }
+
+    #[test]
+    fn test_sheet_map_says_whether_there_is_a_back_row() {
+        assert!(!sheet_has_back(Some(include_bytes!("../res/cards.json"))));
+        let with_back = br#"{"cols": 13, "rows": 5, "card_w": 71, "card_h": 96,
+            "order": ["spades", "hearts", "diamonds", "clubs"], "back_row": 4}"#;
+        assert!(sheet_has_back(Some(with_back)));
+        assert!(!sheet_has_back(Some(b"not json")));
+        assert!(!sheet_has_back(None));
+    }
+
+    #[test]
+    fn test_scale_for_dpi_keeps_physical_size() {
+        assert_eq!(scale_for_dpi(800, 96, 144), 1200);
+        assert_eq!(scale_for_dpi(1200, 144, 96), 800);
+        assert_eq!(scale_for_dpi(101, 144, 96), 67);
+        assert_eq!(scale_for_dpi(640, 120, 120), 640);
+        assert_eq!(scale_for_dpi(640, 0, 144), 640);
+    }
+
+    #[test]
+    fn test_saved_bounds_stay_on_their_own_monitor() {
+        let secondary = RECT {
+            left: 1920,
+            top: 0,
+            right: 3840,
+            bottom: 1040,
+        };
+        let clamp = |left: i32, top: i32, width: i32, height: i32| {
+            let mut rect = RECT {
+                left,
+                top,
+                right: left + width,
+                bottom: top + height,
+            };
+            let (mut width, mut height) = (width, height);
+            clamp_rect_to(&mut rect, &mut width, &mut height, secondary);
+            (rect.left, rect.top, width, height)
+        };
+
+        // Already inside the secondary monitor: left exactly where it was saved.
+        assert_eq!(clamp(2200, 100, 1000, 700), (2200, 100, 1000, 700));
+        // Hanging off its right and bottom edges: slid back in, not onto the primary.
+        assert_eq!(clamp(3400, 600, 1000, 700), (2840, 340, 1000, 700));
+        // Bigger than the work area: shrunk to fit it.
+        assert_eq!(clamp(1800, -50, 2400, 1400), (1920, 0, 1920, 1040));
+    }
+
+    #[test]
+    fn test_hover_names_only_face_up_cards() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        let top = *game.tableau_card(6, 6).unwrap();
+        let target = |card_index| HitTarget::Tableau {
+            column: 6,
+            card_index: Some(card_index),
+        };
+        assert_eq!(hover_card(&game, target(6)), Some(top));
+        assert_eq!(hover_card(&game, target(2)), None);
+        assert_eq!(hover_card(&game, HitTarget::Waste), None);
+        assert_eq!(hover_card(&game, HitTarget::Stock), None);
+        assert_eq!(
+            card_name(&Card::new(Suit::Hearts, Rank::Queen)),
+            "Queen of Hearts"
+        );
+    }
+
+    #[test]
+    fn test_announcements_name_focus_and_moves() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        let hidden = Card {
+            face_up: false,
+            ..Card::new(Suit::Clubs, Rank::Three)
+        };
+        let mut ace = Card::new(Suit::Hearts, Rank::Ace);
+        ace.face_up = true;
+        game.tableaus[0].cards = vec![hidden, ace];
+        let focus = |card_index| HitTarget::Tableau {
+            column: 0,
+            card_index,
+        };
+        assert_eq!(
+            describe_focus(&game, focus(Some(1))).as_deref(),
+            Some("Focus: Ace of Hearts, column 1")
+        );
+        assert_eq!(
+            describe_focus(&game, focus(Some(0))).as_deref(),
+            Some("Focus: face-down card, column 1")
+        );
+        assert_eq!(
+            describe_focus(&game, HitTarget::Foundation(2)).as_deref(),
+            Some("Focus: foundation 3, empty")
+        );
+
+        let before = game.clone();
+        assert!(game.move_tableau_top_to_any_foundation(0));
+        assert_eq!(
+            describe_moves(&before, &game).as_deref(),
+            Some("Moved Ace of Hearts to foundation. Turned up Three of Clubs")
+        );
+        let before = game.clone();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 6).unwrap();
+        assert_eq!(describe_moves(&before, &game), None);
+    }
+
+    #[test]
+    fn test_stock_thins_as_it_is_drawn() {
+        assert_eq!(stock_layers(24), 3);
+        assert_eq!(stock_layers(16), 2);
+        assert_eq!(stock_layers(15), 1);
+        assert_eq!(stock_layers(1), 1);
+    }
+
+    #[test]
+    fn test_difficulty_follows_solver_effort() {
+        let rate = Difficulty::rate;
+        assert_eq!(rate(SolveResult::Winnable, 300), Difficulty::Easy);
+        assert_eq!(
+            rate(SolveResult::Winnable, EASY_MAX_NODES),
+            Difficulty::Easy
+        );
+        assert_eq!(rate(SolveResult::Winnable, 20_000), Difficulty::Medium);
+        assert_eq!(rate(SolveResult::Winnable, 900_000), Difficulty::Hard);
+        assert_eq!(rate(SolveResult::Unwinnable, 50), Difficulty::Unwinnable);
+        assert_eq!(rate(SolveResult::Timeout, 3_000_000), Difficulty::Unknown);
+        for (index, rating) in Difficulty::ALL.into_iter().enumerate() {
+            assert_eq!(rating as usize, index);
+        }
+    }
+
+    #[test]
+    fn test_discard_prompt_only_guards_games_under_way() {
+        let mut settings = Settings::default();
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 9).unwrap();
+        assert!(!discard_needs_confirmation(&game, &settings, false));
+
+        game.stock_click();
+        assert!(game.moves > 0);
+        assert!(discard_needs_confirmation(&game, &settings, false));
+        assert!(!discard_needs_confirmation(&game, &settings, true));
+        settings.confirm_discard = false;
+        assert!(!discard_needs_confirmation(&game, &settings, false));
+    }
+
+    #[test]
+    fn test_status_parts_make_room_for_the_seed() {
+        let mut settings = Settings {
+            announce: false,
+            show_seed: false,
+            ..Settings::default()
+        };
+        assert_eq!(status_part_edges(900, 170, 180, 200, &settings), [730, -1]);
+        assert_eq!(seed_part(&settings), None);
+        assert_eq!(winnable_part(&settings), 1);
+
+        settings.show_seed = true;
+        assert_eq!(
+            status_part_edges(900, 170, 180, 200, &settings),
+            [550, 730, -1]
+        );
+        assert_eq!(seed_part(&settings), Some(1));
+        assert_eq!(winnable_part(&settings), 2);
+
+        settings.announce = true;
+        assert_eq!(
+            status_part_edges(900, 170, 180, 200, &settings),
+            [250, 550, 730, -1]
+        );
+        assert_eq!(seed_part(&settings), Some(2));
+        assert_eq!(winnable_part(&settings), 3);
+
+        // A narrow bar squeezes the announcements rather than the score.
+        assert_eq!(
+            status_part_edges(640, 170, 180, 280, &settings),
+            [280, 290, 470, -1]
+        );
+        assert_eq!(
+            status_part_edges(500, 170, 180, 280, &settings),
+            [280, 280, 330, -1]
+        );
+        settings.show_seed = false;
+        assert_eq!(
+            status_part_edges(500, 170, 180, 280, &settings),
+            [280, 330, -1]
+        );
+    }
+
+    #[test]
+    fn test_card_size_caps_growth_but_never_overflows_the_window() {
+        // A roomy window: each preset stops at its own cap, scaled for DPI.
+        assert_eq!(layout_scale(3.0, 2.5, 1.0, CardSize::Small), 1.0);
+        assert_eq!(layout_scale(3.0, 2.5, 1.0, CardSize::Medium), 1.5);
+        assert_eq!(layout_scale(3.0, 2.5, 1.0, CardSize::Large), 2.5);
+        assert_eq!(layout_scale(3.0, 2.5, 2.0, CardSize::Small), 2.0);
+        // A cramped window: every preset shrinks to fit the columns.
+        for size in [CardSize::Small, CardSize::Medium, CardSize::Large] {
+            assert_eq!(layout_scale(0.6, 0.8, 1.0, size), 0.6);
+            assert_eq!(layout_scale(0.1, 0.8, 1.0, size), MIN_LAYOUT_SCALE);
+        }
+    }
+
+    #[test]
+    fn test_undoing_a_foundation_drag_keeps_every_card() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 5).unwrap();
+        // Stand an ace from the stock on the first foundation so there is one to drag down.
+        let at = game
+            .stock
+            .cards
+            .iter()
+            .position(|card| card.rank == Rank::Ace)
+            .expect("the deal has an ace in the stock");
+        let card = game.stock.cards.remove(at);
+        game.foundations[0].cards.push(card);
+        let mut history = UndoHistory::default();
+
+        let (lifted, snapshot) = lift_top_card(&mut game, DragSource::Foundation(0)).unwrap();
+        assert_eq!(lifted, card);
+        game.tableaus[3].cards.push(lifted);
+        history.push(snapshot);
+
+        assert!(history.undo(&mut game));
+        assert_eq!(game.foundations[0].cards.last(), Some(&card));
+        game.validate().unwrap();
+    }
+
+    #[test]
+    fn test_winnable_check_gives_way_to_a_newer_one() {
+        // Everything is home but the black kings, one on each of two piles.
+        let mut piles: [(Vec<u8>, usize); 7] = std::array::from_fn(|_| (Vec::new(), 0));
+        piles[2] = (vec![12], 0);
+        piles[5] = (vec![51], 0);
+        let position = solver::Position {
+            piles,
+            foundations: [11, 12, 12, 11],
+            waste: Vec::new(),
+            stock: Vec::new(),
+            draw: 1,
+            recycles: None,
+            any_to_empty: false,
+        };
+        let budget = Duration::from_secs(1);
+        let latest = AtomicU32::new(3);
+        let ticket = Ticket {
+            generation: 3,
+            latest: &latest,
+        };
+        assert_eq!(check_winnable(&position, &ticket, budget), Some(true));
+        latest.store(4, Ordering::Relaxed);
+        assert_eq!(check_winnable(&position, &ticket, budget), None);
+    }
+
+    #[test]
+    fn test_rewind_stops_at_the_latest_winnable_position() {
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 10).unwrap();
+        game.stock_click();
+        let mut snapshots = VecDeque::from([game.clone()]);
+        // New Game keeps the last game for undo; Go Back must not return to it.
+        game.deal_new_game_with_seed(DrawMode::DrawOne, 11).unwrap();
+        for _ in 0..5 {
+            snapshots.push_back(game.clone());
+            game.stock_click();
+        }
+        let candidates = rewind_candidates(&game, &snapshots);
+        assert_eq!(candidates.len(), 6);
+        assert!(candidates
+            .iter()
+            .all(|position| position.rng_seed == game.rng_seed));
+        assert_eq!(candidates.last().map(|position| position.moves), Some(0));
+
+        // Positions with at most two stock draws count as winnable.
+        let mut asked = 0;
+        let steps = last_winnable_rewind(&candidates, |position| {
+            asked += 1;
+            Some(position.waste_count() <= 2)
+        });
+        assert_eq!(steps, Some(Some(3)));
+        assert_eq!(asked, 4);
+        assert_eq!(
+            last_winnable_rewind(&candidates, |_| Some(true)),
+            Some(Some(0))
+        );
+        assert_eq!(
+            last_winnable_rewind(&candidates, |_| Some(false)),
+            Some(None)
+        );
+        assert_eq!(last_winnable_rewind(&candidates, |_| None), None);
+    }
+
+    #[test]
+    fn test_move_tween_travels_from_source_to_destination() {
+        let start = Instant::now();
+        let tween = MoveTween {
+            card: Card::new(engine::Suit::Hearts, Rank::Ace),
+            from: (10, 100),
+            to: (110, 0),
+            start,
+        };
+        assert_eq!(tween.position(start), (10, 100));
+        let midway = tween.position(start + TWEEN_DURATION / 2);
+        assert!(midway.0 > 10 && midway.0 < 110);
+        assert!(midway.1 > 0 && midway.1 < 100);
+        assert!(!tween.finished(start + TWEEN_DURATION / 2));
+        assert_eq!(tween.position(start + TWEEN_DURATION), (110, 0));
+        assert!(tween.finished(start + TWEEN_DURATION));
+    }
+
+    #[test]
+    fn test_recycle_sweeps_the_waste_fan_onto_the_stock() {
+        let metrics = CardMetrics {
+            card_w: 70,
+            card_h: 100,
+            column_gap: 10,
+            row_gap: 16,
+            face_down_offset: 12,
+            face_up_offset: 20,
+            face_inset: 4,
+            waste_fan_offset: 15,
+            margin: 20,
+            tableau_bottom: 600,
+            slot_fill: COLORREF(0),
+            corner_indices: false,
+            left_handed: false,
+            shadow: None,
+        };
+        let mut game = GameState::new();
+        game.deal_new_game_with_seed(DrawMode::DrawThree, 7)
+            .unwrap();
+        while game.stock_count() > 0 {
+            game.stock_click();
+        }
+        let before = game.clone();
+        assert!(matches!(game.stock_click(), StockAction::Recycled(_)));
+
+        let start = Instant::now();
+        let tweens = recycle_tweens(&before, &metrics, start);
+        assert_eq!(tweens.len(), WASTE_FAN_CARDS);
+        let top = before.waste.cards.last().unwrap();
+        assert_eq!(tweens[0].card.sprite_index, top.sprite_index);
+        assert_eq!(tweens[0].from, (metrics.waste_x(0) + 30, 20));
+        assert!(tweens.iter().all(|tween| !tween.card.face_up));
+        assert!(tweens
+            .iter()
+            .all(|tween| tween.to == (metrics.stock_x(), metrics.top_y())));
+        assert_eq!(tweens[2].start, start + RECYCLE_STAGGER * 2);
+        assert!(recycle_tweens(&game, &metrics, start).is_empty());
+    }
+
+    #[test]
+    fn test_flip_animation_shows_back_then_face() {
+        let start = Instant::now();
+        let flip = FlipAnimation {
+            sprite_index: 0,
+            start,
+        };
+        assert_eq!(flip.phase(start), (1.0, false));
+        let (width, face) = flip.phase(start + FLIP_DURATION / 4);
+        assert!(!face && width > 0.0 && width < 1.0);
+        let (width, face) = flip.phase(start + FLIP_DURATION * 3 / 4);
+        assert!(face && width > 0.0 && width < 1.0);
+        assert_eq!(flip.phase(start + FLIP_DURATION), (1.0, true));
+        assert!(flip.finished(start + FLIP_DURATION));
+    }
+
+    #[test]
+    fn test_deal_animation_lands_cards_in_deal_order() {
+        let mut anim = DealAnimation::new();
+        assert_eq!(anim.cards.len(), 28);
+        let mut now = anim.last_tick;
+        let step = Duration::from_millis(15);
+        now += step;
+        assert!(!anim.tick(now));
+        assert!(!anim.landed(0, 0));
+
+        let mut ticks = 0;
+        while !anim.tick(now) {
+            now += step;
+            ticks += 1;
+            assert!(ticks < 1000, "deal animation never finished");
+            if anim.landed(1, 0) {
+                assert!(anim.landed(0, 0));
+            }
+        }
+        assert!((0..TABLEAU_COLUMNS).all(|column| anim.landed(column, column)));
+    }
+}
+
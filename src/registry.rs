@@ -0,0 +1,93 @@
+// Small helpers for the per-user values stored under REGISTRY_BASE_KEY.
+
+use windows::core::PCWSTR;
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegCreateKeyExW, RegOpenKeyExW, RegQueryValueExW, RegSetValueExW, HKEY,
+    HKEY_CURRENT_USER, KEY_QUERY_VALUE, KEY_READ, KEY_SET_VALUE, REG_BINARY,
+    REG_OPTION_NON_VOLATILE,
+};
+
+use crate::constants;
+use crate::to_wide;
+
+/// Reads a `REG_BINARY` value; `None` if the key or value is missing or of another type.
+pub fn read_binary(name: &str) -> Option<Vec<u8>> {
+    unsafe {
+        let subkey = to_wide(constants::REGISTRY_BASE_KEY);
+        let mut hkey = HKEY::default();
+        if RegOpenKeyExW(
+            HKEY_CURRENT_USER,
+            PCWSTR(subkey.as_ptr()),
+            0,
+            KEY_READ,
+            &mut hkey,
+        )
+        .is_err()
+        {
+            return None;
+        }
+
+        let value_name = to_wide(name);
+        let mut value_type = REG_BINARY;
+        let mut data_size = 0u32;
+        let mut data = Vec::new();
+        let mut status = RegQueryValueExW(
+            hkey,
+            PCWSTR(value_name.as_ptr()),
+            None,
+            Some(&mut value_type),
+            None,
+            Some(&mut data_size),
+        );
+        if status.is_ok() {
+            data.resize(data_size as usize, 0);
+            status = RegQueryValueExW(
+                hkey,
+                PCWSTR(value_name.as_ptr()),
+                None,
+                Some(&mut value_type),
+                Some(data.as_mut_ptr()),
+                Some(&mut data_size),
+            );
+        }
+        let _ = RegCloseKey(hkey);
+
+        if status.is_err() || value_type != REG_BINARY {
+            return None;
+        }
+        data.truncate(data_size as usize);
+        Some(data)
+    }
+}
+
+pub fn write_binary(name: &str, bytes: &[u8]) {
+    unsafe {
+        let subkey = to_wide(constants::REGISTRY_BASE_KEY);
+        let mut hkey = HKEY::default();
+        if RegCreateKeyExW(
+            HKEY_CURRENT_USER,
+            PCWSTR(subkey.as_ptr()),
+            0,
+            None,
+            REG_OPTION_NON_VOLATILE,
+            KEY_SET_VALUE | KEY_QUERY_VALUE,
+            None,
+            &mut hkey,
+            None,
+        )
+        .is_err()
+        {
+            return;
+        }
+
+        let value_name = to_wide(name);
+        let _ = RegSetValueExW(
+            hkey,
+            PCWSTR(value_name.as_ptr()),
+            0,
+            REG_BINARY,
+            Some(bytes),
+        );
+        let _ = RegCloseKey(hkey);
+    }
+}
@@ -0,0 +1,297 @@
+// Player preferences, stored as one JSON blob so a partial write can never mix old and new.
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{DrawMode, ScoringMode};
+use crate::registry;
+
+const SETTINGS_VALUE: &str = "Settings";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeltColor {
+    #[default]
+    Green,
+    Blue,
+    Burgundy,
+    Charcoal,
+}
+
+/// Procedurally drawn designs for face-down cards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CardBack {
+    #[default]
+    ClassicBlue,
+    RedDiamond,
+    GreenLattice,
+    Plain,
+}
+
+/// How large the cards may grow. Every preset still shrinks to keep all seven columns in
+/// the window; the smaller ones stop growing early and leave more felt around the table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CardSize {
+    Small,
+    Medium,
+    #[default]
+    Large,
+}
+
+impl CardSize {
+    /// Largest card scale, relative to the sprite sheet at 96 DPI.
+    pub fn max_scale(self) -> f32 {
+        match self {
+            CardSize::Small => 1.0,
+            CardSize::Medium => 1.5,
+            CardSize::Large => 4.0,
+        }
+    }
+}
+
+/// Feel of the modern victory animation: how hard the cards are thrown, how heavily they
+/// fall and how soon the next one follows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VictoryPhysics {
+    Calm,
+    #[default]
+    Classic,
+    Chaotic,
+}
+
+impl VictoryPhysics {
+    pub fn gravity_scale(self) -> f32 {
+        match self {
+            VictoryPhysics::Calm => 0.6,
+            VictoryPhysics::Classic => 1.0,
+            VictoryPhysics::Chaotic => 1.4,
+        }
+    }
+
+    /// Multiplier on each card's launch velocity.
+    pub fn launch_scale(self) -> f32 {
+        match self {
+            VictoryPhysics::Calm => 0.75,
+            VictoryPhysics::Classic => 1.0,
+            VictoryPhysics::Chaotic => 1.3,
+        }
+    }
+
+    /// Multiplier on the pause between one card leaving its foundation and the next.
+    pub fn emit_interval_scale(self) -> f32 {
+        match self {
+            VictoryPhysics::Calm => 1.5,
+            VictoryPhysics::Classic => 1.0,
+            VictoryPhysics::Chaotic => 0.5,
+        }
+    }
+}
+
+/// Colours (as RGB) and line weight of the keyboard focus, click selection and drag target
+/// outlines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutlineStyle {
+    pub focus: [u8; 3],
+    pub selection: [u8; 3],
+    pub drag_legal: [u8; 3],
+    pub drag_illegal: [u8; 3],
+    pub thickness: i32,
+}
+
+impl OutlineStyle {
+    pub const STANDARD: OutlineStyle = OutlineStyle {
+        focus: [255, 215, 0],
+        selection: [255, 204, 48],
+        drag_legal: [72, 220, 96],
+        drag_illegal: [230, 60, 60],
+        thickness: 3,
+    };
+
+    /// Saturated colours at double weight, easy to pick out against any felt.
+    pub const HIGH_CONTRAST: OutlineStyle = OutlineStyle {
+        focus: [255, 255, 0],
+        selection: [0, 255, 255],
+        drag_legal: [255, 255, 255],
+        drag_illegal: [255, 0, 0],
+        thickness: 6,
+    };
+}
+
+impl Default for OutlineStyle {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Fly the cards out from the stock at the start of each deal, and sweep the waste back
+    /// onto it when it is turned over.
+    pub animate_deal: bool,
+    /// Turn newly exposed tableau cards over with a short flip instead of instantly.
+    pub animate_flips: bool,
+    /// Play the card sound effects; off unless the player opts in.
+    pub sound: bool,
+    /// Outline every pile a click-selected card could be moved to.
+    pub highlight_moves: bool,
+    pub felt: FeltColor,
+    pub card_back: CardBack,
+    pub card_size: CardSize,
+    /// Draw mode for the first deal of a session; later deals keep whatever is in play.
+    pub draw_mode: DrawMode,
+    /// Scoring rules for fresh deals; a resumed game keeps the rules it was started with.
+    pub scoring_mode: ScoringMode,
+    pub vegas_cumulative: bool,
+    pub pass_limit: Option<u32>,
+    /// Turn up a card as soon as a move uncovers it; when off the player clicks it over.
+    pub auto_flip: bool,
+    /// Easy variant for fresh deals: an empty column takes any card, not only a king.
+    pub allow_any_to_empty: bool,
+    pub outlines: OutlineStyle,
+    /// Rank and suit overlay on card faces; `None` follows whether the sprite sheet loaded.
+    pub corner_indices: Option<bool>,
+    /// Mirror the top row: stock and waste on the right, foundations on the left.
+    pub left_handed: bool,
+    /// Report focus changes and moves to screen readers through the status bar.
+    pub announce: bool,
+    /// Soft shadow under every card; costs a second fill per card drawn.
+    pub card_shadows: bool,
+    /// Offer Go Back to Last Winnable, which runs the solver over the whole undo history.
+    pub rewind_assist: bool,
+    /// Give the deal's seed its own field at the end of the status bar.
+    pub show_seed: bool,
+    /// Ask before a new deal, restart or load throws away a game that is under way.
+    pub confirm_discard: bool,
+    /// Forgiving variant: when play is stuck, a stock click draws on to the next playable
+    /// card, or offers to reshuffle the stock and waste once nothing is left to find.
+    pub reshuffle_assist: bool,
+    /// Show how many cards are left in the stock on a badge over it.
+    pub stock_count_badge: bool,
+    /// Keep the window above every other one, for playing alongside other work.
+    pub always_on_top: bool,
+    /// Hide the status bar and give its strip to the table.
+    pub compact: bool,
+    /// Hide the status bar too, but paint the draw mode, score, moves and time on the felt
+    /// along the bottom edge.
+    pub score_overlay: bool,
+    pub victory_physics: VictoryPhysics,
+    /// Bounce the cards off the foundations after a win; when off the win summary opens
+    /// straight away.
+    pub victory_animation: bool,
+    /// Holding Shift over the stock shows faint copies of the cards the next click would
+    /// turn up. Off by default, since it makes the game easier.
+    pub stock_preview: bool,
+    /// Deal the next game a moment after a win's celebration instead of opening the win
+    /// summary; a key press or click in the meantime stays on the won table.
+    pub auto_deal: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            animate_deal: true,
+            animate_flips: true,
+            sound: false,
+            highlight_moves: true,
+            felt: FeltColor::Green,
+            card_back: CardBack::ClassicBlue,
+            card_size: CardSize::Large,
+            draw_mode: DrawMode::DrawOne,
+            scoring_mode: ScoringMode::Standard,
+            vegas_cumulative: false,
+            pass_limit: None,
+            auto_flip: true,
+            allow_any_to_empty: false,
+            outlines: OutlineStyle::STANDARD,
+            corner_indices: None,
+            left_handed: false,
+            announce: true,
+            card_shadows: true,
+            rewind_assist: false,
+            show_seed: false,
+            confirm_discard: true,
+            reshuffle_assist: false,
+            stock_count_badge: true,
+            always_on_top: false,
+            compact: false,
+            score_overlay: false,
+            victory_physics: VictoryPhysics::Classic,
+            victory_animation: true,
+            stock_preview: false,
+            auto_deal: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Without card artwork the faces are blank, so the overlay defaults on.
+    pub fn corner_indices_enabled(&self, has_card_images: bool) -> bool {
+        self.corner_indices.unwrap_or(!has_card_images)
+    }
+
+    /// Unknown or unreadable data falls back to the defaults; missing fields keep theirs.
+    fn from_bytes(bytes: &[u8]) -> Settings {
+        serde_json::from_slice(bytes).unwrap_or_default()
+    }
+}
+
+pub fn load_settings() -> Settings {
+    registry::read_binary(SETTINGS_VALUE)
+        .map(|bytes| Settings::from_bytes(&bytes))
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &Settings) {
+    if let Ok(bytes) = serde_json::to_vec(settings) {
+        registry::write_binary(SETTINGS_VALUE, &bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_tolerate_missing_and_corrupt_data() {
+        let settings = Settings {
+            animate_deal: false,
+            felt: FeltColor::Burgundy,
+            pass_limit: Some(3),
+            draw_mode: DrawMode::DrawThree,
+            ..Settings::default()
+        };
+        let bytes = serde_json::to_vec(&settings).unwrap();
+        assert_eq!(Settings::from_bytes(&bytes), settings);
+        assert_eq!(Settings::from_bytes(b"{}"), Settings::default());
+        assert_eq!(Settings::from_bytes(b"\x00garbage"), Settings::default());
+        assert_eq!(
+            Settings::from_bytes(br#"{"felt":"Plaid"}"#),
+            Settings::default()
+        );
+        assert_eq!(
+            Settings::from_bytes(br#"{"draw_mode":"DrawFive"}"#).draw_mode,
+            DrawMode::DrawOne
+        );
+    }
+
+    #[test]
+    fn test_outline_style_keeps_defaults_for_missing_fields() {
+        let settings = Settings::from_bytes(br#"{"outlines":{"thickness":6}}"#);
+        assert_eq!(
+            settings.outlines,
+            OutlineStyle {
+                thickness: 6,
+                ..OutlineStyle::STANDARD
+            }
+        );
+        let bytes = serde_json::to_vec(&Settings {
+            outlines: OutlineStyle::HIGH_CONTRAST,
+            ..Settings::default()
+        })
+        .unwrap();
+        assert_eq!(
+            Settings::from_bytes(&bytes).outlines,
+            OutlineStyle::HIGH_CONTRAST
+        );
+    }
+}
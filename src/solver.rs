@@ -1,6 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+#[cfg(any(test, feature = "solve-cli"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(any(test, feature = "solve-cli"))]
+use std::thread;
 use std::time::{Duration, Instant};
 
+#[cfg(feature = "solve-cli")]
+use anyhow::Result;
+
+#[cfg(any(test, feature = "solve-cli"))]
+use crate::engine::{self, DrawMode};
+
 /// ----- Card representation ----------------------------------------------------
 /// We represent cards as u8 in [0, 51]. Suits are 0..=3; ranks are 0..=12 (Ace=0,...,King=12).
 /// Mapping: 0..12 = Clubs A..K, 13..25 = Diamonds, 26..38 = Hearts, 39..51 = Spades.
@@ -24,13 +34,18 @@ fn colors_alternate(a: u8, b: u8) -> bool {
     is_red(suit(a)) != is_red(suit(b))
 }
 
-/// Convenience for debugging (e.g., "AH", "TC").
-#[allow(dead_code)]
-fn card_str(c: u8) -> &'static str {
-    const R: [&str; 13] = [
-        "A", "2", "3", "4", "5", "6", "7", "8", "9", "T", "J", "Q", "K",
-    ];
-    R[rank(c) as usize]
+const RANK_CHARS: [char; 13] = [
+    'A', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K',
+];
+const SUIT_CHARS: [char; 4] = ['C', 'D', 'H', 'S'];
+
+/// Convenience for debugging (e.g., "AH", "TC"); reads back through `parse_deck`.
+fn card_str(c: u8) -> String {
+    format!(
+        "{}{}",
+        RANK_CHARS[rank(c) as usize],
+        SUIT_CHARS[suit(c) as usize]
+    )
 }
 
 /// ----- Game state -------------------------------------------------------------
@@ -53,29 +68,67 @@ impl Pile {
     }
 }
 
-/// K+ compressed stock/waste representation.
+/// K+ compressed stock/waste representation: waste and stock as one list in the order the
+/// cards cycle, with the first `waste` entries currently in the waste.
 #[derive(Clone)]
 struct KPlus {
     stock: Vec<u8>,
     draw: u8,
-    phase: u8,
+    waste: usize,
+    /// Times the waste may still be turned over, or `None` when passes are unlimited.
+    recycles: Option<u32>,
 }
 
 impl KPlus {
+    /// Every card that can become the waste top without playing another stock card first.
+    /// Drawing one at a time reaches every card over unlimited passes. Drawing two or three
+    /// reaches the current waste top, each group top still ahead in this pass, every group
+    /// top of the next pass (which regroups from the start), and the last card of either
+    /// pass, where a short final group turns it up. Once no recycles are left, only this
+    /// pass's cards remain.
+    fn playable_indices(&self) -> Vec<usize> {
+        let len = self.stock.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let d = self.draw.max(1) as usize;
+        let this_pass = (self.pass_top()..len).step_by(d);
+        if self.recycles == Some(0) {
+            let mut idxs: Vec<usize> = this_pass.chain([len - 1]).collect();
+            idxs.dedup();
+            return idxs;
+        }
+        if d == 1 {
+            return (0..len).collect();
+        }
+        let next_pass = (d - 1..len).step_by(d);
+        let mut idxs: Vec<usize> = this_pass.chain(next_pass).chain([len - 1]).collect();
+        idxs.sort_unstable();
+        idxs.dedup();
+        idxs
+    }
+
+    /// The first index this pass can still turn up: the waste top, or with nothing drawn
+    /// yet, the top of the first group.
     #[inline]
-    fn playable_indices(&self) -> impl Iterator<Item = usize> + '_ {
-        let d = self.draw as usize;
-        let p = self.phase as usize;
-        (0..self.stock.len()).filter(move |&i| i % d == p)
+    fn pass_top(&self) -> usize {
+        let d = self.draw.max(1) as usize;
+        self.waste.checked_sub(1).unwrap_or(d - 1)
     }
 
+    /// Plays the card at `idx`; everything before it has been drawn into the waste. Reaching
+    /// a card this pass has already gone by uses up a recycle.
     #[inline]
     fn take_at(&mut self, idx: usize) -> u8 {
-        let c = self.stock.remove(idx);
-        if self.draw > 1 {
-            self.phase = (self.phase + self.draw - 1) % self.draw;
+        let d = self.draw.max(1) as usize;
+        let top = self.pass_top();
+        let this_pass =
+            idx + 1 == self.stock.len() || (idx >= top && (idx - top).is_multiple_of(d));
+        if !this_pass {
+            self.recycles = self.recycles.map(|left| left.saturating_sub(1));
         }
-        c
+        self.waste = idx;
+        self.stock.remove(idx)
     }
 }
 
@@ -85,65 +138,54 @@ struct State {
     piles: [Pile; 7],
     fnd: [i8; 4],
     k: KPlus,
+    /// Easy variant: any card or run may go to an empty pile, not just a king.
+    any_to_empty: bool,
 }
 
 impl State {
-    fn normalize(&mut self) {
-        loop {
-            let mut progressed = false;
-
-            for p in &mut self.piles {
-                if p.up_from == p.cards.len() && p.up_from > 0 {
-                    p.up_from -= 1;
-                    progressed = true;
-                }
+    /// Turns up any face-down tableau top, as the game does after every move.
+    fn reveal_tops(&mut self) {
+        for p in &mut self.piles {
+            if p.up_from == p.cards.len() && p.up_from > 0 {
+                p.up_from -= 1;
             }
+        }
+    }
 
-            'outer: loop {
-                for i in 0..7 {
-                    if let Some(top) = self.piles[i].top() {
-                        let s = suit(top) as usize;
-                        let need = self.fnd[s] + 1;
-                        if need as u8 == rank(top) && safe_to_foundation(top, &self.fnd) {
-                            self.piles[i].cards.pop();
-                            if self.piles[i].up_from > self.piles[i].cards.len() {
-                                self.piles[i].up_from = self.piles[i].cards.len();
-                            }
-                            self.fnd[s] += 1;
-                            progressed = true;
-                            continue 'outer;
-                        }
-                    }
-                }
-
-                let mut moved = false;
-                let playable: Vec<usize> = self.k.playable_indices().collect();
-                for idx in playable {
-                    if idx >= self.k.stock.len() {
-                        continue;
-                    }
-                    let c = self.k.stock[idx];
-                    let s = suit(c) as usize;
-                    if (self.fnd[s] + 1) as u8 == rank(c) && safe_to_foundation(c, &self.fnd) {
-                        let card = self.k.take_at(idx);
-                        debug_assert_eq!(card, c);
-                        self.fnd[s] += 1;
-                        progressed = true;
-                        moved = true;
-                        break;
-                    }
-                }
-                if moved {
-                    continue 'outer;
+    /// Plays every safe foundation move, appending each one to `forced` so a winning line
+    /// can be replayed move by move.
+    fn normalize(&mut self, forced: &mut Vec<Move>) {
+        loop {
+            self.reveal_tops();
+            match forced_move(self) {
+                Some(mv) => {
+                    apply_move(self, mv);
+                    forced.push(mv);
                 }
-                break;
+                None => break,
             }
+        }
+    }
+}
 
-            if !progressed {
-                break;
+/// Returns the first card `normalize` would send to a foundation, so callers that need an
+/// explicit move (e.g. hints) can surface it instead of having it applied silently.
+fn forced_move(s: &State) -> Option<Move> {
+    for src in 0..7 {
+        if let Some(top) = s.piles[src].top() {
+            let need = s.fnd[suit(top) as usize] + 1;
+            if need as u8 == rank(top) && safe_to_foundation(top, &s.fnd) {
+                return Some(Move::TableauToFoundation { src });
             }
         }
     }
+    s.k.playable_indices()
+        .into_iter()
+        .find(|&idx| {
+            let c = s.k.stock[idx];
+            (s.fnd[suit(c) as usize] + 1) as u8 == rank(c) && safe_to_foundation(c, &s.fnd)
+        })
+        .map(|idx_in_k| Move::WasteToFoundation { idx_in_k })
 }
 
 fn safe_to_foundation(card: u8, fnd: &[i8; 4]) -> bool {
@@ -174,8 +216,14 @@ fn is_king(c: u8) -> bool {
     rank(c) == 12
 }
 
-#[derive(Clone, Copy)]
-enum Move {
+/// Whether `c` may start an empty pile under the rules `s` is played by.
+#[inline]
+fn fits_empty(s: &State, c: u8) -> bool {
+    s.any_to_empty || is_king(c)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
     TableauToFoundation {
         src: usize,
     },
@@ -222,9 +270,9 @@ fn generate_moves(s: &State) -> Vec<Move> {
         let mut valid_starts: Vec<usize> = Vec::new();
         valid_starts.push(j);
         while j > p.up_from {
-            let a = p.cards[j - 1];
-            let b = p.cards[j];
-            if can_build_onto(a, b) {
+            let below = p.cards[j - 1];
+            let above = p.cards[j];
+            if can_build_onto(above, below) {
                 j -= 1;
                 valid_starts.push(j);
             } else {
@@ -247,7 +295,8 @@ fn generate_moves(s: &State) -> Vec<Move> {
                     }
                 }
             }
-            if is_king(bottom) {
+            // Moving a whole pile to another empty one gains nothing.
+            if is_king(bottom) || (s.any_to_empty && start_idx > 0) {
                 if let Some(dst) = (0..7).find(|&i| s.piles[i].is_empty()) {
                     if dst != src {
                         moves.push(Move::TableauToTableau {
@@ -261,7 +310,7 @@ fn generate_moves(s: &State) -> Vec<Move> {
         }
     }
 
-    let k_idxs: Vec<usize> = s.k.playable_indices().collect();
+    let k_idxs = s.k.playable_indices();
     for idx in k_idxs {
         if idx >= s.k.stock.len() {
             continue;
@@ -276,7 +325,7 @@ fn generate_moves(s: &State) -> Vec<Move> {
                 if can_build_onto(c, t) {
                     moves.push(Move::WasteToTableau { idx_in_k: idx, dst });
                 }
-            } else if is_king(c) {
+            } else if fits_empty(s, c) {
                 if let Some(dst0) = (0..7).find(|&i| s.piles[i].is_empty()) {
                     if dst0 == dst {
                         moves.push(Move::WasteToTableau { idx_in_k: idx, dst });
@@ -302,7 +351,7 @@ fn generate_moves(s: &State) -> Vec<Move> {
                 if can_build_onto(c, t) {
                     moves.push(Move::FoundationToTableau { suit: su, dst });
                 }
-            } else if is_king(c) {
+            } else if fits_empty(s, c) {
                 if let Some(dst0) = (0..7).find(|&i| s.piles[i].is_empty()) {
                     if dst0 == dst {
                         moves.push(Move::FoundationToTableau { suit: su, dst });
@@ -328,6 +377,27 @@ fn generate_moves(s: &State) -> Vec<Move> {
     moves
 }
 
+/// The move that would put `st` back exactly as it was before `mv`, if there is one. A
+/// tableau move that turns up a face-down card can't be taken back that way.
+fn reverse_of(st: &State, mv: Move) -> Option<Move> {
+    match mv {
+        Move::TableauToTableau {
+            src,
+            start_idx,
+            dst,
+        } => {
+            let reveals = start_idx == st.piles[src].up_from && start_idx > 0;
+            (!reveals).then_some(Move::TableauToTableau {
+                src: dst,
+                start_idx: st.piles[dst].cards.len(),
+                dst: src,
+            })
+        }
+        Move::FoundationToTableau { dst, .. } => Some(Move::TableauToFoundation { src: dst }),
+        _ => None,
+    }
+}
+
 fn apply_move(st: &mut State, mv: Move) {
     match mv {
         Move::TableauToFoundation { src } => {
@@ -381,7 +451,14 @@ fn hash_state(s: &State) -> Key {
         mix((f as i64 as u64).wrapping_add(1));
     }
     mix(s.k.draw as u64);
-    mix(s.k.phase as u64);
+    // Drawing one at a time with unlimited passes, every card is reachable wherever the
+    // waste stands.
+    if s.k.draw > 1 || s.k.recycles.is_some() {
+        mix(s.k.waste as u64);
+    }
+    if let Some(left) = s.k.recycles {
+        mix(left as u64 + 0x5bd1e995);
+    }
     mix(s.k.stock.len() as u64);
     for &c in &s.k.stock {
         mix(c as u64 + 0x9e3779b97f4a7c15);
@@ -404,57 +481,111 @@ pub enum SolveResult {
     Timeout,
 }
 
+/// How much work a search took, for the command-line solver's report and diagnosing timeouts.
+#[cfg(any(test, feature = "solve-cli"))]
+#[derive(Clone, Copy, Debug)]
+pub struct SolveStats {
+    pub result: SolveResult,
+    /// Positions expanded, transposition-table hits included.
+    pub nodes: u64,
+    pub elapsed: Duration,
+    /// Distinct positions in the transposition table when the search stopped.
+    pub tt_size: usize,
+}
+
 struct Frame {
     state: State,
     key: Option<Key>,
+    /// Foundation moves `normalize` applied on entering this frame.
+    forced: Vec<Move>,
     moves: Vec<Move>,
     next_child: usize,
     initialized: bool,
     found_success: bool,
+    /// Move that would take back the one that led here; never worth trying.
+    reverse: Option<Move>,
 }
 
 impl Frame {
-    fn new(state: State) -> Self {
+    fn new(state: State, reverse: Option<Move>) -> Self {
         Self {
             state,
             key: None,
+            forced: Vec::new(),
             moves: Vec::new(),
             next_child: 0,
             initialized: false,
             found_success: false,
+            reverse,
         }
     }
 }
 
-fn dfs(
-    start: State,
-    tt: &mut HashMap<Key, bool>,
-    deadline: Instant,
-    node_counter: &mut u64,
-) -> Option<bool> {
-    let mut stack = vec![Frame::new(start)];
+/// Iterative DFS that can be paused after any number of expanded positions and picked up
+/// again, so a search can be spread over timer ticks or abandoned part way. The
+/// transposition table and the line being explored survive between steps.
+pub struct Solver {
+    stack: Vec<Frame>,
+    /// Positions on the line being explored. The table only learns a position's result once
+    /// its subtree is finished, so without this a longer shuffle loop would recurse forever.
+    on_path: HashSet<Key>,
+    tt: HashMap<Key, bool>,
+    nodes: u64,
+    path: Vec<Move>,
+    verdict: Option<bool>,
+}
 
-    while let Some(frame) = stack.last_mut() {
-        if frame.initialized && frame.found_success {
-            let key = frame.key.expect("initialized frames must have a key");
-            tt.insert(key, true);
-            stack.pop();
-            if let Some(parent) = stack.last_mut() {
-                parent.found_success = true;
-            } else {
-                return Some(true);
-            }
-            continue;
+impl Solver {
+    /// Starts a search from a position taken from the live game.
+    pub fn from_position(position: &Position) -> Solver {
+        Solver::from_state(position.to_state())
+    }
+
+    /// Starts a search from a fresh deal, laid out the way [`solve_deck`] takes it.
+    pub fn from_deck(deck: &[u8; 52], draw_size: u8, any_to_empty: bool) -> Solver {
+        assert!(draw_size >= 1, "draw_size must be at least 1");
+        Solver::from_state(initial_state(deck, draw_size, any_to_empty))
+    }
+
+    fn from_state(start: State) -> Solver {
+        Solver {
+            stack: vec![Frame::new(start, None)],
+            on_path: HashSet::new(),
+            tt: HashMap::with_capacity(1 << 16),
+            nodes: 0,
+            path: Vec::new(),
+            verdict: None,
         }
+    }
 
-        if !frame.initialized {
-            frame.state.normalize();
+    /// Positions expanded so far, transposition-table hits included; a position the table
+    /// already settles still counts, it just isn't searched again.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
 
-            if frame.state.fnd.iter().all(|&r| r == 12) {
-                let key = hash_state(&frame.state);
-                tt.insert(key, true);
-                stack.pop();
-                if let Some(parent) = stack.last_mut() {
+    #[cfg(any(test, feature = "solve-cli"))]
+    pub fn tt_size(&self) -> usize {
+        self.tt.len()
+    }
+
+    /// Expands up to `budget_nodes` more positions. Returns the verdict once the search has
+    /// one, and keeps returning it; `None` means there is more to do.
+    pub fn step(&mut self, budget_nodes: u64) -> Option<bool> {
+        if self.verdict.is_none() {
+            self.verdict = self.run(self.nodes.saturating_add(budget_nodes));
+        }
+        self.verdict
+    }
+
+    fn run(&mut self, stop_at: u64) -> Option<bool> {
+        while let Some(frame) = self.stack.last_mut() {
+            if frame.initialized && frame.found_success {
+                let key = frame.key.expect("initialized frames must have a key");
+                self.tt.insert(key, true);
+                self.on_path.remove(&key);
+                self.stack.pop();
+                if let Some(parent) = self.stack.last_mut() {
                     parent.found_success = true;
                 } else {
                     return Some(true);
@@ -462,69 +593,122 @@ fn dfs(
                 continue;
             }
 
-            *node_counter += 1;
-            if (*node_counter & 0x3ff) == 0 && Instant::now() >= deadline {
-                return None;
-            }
-
-            let key = hash_state(&frame.state);
-            frame.key = Some(key);
+            if !frame.initialized {
+                // Pausing before the frame is touched lets the next step start it cleanly.
+                if self.nodes >= stop_at {
+                    return None;
+                }
+                frame.state.normalize(&mut frame.forced);
 
-            if let Some(&res) = tt.get(&key) {
-                stack.pop();
-                if let Some(parent) = stack.last_mut() {
-                    if res {
+                if frame.state.fnd.iter().all(|&r| r == 12) {
+                    // Success only ever comes from a real leaf (the table starts empty and
+                    // the first win unwinds straight to the root), so the stack holds the
+                    // whole line.
+                    self.path = winning_line(&self.stack);
+                    let frame = self.stack.last().expect("leaf frame is on the stack");
+                    let key = hash_state(&frame.state);
+                    self.tt.insert(key, true);
+                    self.stack.pop();
+                    if let Some(parent) = self.stack.last_mut() {
                         parent.found_success = true;
+                    } else {
+                        return Some(true);
                     }
-                } else {
-                    return Some(res);
+                    continue;
                 }
-                continue;
-            }
 
-            frame.moves = generate_moves(&frame.state);
-            frame.next_child = 0;
-            frame.initialized = true;
+                self.nodes += 1;
 
-            if frame.moves.is_empty() {
-                tt.insert(key, false);
-                stack.pop();
-                if stack.is_empty() {
-                    return Some(false);
+                let key = hash_state(&frame.state);
+                frame.key = Some(key);
+
+                if let Some(&res) = self.tt.get(&key) {
+                    self.stack.pop();
+                    if let Some(parent) = self.stack.last_mut() {
+                        if res {
+                            parent.found_success = true;
+                        }
+                    } else {
+                        return Some(res);
+                    }
+                    continue;
+                }
+
+                if !self.on_path.insert(key) {
+                    self.stack.pop();
+                    if self.stack.is_empty() {
+                        return Some(false);
+                    }
+                    continue;
+                }
+
+                frame.moves = generate_moves(&frame.state);
+                // Forced foundation moves change the position, so the way back no longer
+                // leads to the parent.
+                if let (Some(reverse), true) = (frame.reverse, frame.forced.is_empty()) {
+                    frame.moves.retain(|&mv| mv != reverse);
+                }
+                frame.next_child = 0;
+                frame.initialized = true;
+
+                if frame.moves.is_empty() {
+                    self.tt.insert(key, false);
+                    self.on_path.remove(&key);
+                    self.stack.pop();
+                    if self.stack.is_empty() {
+                        return Some(false);
+                    }
+                    continue;
                 }
-                continue;
             }
-        }
 
-        if frame.next_child < frame.moves.len() {
-            let mv = frame.moves[frame.next_child];
-            frame.next_child += 1;
+            if frame.next_child < frame.moves.len() {
+                let mv = frame.moves[frame.next_child];
+                frame.next_child += 1;
 
-            let mut child_state = frame.state.clone();
-            apply_move(&mut child_state, mv);
-            stack.push(Frame::new(child_state));
-            continue;
-        }
+                let reverse = reverse_of(&frame.state, mv);
+                let mut child_state = frame.state.clone();
+                apply_move(&mut child_state, mv);
+                self.stack.push(Frame::new(child_state, reverse));
+                continue;
+            }
 
-        let key = frame.key.expect("initialized frames must have a key");
-        let result = frame.found_success;
-        tt.insert(key, result);
-        stack.pop();
-        if let Some(parent) = stack.last_mut() {
-            if result {
-                parent.found_success = true;
+            let key = frame.key.expect("initialized frames must have a key");
+            let result = frame.found_success;
+            self.tt.insert(key, result);
+            self.on_path.remove(&key);
+            self.stack.pop();
+            if let Some(parent) = self.stack.last_mut() {
+                if result {
+                    parent.found_success = true;
+                }
+            } else {
+                return Some(result);
             }
-        } else {
-            return Some(result);
         }
-    }
 
-    Some(false)
+        Some(false)
+    }
 }
 
-pub fn solve_deck(deck: &[u8; 52], draw_size: u8, time_budget: Duration) -> SolveResult {
-    assert!(draw_size == 1 || draw_size == 3, "draw_size must be 1 or 3");
+/// Flattens the DFS stack into the moves that lead from the root to its top frame: each
+/// frame's forced foundation moves, then the child move it is currently exploring.
+fn winning_line(stack: &[Frame]) -> Vec<Move> {
+    let mut line = Vec::new();
+    for frame in stack {
+        line.extend_from_slice(&frame.forced);
+        if let Some(mv) = frame
+            .next_child
+            .checked_sub(1)
+            .and_then(|idx| frame.moves.get(idx))
+        {
+            line.push(*mv);
+        }
+    }
+    line
+}
 
+fn initial_state(deck: &[u8; 52], draw_size: u8, any_to_empty: bool) -> State {
     let mut it = 0usize;
     let mut piles: [Pile; 7] = std::array::from_fn(|_| Pile {
         cards: Vec::new(),
@@ -539,34 +723,315 @@ pub fn solve_deck(deck: &[u8; 52], draw_size: u8, time_budget: Duration) -> Solv
     }
 
     let stock: Vec<u8> = deck[it..].to_vec();
-    let phase = if draw_size == 0 { 0 } else { draw_size - 1 };
     let k = KPlus {
         stock,
         draw: draw_size,
-        phase,
+        waste: 0,
+        recycles: None,
     };
 
-    let mut s = State {
+    State {
         piles,
         fnd: [-1; 4],
         k,
-    };
-    s.normalize();
+        any_to_empty,
+    }
+}
+
+/// Solves a fresh deal. `any_to_empty` plays the easy variant, where an empty pile takes
+/// any card instead of only a king.
+pub fn solve_deck(
+    deck: &[u8; 52],
+    draw_size: u8,
+    any_to_empty: bool,
+    time_budget: Duration,
+) -> SolveResult {
+    solve_deck_with_path(deck, draw_size, any_to_empty, time_budget).0
+}
+
+/// Like [`solve_deck`], but also reports how hard the search had to work.
+#[cfg(any(test, feature = "solve-cli"))]
+pub fn solve_deck_with_stats(
+    deck: &[u8; 52],
+    draw_size: u8,
+    any_to_empty: bool,
+    time_budget: Duration,
+) -> SolveStats {
+    assert!(draw_size >= 1, "draw_size must be at least 1");
+    let start = Instant::now();
+    let (result, solver) = search(initial_state(deck, draw_size, any_to_empty), time_budget);
+    SolveStats {
+        result,
+        nodes: solver.nodes(),
+        elapsed: start.elapsed(),
+        tt_size: solver.tt_size(),
+    }
+}
+
+/// Positions searched between deadline checks.
+const DEADLINE_CHECK_NODES: u64 = 1024;
 
+/// Steps a [`Solver`] from `s` to a verdict or until `time_budget` runs out, returning the
+/// result and the solver, which holds the winning line when the result is `Winnable`.
+fn search(s: State, time_budget: Duration) -> (SolveResult, Solver) {
     let start = Instant::now();
     let deadline = start
         .checked_add(time_budget)
         .unwrap_or_else(|| start + Duration::from_secs(5));
-    let mut tt: HashMap<Key, bool> = HashMap::with_capacity(1 << 16);
-    let mut nodes: u64 = 0;
-    match dfs(s, &mut tt, deadline, &mut nodes) {
-        Some(true) => SolveResult::Winnable,
-        Some(false) => SolveResult::Unwinnable,
-        None => SolveResult::Timeout,
+    let mut solver = Solver::from_state(s);
+    let result = loop {
+        match solver.step(DEADLINE_CHECK_NODES) {
+            Some(true) => break SolveResult::Winnable,
+            Some(false) => break SolveResult::Unwinnable,
+            None if Instant::now() >= deadline => break SolveResult::Timeout,
+            None => {}
+        }
+    };
+    (result, solver)
+}
+
+/// Like [`solve_deck`], but on `Winnable` also returns the winning line. Replaying it means
+/// applying each move in turn and turning up any exposed tableau card after each one; waste
+/// moves index into the K+ stock (remaining stock and waste in cycling order).
+pub fn solve_deck_with_path(
+    deck: &[u8; 52],
+    draw_size: u8,
+    any_to_empty: bool,
+    time_budget: Duration,
+) -> (SolveResult, Option<Vec<Move>>) {
+    assert!(draw_size >= 1, "draw_size must be at least 1");
+
+    let (result, solver) = search(initial_state(deck, draw_size, any_to_empty), time_budget);
+    match result {
+        SolveResult::Winnable => (result, Some(solver.path)),
+        _ => (result, None),
+    }
+}
+
+/// Outcome of solving a batch of random deals.
+#[cfg(any(test, feature = "solve-cli"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WinnableEstimate {
+    /// Deals actually searched; fewer than requested if the overall budget ran out.
+    pub sampled: usize,
+    pub winnable: usize,
+    pub timeouts: usize,
+}
+
+#[cfg(any(test, feature = "solve-cli"))]
+impl WinnableEstimate {
+    /// Share of the sampled deals proven winnable; timeouts count against it.
+    pub fn fraction(&self) -> f64 {
+        if self.sampled == 0 {
+            0.0
+        } else {
+            self.winnable as f64 / self.sampled as f64
+        }
+    }
+}
+
+/// Deals `samples` random games through the same seeded shuffle as play and solves them on
+/// a thread per available core, each with the usual per-deal budget. Stops handing out
+/// deals once `budget` has elapsed.
+#[cfg(feature = "solve-cli")]
+pub fn estimate_winnable_fraction(
+    draw: DrawMode,
+    any_to_empty: bool,
+    samples: usize,
+    budget: Duration,
+) -> Result<WinnableEstimate> {
+    let seeds = (0..samples)
+        .map(|_| engine::random_seed())
+        .collect::<Result<Vec<_>>>()?;
+    Ok(estimate_from_seeds(draw, any_to_empty, &seeds, budget))
+}
+
+#[cfg(any(test, feature = "solve-cli"))]
+fn estimate_from_seeds(
+    draw: DrawMode,
+    any_to_empty: bool,
+    seeds: &[u64],
+    budget: Duration,
+) -> WinnableEstimate {
+    let draw_size = draw.count();
+    let start = Instant::now();
+    let deadline = start
+        .checked_add(budget)
+        .unwrap_or_else(|| start + Duration::from_secs(60));
+    let per_deal = Duration::from_millis(engine::SOLVER_TIME_BUDGET_MS);
+    let threads = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(seeds.len())
+        .max(1);
+
+    let next = AtomicUsize::new(0);
+    let sampled = AtomicUsize::new(0);
+    let winnable = AtomicUsize::new(0);
+    let timeouts = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                let Some(&seed) = seeds.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    break;
+                };
+                let Some(deck) = engine::solver_deck_for_seed(draw, seed) else {
+                    continue;
+                };
+                let result = solve_deck(&deck, draw_size, any_to_empty, per_deal.min(remaining));
+                sampled.fetch_add(1, Ordering::Relaxed);
+                match result {
+                    SolveResult::Winnable => {
+                        winnable.fetch_add(1, Ordering::Relaxed);
+                    }
+                    SolveResult::Timeout => {
+                        timeouts.fetch_add(1, Ordering::Relaxed);
+                    }
+                    SolveResult::Unwinnable => {}
+                }
+            });
+        }
+    });
+
+    WinnableEstimate {
+        sampled: sampled.into_inner(),
+        winnable: winnable.into_inner(),
+        timeouts: timeouts.into_inner(),
     }
 }
 
-#[allow(dead_code)]
+/// A mid-game position in solver card encoding, used to search from the live game rather
+/// than from a freshly dealt deck.
+pub struct Position {
+    /// Tableau piles bottom->top, each with the index of its first face-up card.
+    pub piles: [(Vec<u8>, usize); 7],
+    /// Highest rank on each suit's foundation, or -1 when empty.
+    pub foundations: [i8; 4],
+    /// Waste pile bottom->top; the last card is the playable one.
+    pub waste: Vec<u8>,
+    /// Stock in the order the cards will be drawn.
+    pub stock: Vec<u8>,
+    pub draw: u8,
+    /// Times the waste may still be turned over, or `None` when passes are unlimited.
+    pub recycles: Option<u32>,
+    /// Easy variant: empty piles take any card, not only a king.
+    pub any_to_empty: bool,
+}
+
+impl Position {
+    fn to_state(&self) -> State {
+        let piles = std::array::from_fn(|i| {
+            let (cards, up_from) = &self.piles[i];
+            Pile {
+                cards: cards.clone(),
+                up_from: (*up_from).min(cards.len()),
+            }
+        });
+        // K+ lists cards in the order they cycle through the waste: the current waste
+        // first, then the stock in draw order.
+        let mut stock = self.waste.clone();
+        stock.extend_from_slice(&self.stock);
+        State {
+            piles,
+            fnd: self.foundations,
+            k: KPlus {
+                stock,
+                draw: self.draw.max(1),
+                waste: self.waste.len(),
+                recycles: self.recycles,
+            },
+            any_to_empty: self.any_to_empty,
+        }
+    }
+}
+
+/// Readable ASCII board for bug reports: foundation heights, the waste (playable card last)
+/// and the stock (next card first), then one line per tableau pile from the bottom up with
+/// face-down cards in brackets.
+fn dump_state(s: &State) -> String {
+    let cards = |cards: &[u8]| -> String {
+        if cards.is_empty() {
+            "-".to_string()
+        } else {
+            cards
+                .iter()
+                .map(|&c| card_str(c))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    };
+    let foundations: Vec<String> = SUIT_CHARS
+        .iter()
+        .zip(s.fnd)
+        .map(|(suit, height)| match height {
+            -1 => format!("{suit}:-"),
+            h => format!("{suit}:{}", RANK_CHARS[h as usize]),
+        })
+        .collect();
+    let mut out = format!("Foundations: {}\n", foundations.join(" "));
+    out.push_str(&format!("Waste: {}\n", cards(&s.k.stock[..s.k.waste])));
+    out.push_str(&format!("Stock: {}\n", cards(&s.k.stock[s.k.waste..])));
+    for (i, p) in s.piles.iter().enumerate() {
+        let line: Vec<String> = p
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(j, &c)| {
+                if j < p.up_from {
+                    format!("[{}]", card_str(c))
+                } else {
+                    card_str(c)
+                }
+            })
+            .collect();
+        let line = if line.is_empty() {
+            "-".to_string()
+        } else {
+            line.join(" ")
+        };
+        out.push_str(&format!("T{}: {line}\n", i + 1));
+    }
+    out
+}
+
+/// [`dump_state`] for a position taken from the game.
+pub fn dump_position(position: &Position) -> String {
+    dump_state(&position.to_state())
+}
+
+/// Solves the live game from wherever play has reached: face-down tableau cards stay hidden
+/// until uncovered, and the waste and stock cycle under the game's draw mode.
+#[cfg(test)]
+pub fn solve_state(game: &engine::GameState, time_budget: Duration) -> SolveResult {
+    search(game.to_solver_position().to_state(), time_budget).0
+}
+
+/// Returns the first move of a winning line from `position`, or `None` when the search
+/// proves the position lost or runs out of time. `idx_in_k` indices refer to the waste
+/// followed by the stock, as laid out in [`Position`].
+pub fn suggest_move(position: &Position, time_budget: Duration) -> Option<Move> {
+    let s = position.to_state();
+    if let Some(mv) = forced_move(&s) {
+        return Some(mv);
+    }
+    solve_position_with_path(position, time_budget)?
+        .first()
+        .copied()
+}
+
+/// Searches for a complete winning line from `position`; see [`solve_deck_with_path`] for
+/// how to replay it. Drawing from the stock never changes a card's `idx_in_k`, so a waste
+/// move can be reached by drawing until that index is the waste top.
+pub fn solve_position_with_path(position: &Position, time_budget: Duration) -> Option<Vec<Move>> {
+    let (result, solver) = search(position.to_state(), time_budget);
+    (result == SolveResult::Winnable).then_some(solver.path)
+}
+
+/// Reads the 52 card tokens of a deck file (`AS`, `TD`, `QH`, ...) in `solve_deck` order.
+#[cfg(feature = "solve-cli")]
 pub fn parse_deck(tokens: &[&str]) -> Option<[u8; 52]> {
     if tokens.len() != 52 {
         return None;
@@ -621,19 +1086,304 @@ mod tests {
         assert!(!safe_to_foundation(26 + 4, &f));
     }
 
+    #[test]
+    fn test_dump_lists_every_pile() {
+        let mut piles: [(Vec<u8>, usize); 7] = Default::default();
+        piles[0] = (vec![12, 13 + 11], 1);
+        piles[6] = (vec![26 + 9], 0);
+        let position = Position {
+            piles,
+            foundations: [2, -1, 0, -1],
+            waste: vec![39 + 4, 39 + 5],
+            stock: vec![13],
+            draw: 3,
+            recycles: None,
+            any_to_empty: false,
+        };
+        assert_eq!(
+            dump_position(&position),
+            "Foundations: C:3 D:- H:A S:-\n\
+             Waste: 5S 6S\n\
+             Stock: AD\n\
+             T1: [KC] QD\n\
+             T2: -\nT3: -\nT4: -\nT5: -\nT6: -\n\
+             T7: TH\n"
+        );
+    }
+
+    #[test]
+    fn test_stepped_search_matches_one_shot() {
+        let deck = engine::solver_deck_for_seed(DrawMode::DrawOne, 2).unwrap();
+        let (result, path) = solve_deck_with_path(&deck, 1, false, Duration::from_secs(10));
+        assert_ne!(result, SolveResult::Timeout);
+
+        let mut solver = Solver::from_state(initial_state(&deck, 1, false));
+        let mut steps = 0;
+        let verdict = loop {
+            steps += 1;
+            if let Some(verdict) = solver.step(64) {
+                break verdict;
+            }
+        };
+        assert!(steps > 1, "the search should have paused along the way");
+        assert_eq!(verdict, result == SolveResult::Winnable);
+        assert_eq!(solver.path, path.unwrap_or_default());
+        // A finished search keeps its verdict without doing more work.
+        let nodes = solver.nodes();
+        assert_eq!(solver.step(64), Some(verdict));
+        assert_eq!(solver.nodes(), nodes);
+    }
+
     #[test]
     fn test_kplus_indices() {
         let mut k = KPlus {
-            stock: (0..24u8).collect(),
+            stock: (0..23u8).collect(),
             draw: 3,
-            phase: 2,
+            waste: 0,
+            recycles: None,
+        };
+        assert_eq!(
+            k.playable_indices(),
+            vec![2, 5, 8, 11, 14, 17, 20, 22],
+            "group tops plus the short last group"
+        );
+        assert_eq!(k.take_at(5), 5);
+        assert_eq!(k.waste, 5);
+        // The waste top and the groups after it this pass, then next pass's regrouping.
+        assert_eq!(
+            k.playable_indices(),
+            vec![2, 4, 5, 7, 8, 10, 11, 13, 14, 16, 17, 19, 20, 21]
+        );
+
+        k.draw = 2;
+        let mut pairs = vec![1, 3];
+        pairs.extend(4..22);
+        assert_eq!(k.playable_indices(), pairs);
+
+        k.draw = 1;
+        assert_eq!(k.playable_indices(), (0..22).collect::<Vec<_>>());
+
+        // Going back to a card this pass has passed spends the last recycle, after which
+        // only the rest of the pass is left.
+        k.draw = 3;
+        k.recycles = Some(1);
+        assert_eq!(k.take_at(2), 2);
+        assert_eq!(k.recycles, Some(0));
+        assert_eq!(k.playable_indices(), vec![1, 4, 7, 10, 13, 16, 19, 20]);
+        // Playing on within the pass costs nothing.
+        assert_eq!(k.take_at(4), 6);
+        assert_eq!(k.recycles, Some(0));
+        k.draw = 1;
+        assert_eq!(k.playable_indices(), (3..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_suggest_move_from_position() {
+        // Everything is home except the black kings, which sit on two tableau piles.
+        let mut piles: [(Vec<u8>, usize); 7] = std::array::from_fn(|_| (Vec::new(), 0));
+        piles[2] = (vec![12], 0);
+        piles[5] = (vec![51], 0);
+        let position = Position {
+            piles,
+            foundations: [11, 12, 12, 11],
+            waste: Vec::new(),
+            stock: Vec::new(),
+            draw: 1,
+            recycles: None,
+            any_to_empty: false,
+        };
+        let mv = suggest_move(&position, Duration::from_millis(200));
+        assert_eq!(mv, Some(Move::TableauToFoundation { src: 2 }));
+    }
+
+    #[test]
+    fn test_solve_deck_with_path_replays_to_win() {
+        const C: u8 = 0;
+        const D: u8 = 1;
+        const H: u8 = 2;
+        const S: u8 = 3;
+        let c = |suit: u8, rank: u8| suit * 13 + rank;
+        // Tableau (ranks are 0-based) with the aces..sixes in the stock. Every pile runs
+        // high->low except the last, where 8H sits on 7C and 7H, so it has to be parked on
+        // the 9S before those two sevens can go up.
+        let piles: [&[u8]; 7] = [
+            &[c(S, 8)],
+            &[c(C, 12), c(D, 12)],
+            &[c(H, 12), c(C, 11), c(D, 11)],
+            &[c(H, 11), c(C, 10), c(D, 10), c(H, 10)],
+            &[c(C, 9), c(D, 9), c(H, 9), c(C, 8), c(D, 8)],
+            &[c(H, 8), c(C, 7), c(D, 7), c(S, 7), c(D, 6), c(S, 6)],
+            &[
+                c(S, 12),
+                c(S, 11),
+                c(S, 10),
+                c(S, 9),
+                c(H, 6),
+                c(C, 6),
+                c(H, 7),
+            ],
+        ];
+        let mut deck = [0u8; 52];
+        let mut it = 0;
+        for card in piles.iter().flat_map(|p| p.iter()) {
+            deck[it] = *card;
+            it += 1;
+        }
+        for rank in 0..6 {
+            for suit in 0..4 {
+                deck[it] = c(suit, rank);
+                it += 1;
+            }
+        }
+        assert_eq!(it, 52);
+
+        let (res, path) = solve_deck_with_path(&deck, 1, false, Duration::from_secs(2));
+        assert_eq!(res, SolveResult::Winnable);
+        let path = path.expect("winnable results carry a path");
+        assert!(path
+            .iter()
+            .any(|mv| matches!(mv, Move::TableauToTableau { .. })));
+
+        let mut st = initial_state(&deck, 1, false);
+        for mv in path {
+            assert!(generate_moves(&st).contains(&mv), "illegal move {mv:?}");
+            apply_move(&mut st, mv);
+            st.reveal_tops();
+        }
+        assert_eq!(st.fnd, [12; 4]);
+    }
+
+    #[test]
+    fn test_solve_stats_count_the_search() {
+        // A fixed xorshift shuffle gives a real deal that needs actual searching.
+        let mut deck: [u8; 52] = std::array::from_fn(|i| i as u8);
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        for i in (1..52).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            deck.swap(i, (seed % (i as u64 + 1)) as usize);
+        }
+        let budget = Duration::from_millis(200);
+        let stats = solve_deck_with_stats(&deck, 1, false, budget);
+        assert!(stats.nodes > 0);
+        // Only a search that used up its budget reports a timeout.
+        if stats.result == SolveResult::Timeout {
+            assert!(stats.elapsed >= budget);
+        }
+        // Every table entry is an expanded position, bar the one winning leaf.
+        assert!(stats.tt_size as u64 <= stats.nodes + 1);
+    }
+
+    #[test]
+    fn test_estimate_from_a_few_seeds() {
+        let estimate =
+            estimate_from_seeds(DrawMode::DrawOne, false, &[1, 2, 3], Duration::from_secs(5));
+        assert_eq!(estimate.sampled, 3);
+        assert!(estimate.winnable + estimate.timeouts <= 3);
+        assert!((0.0..=1.0).contains(&estimate.fraction()));
+
+        let expired = estimate_from_seeds(DrawMode::DrawOne, false, &[1, 2, 3], Duration::ZERO);
+        assert_eq!(expired, WinnableEstimate::default());
+    }
+
+    #[test]
+    fn test_solve_deck_draw_one_goes_round_the_stock_for_every_rank() {
+        // Sevens..kings fill the tableau, each pile running high->low towards its top, and
+        // the stock holds the sixes down to the aces in draw order. Drawing one, the aces
+        // only come up at the very end, and every later rank takes another pass.
+        let high = (6..13)
+            .rev()
+            .flat_map(|rank| (0..4).map(move |suit| suit * 13 + rank));
+        let low = (0..6)
+            .rev()
+            .flat_map(|rank| (0..4).map(move |suit| suit * 13 + rank));
+        let cards: Vec<u8> = high.chain(low).collect();
+        let deck: [u8; 52] = cards.try_into().unwrap();
+        assert_eq!(
+            solve_deck(&deck, 1, false, Duration::from_secs(2)),
+            SolveResult::Winnable
+        );
+    }
+
+    #[test]
+    fn test_draw_one_moves_whole_runs() {
+        let c = |suit: u8, rank: u8| suit * 13 + rank;
+        // The jack of hearts is face down under a king-queen run, and only moving the run
+        // as a whole to the empty pile frees it. Single-card moves alone look lost.
+        let mut piles: [(Vec<u8>, usize); 7] = std::array::from_fn(|_| (Vec::new(), 0));
+        piles[0] = (vec![c(2, 10), c(3, 12), c(2, 11)], 1);
+        piles[2] = (vec![c(2, 12), c(3, 11)], 1);
+        let position = Position {
+            piles,
+            foundations: [12, 12, 9, 10],
+            waste: Vec::new(),
+            stock: Vec::new(),
+            draw: 1,
+            recycles: None,
+            any_to_empty: false,
+        };
+        let (result, solver) = search(position.to_state(), Duration::from_secs(1));
+        assert_eq!(result, SolveResult::Winnable);
+        let path = solver.path;
+        assert!(path.contains(&Move::TableauToTableau {
+            src: 0,
+            start_idx: 1,
+            dst: 1
+        }));
+    }
+
+    #[test]
+    fn test_easy_variant_opens_empty_piles_to_any_card() {
+        let c = |suit: u8, rank: u8| suit * 13 + rank;
+        // The queen of spades covers the jack it needs to follow onto the foundation.
+        let mut piles: [(Vec<u8>, usize); 7] = std::array::from_fn(|_| (Vec::new(), 0));
+        piles[0] = (vec![c(3, 12), c(3, 10), c(3, 11)], 2);
+        let mut position = Position {
+            piles,
+            foundations: [12, 12, 12, 9],
+            waste: Vec::new(),
+            stock: Vec::new(),
+            draw: 1,
+            recycles: None,
+            any_to_empty: false,
+        };
+        let to_empty = Move::TableauToTableau {
+            src: 0,
+            start_idx: 2,
+            dst: 1,
+        };
+        assert!(!generate_moves(&position.to_state()).contains(&to_empty));
+
+        position.any_to_empty = true;
+        assert!(generate_moves(&position.to_state()).contains(&to_empty));
+        let (result, solver) = search(position.to_state(), Duration::from_secs(1));
+        assert_eq!(result, SolveResult::Winnable);
+        let path = solver.path;
+        assert_eq!(path.first(), Some(&to_empty));
+    }
+
+    #[test]
+    fn test_shuffling_a_card_back_and_forth_terminates() {
+        let c = |suit: u8, rank: u8| suit * 13 + rank;
+        // The 7H can only hop between the two black eights; the cards under them never
+        // come up, so the search has to give up rather than loop until the deadline.
+        let mut piles: [(Vec<u8>, usize); 7] = std::array::from_fn(|_| (Vec::new(), 0));
+        piles[0] = (vec![c(1, 3), c(3, 7)], 1);
+        piles[1] = (vec![c(1, 4), c(0, 7)], 1);
+        piles[2] = (vec![c(2, 6)], 0);
+        let position = Position {
+            piles,
+            foundations: [-1; 4],
+            waste: Vec::new(),
+            stock: Vec::new(),
+            draw: 1,
+            recycles: None,
+            any_to_empty: false,
         };
-        let idxs: Vec<_> = k.playable_indices().collect();
-        assert!(idxs.iter().all(|&i| i % 3 == 2));
-        let first = idxs[0];
-        let c = k.take_at(first);
-        assert_eq!(c, 2);
-        assert_eq!(k.phase, 1);
+        let (result, solver) = search(position.to_state(), Duration::from_secs(5));
+        assert_eq!(result, SolveResult::Unwinnable);
+        assert!(solver.nodes() < 16, "explored {} positions", solver.nodes());
     }
 
     #[test]
@@ -642,7 +1392,7 @@ mod tests {
         for (i, slot) in deck.iter_mut().enumerate() {
             *slot = i as u8;
         }
-        let res = solve_deck(&deck, 1, Duration::from_millis(200));
+        let res = solve_deck(&deck, 1, false, Duration::from_millis(200));
         assert!(matches!(
             res,
             SolveResult::Winnable | SolveResult::Timeout | SolveResult::Unwinnable
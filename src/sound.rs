@@ -0,0 +1,42 @@
+// Short card-game sound effects, embedded as WAVE resources in res/app.rc.
+
+use windows::Win32::Media::Audio::{PlaySoundW, SND_ASYNC, SND_NODEFAULT, SND_RESOURCE};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+
+use crate::constants;
+use crate::make_int_resource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Tableau,
+    Foundation,
+    Stock,
+    Invalid,
+    Victory,
+}
+
+impl Sound {
+    fn resource_id(self) -> u16 {
+        match self {
+            Sound::Tableau => constants::IDW_TABLEAU,
+            Sound::Foundation => constants::IDW_FOUNDATION,
+            Sound::Stock => constants::IDW_STOCK,
+            Sound::Invalid => constants::IDW_INVALID,
+            Sound::Victory => constants::IDW_VICTORY,
+        }
+    }
+}
+
+/// Starts `sound` and returns immediately; a new clip cuts off whatever was still playing.
+pub fn play(sound: Sound) {
+    unsafe {
+        let Ok(module) = GetModuleHandleW(None) else {
+            return;
+        };
+        let _ = PlaySoundW(
+            make_int_resource(sound.resource_id()),
+            module,
+            SND_RESOURCE | SND_ASYNC | SND_NODEFAULT,
+        );
+    }
+}
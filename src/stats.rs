@@ -0,0 +1,133 @@
+// Win/loss statistics, persisted as a single REG_BINARY value next to the window bounds.
+
+use std::mem::size_of;
+use std::time::Duration;
+
+use crate::registry;
+
+const STATS_VALUE: &str = "Statistics";
+/// Fields in the first version of the record; later fields read back as zero when missing.
+const BASE_FIELD_COUNT: usize = 5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub played: u32,
+    pub won: u32,
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Fastest win in whole seconds; zero until the first win.
+    pub best_time_secs: u32,
+    /// Most cards ever got onto the foundations in a game that was lost.
+    pub best_lost_foundation: u32,
+}
+
+impl Stats {
+    pub fn record_win(&mut self, elapsed: Duration) {
+        self.played += 1;
+        self.won += 1;
+        self.current_streak += 1;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        let secs = elapsed.as_secs().clamp(1, u32::MAX as u64) as u32;
+        if self.best_time_secs == 0 || secs < self.best_time_secs {
+            self.best_time_secs = secs;
+        }
+    }
+
+    /// `foundation_cards` is the most the game ever had on its foundations.
+    pub fn record_loss(&mut self, foundation_cards: u32) {
+        self.played += 1;
+        self.current_streak = 0;
+        self.best_lost_foundation = self.best_lost_foundation.max(foundation_cards);
+    }
+
+    pub fn win_percentage(&self) -> u32 {
+        if self.played == 0 {
+            0
+        } else {
+            (self.won as u64 * 100 / self.played as u64) as u32
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        [
+            self.played,
+            self.won,
+            self.current_streak,
+            self.best_streak,
+            self.best_time_secs,
+            self.best_lost_foundation,
+        ]
+        .iter()
+        .flat_map(|field| field.to_le_bytes())
+        .collect()
+    }
+
+    /// Anything shorter than the first version's record reads back as zeroed stats.
+    fn from_bytes(bytes: &[u8]) -> Stats {
+        if bytes.len() < BASE_FIELD_COUNT * size_of::<u32>() {
+            return Stats::default();
+        }
+        let mut fields = bytes
+            .chunks_exact(size_of::<u32>())
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        let mut next = || fields.next().unwrap_or(0);
+        Stats {
+            played: next(),
+            won: next(),
+            current_streak: next(),
+            best_streak: next(),
+            best_time_secs: next(),
+            best_lost_foundation: next(),
+        }
+    }
+}
+
+pub fn load_stats() -> Stats {
+    registry::read_binary(STATS_VALUE)
+        .map(|bytes| Stats::from_bytes(&bytes))
+        .unwrap_or_default()
+}
+
+pub fn save_stats(stats: &Stats) {
+    registry::write_binary(STATS_VALUE, &stats.to_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_round_trip_and_truncation() {
+        let mut stats = Stats::default();
+        stats.record_win(Duration::from_secs(300));
+        stats.record_win(Duration::from_secs(200));
+        stats.record_loss(17);
+        stats.record_loss(9);
+        stats.record_win(Duration::from_secs(250));
+        assert_eq!(
+            stats,
+            Stats {
+                played: 5,
+                won: 3,
+                current_streak: 1,
+                best_streak: 2,
+                best_time_secs: 200,
+                best_lost_foundation: 17,
+            }
+        );
+        assert_eq!(stats.win_percentage(), 60);
+
+        let bytes = stats.to_bytes();
+        assert_eq!(Stats::from_bytes(&bytes), stats);
+        assert_eq!(Stats::from_bytes(&bytes[..7]), Stats::default());
+        let old_record = Stats::from_bytes(&bytes[..BASE_FIELD_COUNT * size_of::<u32>()]);
+        assert_eq!(
+            old_record,
+            Stats {
+                best_lost_foundation: 0,
+                ..stats
+            }
+        );
+        assert_eq!(Stats::from_bytes(&[]), Stats::default());
+    }
+}
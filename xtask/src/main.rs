@@ -4,7 +4,7 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
-use image::{ImageBuffer, Rgba};
+use image::{GenericImageView, ImageBuffer, Rgba};
 use reqwest::blocking::Client;
 use serde::Serialize;
 use tiny_skia::Pixmap;
@@ -40,9 +40,34 @@ enum Cmd {
         /// Update res/app.rc to embed the output
         #[arg(long, default_value_t = true)]
         update_rc: bool,
+        /// Optional card back (SVG, PNG, JPEG or WebP) packed into an extra row below the faces
+        #[arg(long)]
+        back: Option<PathBuf>,
+        /// Pack the built-in classic blue back into the extra row instead of a file
+        #[arg(long, conflicts_with = "back")]
+        builtin_back: bool,
+    },
+    /// Check that a packed sprite sheet has the expected size and no blank cards
+    Verify {
+        /// Sprite sheet to check (default: res/cards.png)
+        #[arg(long)]
+        sheet: Option<PathBuf>,
+        /// Expected width per card in pixels
+        #[arg(long, default_value_t = 224)]
+        card_w: u32,
+        /// Expected height per card in pixels
+        #[arg(long, default_value_t = 312)]
+        card_h: u32,
     },
 }
 
+/// Sheet row order, top to bottom.
+const SUITS: [&str; 4] = ["spades", "hearts", "diamonds", "clubs"];
+/// Sheet column order, left to right.
+const RANKS: [&str; 13] = [
+    "ace", "2", "3", "4", "5", "6", "7", "8", "9", "10", "jack", "queen", "king",
+];
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     match cli.cmd {
@@ -56,6 +81,8 @@ fn main() -> Result<()> {
             out,
             source,
             update_rc,
+            back,
+            builtin_back,
         } => {
             let tmp_dir;
             let src_dir = match source {
@@ -82,14 +109,23 @@ fn main() -> Result<()> {
             };
             let source_kind = locate_card_source(&src_dir).map_err(|_| {
                 anyhow!(
-                    "Could not locate per-card SVG or PNGs under {}",
+                    "Could not locate per-card SVG or raster images under {}",
                     src_dir.display()
                 )
             })?;
             let out_path = out.unwrap_or_else(|| PathBuf::from("res/cards.png"));
+            let back = match (back, builtin_back) {
+                (Some(path), _) => Some(BackDesign::File(path)),
+                (None, true) => Some(BackDesign::Builtin),
+                (None, false) => None,
+            };
             let map = match source_kind {
-                CardSource::SvgDir(dir) => rasterize_and_pack_svg(&dir, card_w, card_h, &out_path)?,
-                CardSource::PngDir(dir) => pack_from_png(&dir, card_w, card_h, &out_path)?,
+                CardSource::SvgDir(dir) => {
+                    rasterize_and_pack_svg(&dir, card_w, card_h, back.as_ref(), &out_path)?
+                }
+                CardSource::RasterDir(dir) => {
+                    pack_from_raster(&dir, card_w, card_h, back.as_ref(), &out_path)?
+                }
             };
             if update_rc {
                 update_app_rc(&PathBuf::from("res/app.rc"), &out_path)?;
@@ -99,6 +135,68 @@ fn main() -> Result<()> {
             fs::write(&map_path, serde_json::to_vec_pretty(&map)?)?;
             println!("Sprite sheet: {}", out_path.display());
         }
+        Cmd::Verify {
+            sheet,
+            card_w,
+            card_h,
+        } => {
+            let sheet = sheet.unwrap_or_else(|| PathBuf::from("res/cards.png"));
+            verify_sheet(&sheet, card_w, card_h)?;
+            println!("Sprite sheet OK: {}", sheet.display());
+        }
+    }
+    Ok(())
+}
+
+/// Fails on a sheet whose size doesn't match the card grid or that has a fully
+/// transparent cell, naming the missing card.
+fn verify_sheet(path: &Path, card_w: u32, card_h: u32) -> Result<()> {
+    let sheet = image::open(path)
+        .with_context(|| format!("opening {}", path.display()))?
+        .to_rgba8();
+    let cols = RANKS.len() as u32;
+    let face_rows = SUITS.len() as u32;
+    let (width, height) = sheet.dimensions();
+    let has_back = (width, height) == (card_w * cols, card_h * (face_rows + 1));
+    if (width, height) != (card_w * cols, card_h * face_rows) && !has_back {
+        return Err(anyhow!(
+            "{} is {}x{}, expected {}x{} ({} columns of {}x{} cards by {} rows)",
+            path.display(),
+            width,
+            height,
+            card_w * cols,
+            card_h * face_rows,
+            cols,
+            card_w,
+            card_h,
+            face_rows
+        ));
+    }
+
+    let cell_is_blank = |col: u32, row: u32| {
+        let view = image::imageops::crop_imm(&sheet, col * card_w, row * card_h, card_w, card_h);
+        view.pixels().all(|(_, _, pixel)| pixel.0[3] == 0)
+    };
+    for (row, suit) in SUITS.iter().enumerate() {
+        for (col, rank) in RANKS.iter().enumerate() {
+            if cell_is_blank(col as u32, row as u32) {
+                return Err(anyhow!(
+                    "{}: the {} of {} (row {}, column {}) is blank",
+                    path.display(),
+                    rank,
+                    suit,
+                    row,
+                    col
+                ));
+            }
+        }
+    }
+    if has_back && cell_is_blank(0, face_rows) {
+        return Err(anyhow!(
+            "{}: the card back (row {}, column 0) is blank",
+            path.display(),
+            face_rows
+        ));
     }
     Ok(())
 }
@@ -175,15 +273,27 @@ fn extract_zip(zip_path: &Path, out_dir: &Path) -> Result<()> {
 
 enum CardSource {
     SvgDir(PathBuf),
-    PngDir(PathBuf),
+    RasterDir(PathBuf),
+}
+
+/// Raster formats accepted as card sources, in order of preference when a set mixes them:
+/// lossless PNG first, then WebP, then JPEG.
+const RASTER_EXTENSIONS: [&str; 4] = ["png", "webp", "jpg", "jpeg"];
+
+/// Preference rank of a raster card file, or `None` for anything else.
+fn raster_rank(path: &Path) -> Option<usize> {
+    let ext = path.extension()?.to_str()?;
+    RASTER_EXTENSIONS
+        .iter()
+        .position(|known| ext.eq_ignore_ascii_case(known))
 }
 
 fn locate_card_source(root: &Path) -> Result<CardSource> {
     if let Ok(svg) = find_cards_svg_dir(root) {
         return Ok(CardSource::SvgDir(svg));
     }
-    if let Ok(png) = find_cards_png_dir(root) {
-        return Ok(CardSource::PngDir(png));
+    if let Ok(raster) = find_cards_raster_dir(root) {
+        return Ok(CardSource::RasterDir(raster));
     }
     Err(anyhow!("no card source found under {}", root.display()))
 }
@@ -193,7 +303,8 @@ fn find_cards_svg_dir(root: &Path) -> Result<PathBuf> {
     // 1) Prefer a directory containing >= 50 SVGs (likely the card set root)
     // 2) Otherwise pick a directory that has any SVG file matching common patterns
     let mut best_dir: Option<(PathBuf, usize)> = None;
-    for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+    let walk = WalkDir::new(root).sort_by_file_name();
+    for entry in walk.into_iter().filter_map(|e| e.ok()) {
         if entry.file_type().is_dir() {
             let dir = entry.path();
             let mut svg_count = 0usize;
@@ -247,9 +358,10 @@ fn find_cards_svg_dir(root: &Path) -> Result<PathBuf> {
     ))
 }
 
-fn find_cards_png_dir(root: &Path) -> Result<PathBuf> {
+fn find_cards_raster_dir(root: &Path) -> Result<PathBuf> {
     let mut best_dir: Option<(PathBuf, usize)> = None;
-    for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+    let walk = WalkDir::new(root).sort_by_file_name();
+    for entry in walk.into_iter().filter_map(|e| e.ok()) {
         if entry.file_type().is_dir() {
             let dir = entry.path();
             let mut png_count = 0usize;
@@ -258,11 +370,7 @@ fn find_cards_png_dir(root: &Path) -> Result<PathBuf> {
                 for f in rd {
                     if let Ok(de) = f {
                         let p = de.path();
-                        if p.extension()
-                            .and_then(|e| e.to_str())
-                            .map(|e| e.eq_ignore_ascii_case("png"))
-                            == Some(true)
-                        {
+                        if raster_rank(&p).is_some() {
                             png_count += 1;
                             let stem = p
                                 .file_stem()
@@ -299,7 +407,7 @@ fn find_cards_png_dir(root: &Path) -> Result<PathBuf> {
         return Ok(p);
     }
     Err(anyhow!(
-        "Could not locate PNG card directory under {}",
+        "Could not locate raster card directory under {}",
         root.display()
     ))
 }
@@ -322,9 +430,13 @@ fn find_svg_for(svg_dir: &Path, rank: &str, suit: &str) -> Result<PathBuf> {
             return Ok(p);
         }
     }
-    // Fallback: scan for stems containing both rank and suit substrings
-    for entry in fs::read_dir(svg_dir)? {
-        let p = entry?.path();
+    // Fallback: scan for stems containing both rank and suit substrings, in name order so
+    // the same file wins whatever order the filesystem lists them in.
+    let mut entries = fs::read_dir(svg_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for p in entries {
         if p.extension()
             .and_then(|e| e.to_str())
             .map(|e| e.eq_ignore_ascii_case("svg"))
@@ -355,6 +467,102 @@ struct SheetMap {
     card_w: u32,
     card_h: u32,
     order: Vec<String>, // suits order per row
+    /// Row holding the card back in its first cell, below the four suit rows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    back_row: Option<u32>,
+}
+
+/// Where the optional card back cell comes from.
+enum BackDesign {
+    File(PathBuf),
+    Builtin,
+}
+
+/// Renders the back at cell size and drops it into the first cell of `row`.
+fn pack_back_row(
+    sheet: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
+    back: Option<&BackDesign>,
+    card_w: u32,
+    card_h: u32,
+    row: u32,
+) -> Result<Option<u32>> {
+    let Some(design) = back else {
+        return Ok(None);
+    };
+    let img = match design {
+        BackDesign::File(path) => {
+            let is_svg = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.eq_ignore_ascii_case("svg"))
+                == Some(true);
+            if is_svg {
+                const SVG_OVERSAMPLE: u32 = 8;
+                let pixmap = render_svg(path, card_w * SVG_OVERSAMPLE, card_h * SVG_OVERSAMPLE)
+                    .with_context(|| format!("rendering {}", path.display()))?;
+                downsample_pixmap(&pixmap, SVG_OVERSAMPLE)?
+            } else {
+                image::open(path)
+                    .with_context(|| format!("opening {}", path.display()))?
+                    .resize_exact(card_w, card_h, image::imageops::FilterType::CatmullRom)
+                    .to_rgba8()
+            }
+        }
+        BackDesign::Builtin => builtin_back(card_w, card_h),
+    };
+    image::imageops::replace(sheet, &img, 0, (row * card_h) as i64);
+    Ok(Some(row))
+}
+
+/// Raster version of the app's procedural classic blue back: a light rim, a dark
+/// blue panel and two red stripes, all with rounded corners.
+fn builtin_back(card_w: u32, card_h: u32) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let (w, h) = (card_w as f32, card_h as f32);
+    let unit = w / 120.0;
+    let radius = (w.min(h) / 6.0).max(8.0);
+    let outer = [0.0, 0.0, w, h];
+    let inner = inset(outer, 4.0 * unit);
+    let inner_radius = (radius - 4.0 * unit).max(4.0);
+    let pattern = inset(inner, 6.0 * unit);
+    let stripe_w = ((inner[2] - inner[0]) / 6.0).max(8.0);
+    let stripe_radius = (inner_radius - 4.0 * unit).max(3.0);
+    let stripes = [
+        [pattern[0], pattern[1], pattern[0] + stripe_w, pattern[3]],
+        [pattern[2] - stripe_w, pattern[1], pattern[2], pattern[3]],
+    ];
+
+    ImageBuffer::from_fn(card_w, card_h, |x, y| {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        if !in_round_rect(outer, radius, px, py) {
+            Rgba([0, 0, 0, 0])
+        } else if !in_round_rect(inset(outer, unit.max(1.0)), radius, px, py) {
+            Rgba([240, 240, 240, 255])
+        } else if !in_round_rect(inner, inner_radius, px, py) {
+            Rgba([30, 60, 150, 255])
+        } else if stripes
+            .iter()
+            .any(|&stripe| in_round_rect(stripe, stripe_radius, px, py))
+        {
+            Rgba([200, 48, 64, 255])
+        } else {
+            Rgba([12, 32, 104, 255])
+        }
+    })
+}
+
+fn inset(rect: [f32; 4], by: f32) -> [f32; 4] {
+    [rect[0] + by, rect[1] + by, rect[2] - by, rect[3] - by]
+}
+
+fn in_round_rect(rect: [f32; 4], radius: f32, x: f32, y: f32) -> bool {
+    let [left, top, right, bottom] = rect;
+    if x < left || x >= right || y < top || y >= bottom {
+        return false;
+    }
+    let radius = radius.min((right - left) / 2.0).min((bottom - top) / 2.0);
+    let cx = x.clamp(left + radius, right - radius);
+    let cy = y.clamp(top + radius, bottom - radius);
+    (x - cx).powi(2) + (y - cy).powi(2) <= radius * radius
 }
 
 fn downsample_pixmap(
@@ -451,17 +659,15 @@ fn rasterize_and_pack_svg(
     svg_dir: &Path,
     card_w: u32,
     card_h: u32,
+    back: Option<&BackDesign>,
     out_png: &Path,
 ) -> Result<SheetMap> {
     const SVG_OVERSAMPLE: u32 = 8;
-    // Order: spades, hearts, diamonds, clubs
-    let suits = ["spades", "hearts", "diamonds", "clubs"];
-    let ranks = [
-        "ace", "2", "3", "4", "5", "6", "7", "8", "9", "10", "jack", "queen", "king",
-    ];
+    let (suits, ranks) = (SUITS, RANKS);
 
+    let rows = suits.len() as u32 + u32::from(back.is_some());
     let sheet_w = card_w * ranks.len() as u32;
-    let sheet_h = card_h * suits.len() as u32;
+    let sheet_h = card_h * rows;
     let mut sheet: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(sheet_w, sheet_h);
 
     for (row, suit) in suits.iter().enumerate() {
@@ -484,34 +690,41 @@ fn rasterize_and_pack_svg(
             );
         }
     }
+    let back_row = pack_back_row(&mut sheet, back, card_w, card_h, suits.len() as u32)?;
 
     if let Some(parent) = out_png.parent() {
         fs::create_dir_all(parent)?;
     }
-    sheet.save(out_png)?;
+    // The sheet is always PNG, whatever the sources were or the output is named.
+    sheet.save_with_format(out_png, image::ImageFormat::Png)?;
 
     Ok(SheetMap {
         cols: ranks.len() as u32,
-        rows: suits.len() as u32,
+        rows,
         card_w,
         card_h,
         order: suits.iter().map(|s| s.to_string()).collect(),
+        back_row,
     })
 }
-fn pack_from_png(png_dir: &Path, card_w: u32, card_h: u32, out_png: &Path) -> Result<SheetMap> {
-    let suits = ["spades", "hearts", "diamonds", "clubs"];
-    let ranks = [
-        "ace", "2", "3", "4", "5", "6", "7", "8", "9", "10", "jack", "queen", "king",
-    ];
+fn pack_from_raster(
+    raster_dir: &Path,
+    card_w: u32,
+    card_h: u32,
+    back: Option<&BackDesign>,
+    out_png: &Path,
+) -> Result<SheetMap> {
+    let (suits, ranks) = (SUITS, RANKS);
+    let rows = suits.len() as u32 + u32::from(back.is_some());
     let sheet_w = card_w * ranks.len() as u32;
-    let sheet_h = card_h * suits.len() as u32;
+    let sheet_h = card_h * rows;
     let mut sheet: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(sheet_w, sheet_h);
 
     for (row, suit) in suits.iter().enumerate() {
         for (col, rank) in ranks.iter().enumerate() {
-            let path = find_png_for(png_dir, rank, suit)
+            let path = find_raster_for(raster_dir, rank, suit)
                 .with_context(|| format!("locating {} of {}", rank, suit))?;
-            let img_dyn = image::open(&path)?; // supports many PNG variants
+            let img_dyn = image::open(&path)?; // format comes from the extension
             let mut img = img_dyn
                 .resize_exact(card_w, card_h, image::imageops::FilterType::CatmullRom)
                 .to_rgba8();
@@ -526,21 +739,24 @@ fn pack_from_png(png_dir: &Path, card_w: u32, card_h: u32, out_png: &Path) -> Re
             );
         }
     }
+    let back_row = pack_back_row(&mut sheet, back, card_w, card_h, suits.len() as u32)?;
 
     if let Some(parent) = out_png.parent() {
         fs::create_dir_all(parent)?;
     }
-    sheet.save(out_png)?;
+    // The sheet is always PNG, whatever the sources were or the output is named.
+    sheet.save_with_format(out_png, image::ImageFormat::Png)?;
     Ok(SheetMap {
         cols: ranks.len() as u32,
-        rows: suits.len() as u32,
+        rows,
         card_w,
         card_h,
         order: suits.iter().map(|s| s.to_string()).collect(),
+        back_row,
     })
 }
 
-fn find_png_for(png_dir: &Path, rank: &str, suit: &str) -> Result<PathBuf> {
+fn find_raster_for(raster_dir: &Path, rank: &str, suit: &str) -> Result<PathBuf> {
     let rank_l = rank.to_lowercase();
     let suit_l = suit.to_lowercase();
     let suit_cap = capitalize(&suit_l);
@@ -560,38 +776,46 @@ fn find_png_for(png_dir: &Path, rank: &str, suit: &str) -> Result<PathBuf> {
         _ => "",
     };
 
-    let mut candidates = Vec::new();
+    let mut stems = Vec::new();
     // Common long forms
-    candidates.push(format!("{}_of_{}.png", rank_l, suit_l));
-    candidates.push(format!("{}-of-{}.png", rank_l, suit_l));
-    candidates.push(format!("{}_{}.png", rank_l, suit_l));
-    candidates.push(format!("{}-{}.png", rank_l, suit_l));
-    candidates.push(format!("{}_{}.png", suit_l, rank_l));
-    candidates.push(format!("{}-{}.png", suit_l, rank_l));
+    stems.push(format!("{}_of_{}", rank_l, suit_l));
+    stems.push(format!("{}-of-{}", rank_l, suit_l));
+    stems.push(format!("{}_{}", rank_l, suit_l));
+    stems.push(format!("{}-{}", rank_l, suit_l));
+    stems.push(format!("{}_{}", suit_l, rank_l));
+    stems.push(format!("{}-{}", suit_l, rank_l));
     // Shorthand like AS.png, TS.png (or 10S.png)
-    candidates.push(format!("{}{}.png", rank_letter, suit_letter));
-    candidates.push(format!("{}{}.png", ten_letter, suit_letter));
+    stems.push(format!("{}{}", rank_letter, suit_letter));
+    stems.push(format!("{}{}", ten_letter, suit_letter));
     // Kenney styles
-    candidates.push(format!("card{}{}.png", suit_cap, rank_letter));
-    candidates.push(format!("{}{}_card.png", rank_letter, suit_cap));
-    candidates.push(format!("{}{}_card.png", suit_cap, rank_letter));
-
-    for c in &candidates {
-        let p = png_dir.join(c);
+    stems.push(format!("card{}{}", suit_cap, rank_letter));
+    stems.push(format!("{}{}_card", rank_letter, suit_cap));
+    stems.push(format!("{}{}_card", suit_cap, rank_letter));
+
+    // Each naming style tries every format before the next style, so a set that mixes
+    // formats still resolves the same way on every machine.
+    let candidates = stems.iter().flat_map(|stem| {
+        RASTER_EXTENSIONS
+            .iter()
+            .map(move |ext| format!("{}.{}", stem, ext))
+    });
+    for c in candidates {
+        let p = raster_dir.join(&c);
         if p.exists() {
             return Ok(p);
         }
         // Try case variations
-        let lower = png_dir.join(c.to_lowercase());
+        let lower = raster_dir.join(c.to_lowercase());
         if lower.exists() {
             return Ok(lower);
         }
-        let upper = png_dir.join(c.to_uppercase());
+        let upper = raster_dir.join(c.to_uppercase());
         if upper.exists() {
             return Ok(upper);
         }
     }
-    // Fallback: recursive scan under png_dir for any file containing both suit and rank tokens
+    // Fallback: recursive scan under raster_dir for any file containing both suit and rank
+    // tokens. Walk in name order and prefer formats by rank so ties break deterministically.
     let suit_singular = suit_l.trim_end_matches('s');
     let mut suit_terms = vec![suit_l.as_str()];
     if suit_singular != suit_l {
@@ -610,14 +834,12 @@ fn find_png_for(png_dir: &Path, rank: &str, suit: &str) -> Result<PathBuf> {
         _ => {}
     }
 
-    for entry in WalkDir::new(png_dir).into_iter().filter_map(|e| e.ok()) {
+    let mut best: Option<(usize, PathBuf)> = None;
+    let walk = WalkDir::new(raster_dir).sort_by_file_name();
+    for entry in walk.into_iter().filter_map(|e| e.ok()) {
         if entry.file_type().is_file() {
             let p = entry.path();
-            if p.extension()
-                .and_then(|e| e.to_str())
-                .map(|e| e.eq_ignore_ascii_case("png"))
-                == Some(true)
-            {
+            if let Some(preference) = raster_rank(p) {
                 let stem = p
                     .file_stem()
                     .and_then(|s| s.to_str())
@@ -629,18 +851,23 @@ fn find_png_for(png_dir: &Path, rank: &str, suit: &str) -> Result<PathBuf> {
                 let has_rank = rank_terms
                     .iter()
                     .any(|term| !term.is_empty() && stem.contains(term));
-                if has_suit && has_rank {
-                    return Ok(p.to_path_buf());
+                let better = best
+                    .as_ref()
+                    .is_none_or(|(best_pref, _)| preference < *best_pref);
+                if has_suit && has_rank && better {
+                    best = Some((preference, p.to_path_buf()));
                 }
             }
         }
     }
-    Err(anyhow!(
-        "missing PNG for {} of {} in {}",
-        rank,
-        suit,
-        png_dir.display()
-    ))
+    best.map(|(_, path)| path).ok_or_else(|| {
+        anyhow!(
+            "missing card image for {} of {} in {}",
+            rank,
+            suit,
+            raster_dir.display()
+        )
+    })
 }
 
 fn capitalize(s: &str) -> String {
@@ -651,10 +878,28 @@ fn capitalize(s: &str) -> String {
     }
 }
 
+/// Every rendering knob spelled out rather than left to library defaults, so regenerating the
+/// sheet gives byte-identical output. No system fonts are loaded either, so machines with
+/// different fonts installed still agree.
+fn pinned_svg_options() -> usvg::Options<'static> {
+    usvg::Options {
+        resources_dir: None,
+        dpi: 96.0,
+        font_family: "Times New Roman".to_owned(),
+        font_size: 12.0,
+        languages: vec!["en".to_owned()],
+        shape_rendering: usvg::ShapeRendering::GeometricPrecision,
+        text_rendering: usvg::TextRendering::OptimizeLegibility,
+        image_rendering: usvg::ImageRendering::OptimizeQuality,
+        default_size: usvg::Size::from_wh(100.0, 100.0).expect("valid default size"),
+        ..usvg::Options::default()
+    }
+}
+
 fn render_svg(path: &Path, w: u32, h: u32) -> Result<Pixmap> {
     let mut data = Vec::new();
     File::open(path)?.read_to_end(&mut data)?;
-    let opt = usvg::Options::default();
+    let opt = pinned_svg_options();
     let tree = usvg::Tree::from_data(&data, &opt).map_err(|e| anyhow!("usvg parse: {:?}", e))?;
 
     // Fit to requested size while preserving aspect